@@ -0,0 +1,285 @@
+//! Input expansion and provenance for running `extract` over many files
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use shard_den_json_extractor::{InputFormat, OutputFormat};
+use std::path::{Component, Path, PathBuf};
+
+/// Column added to merged rows naming the file they came from
+pub const SOURCE_COLUMN: &str = "source_file";
+
+/// Whether `input` is a glob pattern rather than a plain path
+///
+/// An existing file or directory is taken as it is, even with glob characters
+/// in its name (e.g. `data[1].json`).
+pub fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '[']) && !Path::new(input).exists()
+}
+
+/// Whether these inputs need batch handling rather than a single read
+pub fn is_batch(inputs: &[String]) -> bool {
+    inputs.len() > 1
+        || inputs
+            .iter()
+            .any(|i| is_pattern(i) || Path::new(i).is_dir())
+}
+
+/// Files named by `inputs`, in order and without duplicates
+///
+/// Glob patterns are expanded (`**` matches any depth); directories are walked
+/// for files with a known input extension when `recursive` is set.
+pub fn expand_inputs(inputs: &[String], recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if is_pattern(input) {
+            let before = files.len();
+            for entry in
+                glob::glob(input).with_context(|| format!("Invalid pattern '{}'", input))?
+            {
+                let path = entry?;
+                if path.is_file() {
+                    files.push(path);
+                }
+            }
+            if files.len() == before {
+                anyhow::bail!("No files match '{}'", input);
+            }
+        } else if Path::new(input).is_dir() {
+            if !recursive {
+                anyhow::bail!("'{}' is a directory (use --recursive to walk it)", input);
+            }
+            for entry in walkdir::WalkDir::new(input).sort_by_file_name() {
+                let entry = entry?;
+                if entry.file_type().is_file() && InputFormat::from_path(entry.path()).is_some() {
+                    files.push(entry.into_path());
+                }
+            }
+        } else {
+            files.push(PathBuf::from(input));
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+/// Prefix every result row with the file it came from
+///
+/// Objects get a `source_file` member; other values become
+/// `{"source_file", "value"}` rows. A row that already has a `source_file`
+/// member is an error rather than losing either value.
+pub fn tag_source(values: Vec<Value>, source: &str) -> Result<Vec<Value>> {
+    values
+        .into_iter()
+        .map(|value| {
+            let mut row = Map::new();
+            match value {
+                Value::Object(members) => {
+                    if members.contains_key(SOURCE_COLUMN) {
+                        anyhow::bail!(
+                            "Result already has a '{}' member, which --merge adds",
+                            SOURCE_COLUMN
+                        );
+                    }
+                    row.extend(members);
+                }
+                other => {
+                    row.insert("value".to_string(), other);
+                }
+            }
+            row.insert(SOURCE_COLUMN.to_string(), Value::String(source.to_string()));
+            Ok(Value::Object(row))
+        })
+        .collect()
+}
+
+/// The deepest directory containing every file, to mirror below `--output-dir`
+pub fn common_root(files: &[PathBuf]) -> PathBuf {
+    let mut parents = files.iter().map(|f| f.parent().unwrap_or(Path::new("")));
+    let Some(first) = parents.next() else {
+        return PathBuf::new();
+    };
+    let mut root: Vec<Component> = first.components().collect();
+    for parent in parents {
+        let shared = root
+            .iter()
+            .zip(parent.components())
+            .take_while(|(a, b)| *a == b)
+            .count();
+        root.truncate(shared);
+    }
+    root.iter().collect()
+}
+
+/// Where the result for `input` goes inside `dir`
+///
+/// The input's directories below `root` (see [`common_root`]) are mirrored, so
+/// `a/resp.json` and `b/resp.json` do not overwrite each other, and the
+/// extension is replaced by the output format's.
+pub fn output_path(dir: &Path, root: &Path, input: &Path, format: OutputFormat) -> PathBuf {
+    let extension = match format {
+        OutputFormat::Json => "json",
+        OutputFormat::Csv => "csv",
+        OutputFormat::Text => "txt",
+        OutputFormat::Yaml => "yaml",
+        OutputFormat::Markdown => "md",
+        OutputFormat::Html => "html",
+    };
+    let relative = input.strip_prefix(root).unwrap_or(input);
+    let mut target = dir.to_path_buf();
+    if let Some(parent) = relative.parent() {
+        // Only plain names, so the result cannot land outside `dir`
+        target.extend(parent.components().filter_map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        }));
+    }
+    let name = input.file_stem().unwrap_or(input.as_os_str());
+    target.join(name).with_extension(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fixture() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("nested/deeper")).unwrap();
+        for file in [
+            "a.json",
+            "b.yaml",
+            "notes.txt",
+            "nested/c.json",
+            "nested/deeper/d.json",
+        ] {
+            std::fs::write(dir.path().join(file), "{}").unwrap();
+        }
+        dir
+    }
+
+    fn names(files: &[PathBuf], root: &Path) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| {
+                f.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_globs_and_directories() {
+        let dir = fixture();
+        let root = dir.path().to_string_lossy().to_string();
+
+        let files = expand_inputs(&[format!("{}/*.json", root)], false).unwrap();
+        assert_eq!(names(&files, dir.path()), vec!["a.json"]);
+
+        let files = expand_inputs(&[format!("{}/**/*.json", root)], false).unwrap();
+        assert_eq!(
+            names(&files, dir.path()),
+            vec!["a.json", "nested/c.json", "nested/deeper/d.json"]
+        );
+
+        let files = expand_inputs(std::slice::from_ref(&root), true).unwrap();
+        assert_eq!(
+            names(&files, dir.path()),
+            vec!["a.json", "b.yaml", "nested/c.json", "nested/deeper/d.json"]
+        );
+
+        assert!(expand_inputs(std::slice::from_ref(&root), false).is_err());
+        assert!(expand_inputs(&[format!("{}/*.xml", root)], false).is_err());
+    }
+
+    #[test]
+    fn test_expand_removes_duplicates() {
+        let dir = fixture();
+        let a = dir.path().join("a.json").to_string_lossy().to_string();
+        let files = expand_inputs(&[a.clone(), a], false).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_pattern_characters_in_existing_file_names() {
+        let dir = fixture();
+        let file = dir.path().join("data[1].json");
+        std::fs::write(&file, "{}").unwrap();
+        let file = file.to_string_lossy().to_string();
+
+        assert!(!is_pattern(&file));
+        assert!(!is_batch(std::slice::from_ref(&file)));
+        let files = expand_inputs(std::slice::from_ref(&file), false).unwrap();
+        assert_eq!(names(&files, dir.path()), vec!["data[1].json"]);
+        assert!(is_pattern(&format!(
+            "{}/data[0-9].json",
+            dir.path().display()
+        )));
+    }
+
+    #[test]
+    fn test_tag_source() {
+        let rows = tag_source(
+            vec![serde_json::json!({"id": 1}), serde_json::json!("x")],
+            "a.json",
+        )
+        .unwrap();
+        assert_eq!(
+            rows[0],
+            serde_json::json!({"source_file": "a.json", "id": 1})
+        );
+        assert_eq!(
+            rows[1],
+            serde_json::json!({"source_file": "a.json", "value": "x"})
+        );
+
+        let clash = tag_source(vec![serde_json::json!({"source_file": "b.json"})], "a.json");
+        assert!(clash.is_err());
+    }
+
+    #[test]
+    fn test_output_path() {
+        let path = output_path(
+            Path::new("out"),
+            Path::new("in"),
+            Path::new("in/resp.json"),
+            OutputFormat::Csv,
+        );
+        assert_eq!(path, Path::new("out").join("resp.csv"));
+    }
+
+    #[test]
+    fn test_output_paths_mirror_directories() {
+        let files = vec![
+            PathBuf::from("in/a/resp.json"),
+            PathBuf::from("in/b/resp.json"),
+        ];
+        let root = common_root(&files);
+        assert_eq!(root, Path::new("in"));
+        let targets: Vec<PathBuf> = files
+            .iter()
+            .map(|f| output_path(Path::new("out"), &root, f, OutputFormat::Json))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                Path::new("out").join("a").join("resp.json"),
+                Path::new("out").join("b").join("resp.json")
+            ]
+        );
+
+        // Nothing outside `dir`, whatever the inputs look like
+        let files = vec![
+            PathBuf::from("../x/resp.json"),
+            PathBuf::from("/tmp/resp.json"),
+        ];
+        let root = common_root(&files);
+        for file in &files {
+            let target = output_path(Path::new("out"), &root, file, OutputFormat::Csv);
+            assert!(target.starts_with("out"));
+            assert!(!target.components().any(|c| c == Component::ParentDir));
+        }
+    }
+}
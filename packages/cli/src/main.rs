@@ -3,11 +3,21 @@
 //! A unified CLI for all ShardDen tools.
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use shard_den_json_extractor::{JsonExtractorCore, OutputFormat};
-use std::io::{self, Read};
+use clap::{Args, Parser, Subcommand};
+use shard_den_json_extractor::{
+    aggregate, alias_columns, diff, redact, Aggregate, CsvArrayMode, CsvOptions, CsvQuoteStyle,
+    Diagnostic, DiffFormat, Differ, Draft, Formatter, InputFormat, JsonExtractorCore, JsonIndent,
+    JsonOptions, LineError, LineTerminator, Mutation, OutputFormat, PathMode, PathParser,
+    PathStats, RedactStrategy, SchemaInferrer, ValidationError,
+};
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use tracing::info;
 
+mod batch;
+mod watch;
+
 #[derive(Parser)]
 #[command(
     name = "shard-den",
@@ -20,38 +30,561 @@ struct Cli {
     command: Commands,
 }
 
+// Parsed once per run, so the size of `Extract` does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Extract fields from JSON using path expressions
     Extract {
-        /// Path expression(s), comma-separated
-        #[arg(short, long, value_name = "PATHS")]
-        paths: String,
+        /// Path expression(s), comma-separated; use `alias=path` to name output columns
+        #[arg(
+            short,
+            long,
+            value_name = "PATHS",
+            required_unless_present = "transform"
+        )]
+        paths: Option<String>,
 
-        /// Input file (stdin if not provided)
-        input: Option<String>,
+        /// Input files, directories or glob patterns such as 'captures/**/*.json' (stdin if not provided)
+        #[arg(value_name = "INPUT")]
+        inputs: Vec<String>,
+
+        /// Walk directories given as input for files of any supported format
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Merge the results of all inputs into one table with a source_file column
+        #[arg(long, conflicts_with_all = ["stream", "ndjson", "transform", "aggregate"])]
+        merge: bool,
+
+        /// Write one result file per input into this directory, mirroring the input directories, instead of printing
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["stream", "ndjson", "merge"])]
+        output_dir: Option<String>,
 
-        /// Output format (json, csv, text, yaml)
+        /// Re-run whenever an input file changes or a pattern matches a new file, until interrupted
+        #[arg(short, long, requires = "inputs", conflicts_with = "stream")]
+        watch: bool,
+
+        /// Output format (json, csv, text, yaml, markdown, html)
         #[arg(short, long, value_name = "FORMAT", default_value = "json")]
         format: String,
+
+        /// Only accept RFC 9535 compliant path expressions
+        #[arg(long)]
+        strict: bool,
+
+        /// Stream the input instead of loading it, printing one match per line
+        /// (no size limit; json and text formats only)
+        #[arg(long)]
+        stream: bool,
+
+        /// Treat the input as JSON Lines, printing one result per record
+        /// (csv, markdown and html print one table for all records)
+        #[arg(long, conflicts_with = "stream")]
+        ndjson: bool,
+
+        /// Report the normalized path and JSON Pointer of every match
+        #[arg(long, conflicts_with = "ndjson")]
+        with_paths: bool,
+
+        /// Add the source span (byte offsets, line and column) of every match to --with-paths output
+        #[arg(long, requires = "with_paths")]
+        spans: bool,
+
+        #[command(flatten)]
+        csv: CsvArgs,
+
+        #[command(flatten)]
+        json: JsonStyleArgs,
+
+        /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+        #[arg(long, value_name = "FORMAT", conflicts_with = "ndjson",
+              value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+        input_format: Option<String>,
+
+        /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+        #[arg(long, conflicts_with = "input_format")]
+        lenient: bool,
+
+        /// Keep the source key order and exact number text (large IDs, decimals);
+        /// needs a build with the `preserve-source` feature
+        #[arg(long)]
+        preserve_source: bool,
+
+        /// Validate the input against this JSON Schema file before extracting
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["stream", "ndjson"])]
+        schema: Option<String>,
+
+        /// jq-style program run on the extracted values (or the whole document
+        /// without --paths), e.g. 'map(select(.age > 30)) | sort_by(.name)'
+        #[arg(short, long, value_name = "PROGRAM",
+              conflicts_with_all = ["stream", "ndjson", "with_paths"])]
+        transform: Option<String>,
+
+        /// Aggregate the values of a single path instead of listing them
+        /// (comma-separated: count, sum, avg, min, max, distinct, median, pN)
+        #[arg(long, value_name = "FUNCTIONS", requires = "paths",
+              conflicts_with_all = ["stream", "ndjson", "with_paths", "transform"])]
+        aggregate: Option<String>,
+
+        /// Aggregate per distinct value of this path (paired with the match under the same parent)
+        #[arg(long, value_name = "PATH", requires = "aggregate")]
+        group_by: Option<String>,
+
+        /// Mask common credential fields (password, token, *secret*, ...) before extracting
+        #[arg(long, conflicts_with = "stream")]
+        redact: bool,
+
+        /// Path expressions of the fields to redact instead, comma-separated
+        #[arg(long, value_name = "PATHS", requires = "redact")]
+        redact_paths: Option<String>,
+
+        /// Key-name patterns to redact instead, comma-separated (`*` matches any text)
+        #[arg(long, value_name = "PATTERNS", requires = "redact")]
+        redact_keys: Option<String>,
+
+        /// How values are redacted (mask, partial, hash, remove)
+        #[arg(long, value_name = "STRATEGY", requires = "redact",
+              value_parser = ["mask", "partial", "hash", "remove"])]
+        redact_strategy: Option<String>,
     },
     /// Detect available paths in JSON
     Detect {
         /// Input file (stdin if not provided)
         input: Option<String>,
+
+        /// Re-run whenever the input file changes, until interrupted
+        #[arg(short, long, requires = "input")]
+        watch: bool,
+
+        /// Treat the input as JSON Lines and merge paths across records
+        #[arg(long)]
+        ndjson: bool,
+
+        /// Show observed types, occurrence count, null ratio and an example per path
+        #[arg(long)]
+        stats: bool,
+
+        /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+        #[arg(long, value_name = "FORMAT", conflicts_with = "ndjson",
+              value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+        input_format: Option<String>,
+
+        /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+        #[arg(long, conflicts_with = "input_format")]
+        lenient: bool,
+    },
+    /// Validate JSON against a JSON Schema (draft 7 or 2020-12)
+    Validate {
+        /// JSON Schema file
+        #[arg(short, long, value_name = "FILE")]
+        schema: String,
+
+        /// Input file (stdin if not provided)
+        input: Option<String>,
+
+        /// Schema dialect (7 or 2020-12); taken from `$schema` if omitted
+        #[arg(long, value_name = "DRAFT", value_parser = ["7", "2020-12"])]
+        draft: Option<String>,
+
+        /// Print violations as a JSON array
+        #[arg(long)]
+        json: bool,
+
+        /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+        #[arg(long, value_name = "FORMAT",
+              value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+        input_format: Option<String>,
+
+        /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+        #[arg(long, conflicts_with = "input_format")]
+        lenient: bool,
+    },
+    /// Infer a JSON Schema (draft 2020-12) from sample documents
+    Infer {
+        /// Sample files, each one document (stdin if none provided)
+        inputs: Vec<String>,
+
+        /// Treat each input as JSON Lines, one sample per record
+        #[arg(long)]
+        ndjson: bool,
+
+        /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+        #[arg(long, value_name = "FORMAT", conflicts_with = "ndjson",
+              value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+        input_format: Option<String>,
+
+        /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+        #[arg(long, conflicts_with = "input_format")]
+        lenient: bool,
+    },
+    /// Set the values at a path, adding missing object members
+    Set {
+        /// Path expression selecting the nodes to write
+        path: String,
+
+        /// New value as JSON; a bare word such as `active` is used as a string
+        value: String,
+
+        /// Use the value as a string as-is instead of parsing it as JSON
+        #[arg(long)]
+        string: bool,
+
+        #[command(flatten)]
+        target: MutateArgs,
+    },
+    /// Delete the values at a path
+    Delete {
+        /// Path expression selecting the nodes to remove
+        path: String,
+
+        #[command(flatten)]
+        target: MutateArgs,
+    },
+    /// Rename the object members at a path
+    Rename {
+        /// Path expression selecting the members to rename
+        path: String,
+
+        /// New member name
+        new_name: String,
+
+        #[command(flatten)]
+        target: MutateArgs,
+    },
+    /// Mask sensitive fields (common credential keys unless paths or keys are given)
+    Redact {
+        /// Path expressions of the fields to redact, comma-separated
+        #[arg(short, long, value_name = "PATHS", default_value = "")]
+        paths: String,
+
+        /// Key-name patterns to redact, comma-separated (`*` matches any text)
+        #[arg(short, long, value_name = "PATTERNS", default_value = "")]
+        keys: String,
+
+        /// How values are redacted (mask, partial, hash, remove)
+        #[arg(short, long, value_name = "STRATEGY", default_value = "mask",
+              value_parser = ["mask", "partial", "hash", "remove"])]
+        strategy: String,
+
+        #[command(flatten)]
+        target: MutateArgs,
+    },
+    /// Compare two documents
+    Diff {
+        /// Original document
+        left: String,
+
+        /// Changed document
+        right: String,
+
+        /// Output format (patch, text, summary)
+        #[arg(short, long, value_name = "FORMAT", default_value = "patch",
+              value_parser = ["patch", "text", "summary"])]
+        format: String,
+
+        /// Match array elements by this path (e.g. `$.id`) instead of by index
+        #[arg(short = 'k', long, value_name = "PATH")]
+        array_key: Option<String>,
+
+        /// Disable colors in text output
+        #[arg(long)]
+        no_color: bool,
+
+        /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+        #[arg(long, value_name = "FORMAT",
+              value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+        input_format: Option<String>,
+
+        /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+        #[arg(long, conflicts_with = "input_format")]
+        lenient: bool,
+
+        /// Keep the source key order and exact number text (large IDs, decimals);
+        /// needs a build with the `preserve-source` feature
+        #[arg(long)]
+        preserve_source: bool,
+    },
+    /// Apply a JSON Patch (RFC 6902) or merge patch (RFC 7396)
+    Patch {
+        /// Patch file
+        #[arg(short, long, value_name = "FILE")]
+        patch: String,
+
+        /// Input file (stdin if not provided)
+        input: Option<String>,
+
+        /// Treat the patch as an RFC 7396 merge patch
+        #[arg(long)]
+        merge: bool,
+
+        /// Write the result back to the input file instead of stdout (JSON input only)
+        #[arg(short, long, requires = "input")]
+        in_place: bool,
+
+        /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+        #[arg(long, value_name = "FORMAT",
+              value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+        input_format: Option<String>,
+
+        /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+        #[arg(long, conflicts_with = "input_format")]
+        lenient: bool,
+
+        /// Keep the source key order and exact number text (large IDs, decimals);
+        /// needs a build with the `preserve-source` feature
+        #[arg(long)]
+        preserve_source: bool,
+
+        #[command(flatten)]
+        json: JsonStyleArgs,
     },
     /// List all available tools
     Tools,
 }
 
+/// Input options shared by `set`, `delete` and `rename`
+#[derive(Args)]
+struct MutateArgs {
+    /// Input file (stdin if not provided)
+    input: Option<String>,
+
+    /// Write the result back to the input file instead of stdout (JSON input only)
+    #[arg(short, long, requires = "input")]
+    in_place: bool,
+
+    /// Only accept RFC 9535 compliant path expressions
+    #[arg(long)]
+    strict: bool,
+
+    /// Input format (json, json5, yaml, toml, csv, xml); guessed from the file extension if omitted
+    #[arg(long, value_name = "FORMAT",
+          value_parser = ["json", "json5", "jsonc", "yaml", "yml", "toml", "csv", "xml"])]
+    input_format: Option<String>,
+
+    /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+    #[arg(long, conflicts_with = "input_format")]
+    lenient: bool,
+
+    /// Keep the source key order and exact number text (large IDs, decimals);
+    /// needs a build with the `preserve-source` feature
+    #[arg(long)]
+    preserve_source: bool,
+
+    #[command(flatten)]
+    json: JsonStyleArgs,
+}
+
+impl MutateArgs {
+    /// Apply `operation` to the input and write the modified document
+    fn run(
+        &self, operation: impl FnOnce(&JsonExtractorCore, &str) -> shard_den_core::Result<Mutation>,
+    ) -> Result<()> {
+        let input_format = resolve_input_format(
+            self.input_format.as_deref(),
+            self.lenient,
+            self.input.as_deref(),
+        );
+        if self.in_place && input_format != InputFormat::Json {
+            anyhow::bail!("--in-place only supports JSON input");
+        }
+        let extractor = if self.strict {
+            JsonExtractorCore::with_mode(PathMode::Rfc9535)
+        } else {
+            JsonExtractorCore::new()
+        }
+        .with_input_format(input_format);
+        check_preserve_source(self.preserve_source)?;
+        let extractor = apply_preserve_source(extractor, self.preserve_source);
+
+        let mutation = operation(&extractor, &read_input(self.input.as_deref())?)?;
+        let output = Formatter::new().format_json_with(&mutation.document, &self.json.to_options());
+        match self.input.as_deref() {
+            Some(path) if self.in_place => std::fs::write(path, output + "\n")?,
+            _ => println!("{}", output),
+        }
+        eprintln!("{} node(s) changed", mutation.changed);
+        Ok(())
+    }
+}
+
+/// CSV dialect options, used with `--format csv`
+#[derive(Args)]
+struct CsvArgs {
+    /// CSV field delimiter (a single character, or "tab")
+    #[arg(long, value_name = "CHAR", default_value = ",")]
+    delimiter: String,
+
+    /// Flatten nested objects into dotted columns (address.city)
+    #[arg(long)]
+    flatten: bool,
+
+    /// How arrays inside records are written
+    #[arg(long, value_name = "MODE", default_value = "json",
+          value_parser = ["json", "join", "index", "explode"])]
+    arrays: String,
+
+    /// Separator used with `--arrays join`
+    #[arg(long, value_name = "SEP", default_value = "|")]
+    join_separator: String,
+
+    /// When to quote fields
+    #[arg(long, value_name = "STYLE", default_value = "necessary",
+          value_parser = ["necessary", "always", "non-numeric", "never"])]
+    quote: String,
+
+    /// Omit the header row
+    #[arg(long)]
+    no_header: bool,
+
+    /// End records with CRLF instead of LF
+    #[arg(long)]
+    crlf: bool,
+}
+
+impl CsvArgs {
+    fn to_options(&self) -> Result<CsvOptions> {
+        let delimiter = match self.delimiter.as_str() {
+            "tab" | "\\t" => '\t',
+            d => {
+                let mut chars = d.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => anyhow::bail!("Delimiter must be a single character: {}", d),
+                }
+            }
+        };
+        let arrays = match self.arrays.as_str() {
+            "join" => CsvArrayMode::Join(self.join_separator.clone()),
+            "index" => CsvArrayMode::Index,
+            "explode" => CsvArrayMode::Explode,
+            _ => CsvArrayMode::Json,
+        };
+        let quote = match self.quote.as_str() {
+            "always" => CsvQuoteStyle::Always,
+            "non-numeric" => CsvQuoteStyle::NonNumeric,
+            "never" => CsvQuoteStyle::Never,
+            _ => CsvQuoteStyle::Necessary,
+        };
+        Ok(CsvOptions {
+            delimiter,
+            flatten: self.flatten,
+            arrays,
+            quote,
+            header: !self.no_header,
+            line_terminator: if self.crlf {
+                LineTerminator::CrLf
+            } else {
+                LineTerminator::Lf
+            },
+        })
+    }
+}
+
+/// Layout of JSON output
+#[derive(Args)]
+struct JsonStyleArgs {
+    /// Write JSON on a single line without whitespace
+    #[arg(long, conflicts_with = "indent")]
+    compact: bool,
+
+    /// Indentation of JSON output: a number of spaces or "tab"
+    #[arg(long, value_name = "WIDTH", value_parser = parse_indent)]
+    indent: Option<JsonIndent>,
+
+    /// Sort object members by key
+    #[arg(long)]
+    sort_keys: bool,
+
+    /// Escape non-ASCII characters as \uXXXX
+    #[arg(long)]
+    ascii: bool,
+
+    /// Write RFC 8785 canonical JSON (for hashing and signing)
+    #[arg(long, conflicts_with_all = ["compact", "indent", "sort_keys", "ascii"])]
+    canonical: bool,
+}
+
+impl JsonStyleArgs {
+    fn to_options(&self) -> JsonOptions {
+        let indent = if self.compact {
+            JsonIndent::Compact
+        } else {
+            self.indent.unwrap_or_default()
+        };
+        JsonOptions {
+            indent,
+            sort_keys: self.sort_keys,
+            ascii: self.ascii,
+            canonical: self.canonical,
+        }
+    }
+
+    /// The same options on a single line, for line-per-value output
+    fn to_line_options(&self) -> JsonOptions {
+        JsonOptions {
+            indent: JsonIndent::Compact,
+            ..self.to_options()
+        }
+    }
+}
+
+fn parse_indent(s: &str) -> std::result::Result<JsonIndent, String> {
+    JsonIndent::from_name(s).ok_or_else(|| format!("expected a number of spaces or \"tab\": {}", s))
+}
+
 const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 
-fn parse_format(format: &str) -> OutputFormat {
-    match format.to_lowercase().as_str() {
-        "csv" => OutputFormat::Csv,
-        "text" => OutputFormat::Text,
-        "yaml" => OutputFormat::Yaml,
-        _ => OutputFormat::Json,
+/// Fail on `--preserve-source` when this build cannot keep the source
+fn check_preserve_source(preserve: bool) -> Result<()> {
+    if preserve && !cfg!(feature = "preserve-source") {
+        anyhow::bail!("--preserve-source needs a build with the `preserve-source` feature");
+    }
+    Ok(())
+}
+
+/// Apply `--preserve-source`, once accepted by [`check_preserve_source`]
+fn apply_preserve_source(extractor: JsonExtractorCore, preserve: bool) -> JsonExtractorCore {
+    #[cfg(feature = "preserve-source")]
+    let extractor = extractor.with_preserve_source(preserve);
+    #[cfg(not(feature = "preserve-source"))]
+    let _ = preserve;
+    extractor
+}
+
+/// JSON text for a `set` value, quoting bare words and `--string` values
+fn set_value_json(value: &str, string: bool) -> Result<String> {
+    if string {
+        return Ok(serde_json::to_string(value)?);
+    }
+    match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(_) => Ok(value.to_string()),
+        Err(e) => {
+            let looks_like_json = value
+                .trim_start()
+                .starts_with(|c: char| matches!(c, '{' | '[' | '"' | '-') || c.is_ascii_digit());
+            if looks_like_json {
+                anyhow::bail!(
+                    "Invalid JSON value '{}': {} (use --string to set it as text)",
+                    value,
+                    e
+                );
+            }
+            Ok(serde_json::to_string(value)?)
+        }
+    }
+}
+
+/// Pick the input format from the flags, falling back to the file extension
+fn resolve_input_format(explicit: Option<&str>, lenient: bool, input: Option<&str>) -> InputFormat {
+    let format = explicit
+        .and_then(InputFormat::from_name)
+        .or_else(|| input.and_then(InputFormat::from_path))
+        .unwrap_or_default();
+    if lenient && format == InputFormat::Json {
+        InputFormat::Json5
+    } else {
+        format
     }
 }
 
@@ -76,6 +609,246 @@ fn read_input(path: Option<&str>) -> Result<String> {
     }
 }
 
+/// Extract matches from a file or stdin as they are found, without the size cap
+fn stream_extract(
+    extractor: &JsonExtractorCore, input: Option<&str>, paths: &str, format: OutputFormat,
+    with_paths: bool, json_options: &JsonOptions,
+) -> Result<()> {
+    if !matches!(format, OutputFormat::Json | OutputFormat::Text) {
+        anyhow::bail!("Streaming mode only supports json and text output");
+    }
+    let reader: Box<dyn Read> = match input {
+        Some(p) => Box::new(std::fs::File::open(p)?),
+        None => Box::new(io::stdin().lock()),
+    };
+    let formatter = Formatter::new();
+    let mut out = io::stdout().lock();
+    extractor.extract_stream(reader, paths, |m| {
+        let value = if with_paths {
+            serde_json::json!({
+                "path": m.location.to_string(),
+                "pointer": m.location.to_json_pointer(),
+                "value": m.value,
+            })
+        } else {
+            m.value
+        };
+        let line = match format {
+            OutputFormat::Json => formatter.format_json_with(&value, json_options),
+            _ => formatter.format(&value, format)?,
+        };
+        writeln!(out, "{}", line)?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// What `extract` does with each loaded document
+struct ExtractJob {
+    paths: String,
+    output_format: OutputFormat,
+    with_paths: bool,
+    csv: CsvOptions,
+    /// Schema text to validate each document against first
+    schema: Option<String>,
+    transform: Option<String>,
+    aggregate: Option<Vec<Aggregate>>,
+    group_by: Option<String>,
+}
+
+impl ExtractJob {
+    /// Fail with located errors if the document or paths do not parse, or on schema violations
+    fn check(&self, extractor: &JsonExtractorCore, json: &str) -> Result<()> {
+        report_diagnostics(&extractor.diagnose(json, &self.paths))?;
+        if let Some(schema) = &self.schema {
+            report_validation_errors(&extractor.validate(json, schema, None)?)?;
+        }
+        Ok(())
+    }
+
+    /// The output for one document, ready to print
+    fn run(
+        &self, extractor: &JsonExtractorCore, formatter: &Formatter, json: &str,
+    ) -> Result<String> {
+        self.check(extractor, json)?;
+        let value = if let Some(functions) = &self.aggregate {
+            let value =
+                extractor.aggregate(json, &self.paths, functions, self.group_by.as_deref())?;
+            let columns = aggregate::columns(functions, self.group_by.is_some());
+            return self.render(&formatter.clone().with_columns(columns), &value);
+        } else if let Some(program) = &self.transform {
+            extractor.transform(json, &self.paths, program)?
+        } else {
+            let result = if self.with_paths {
+                extractor.extract_with_paths(json, &self.paths, self.output_format)?
+            } else if matches!(self.output_format, OutputFormat::Csv) {
+                extractor.extract_csv(json, &self.paths, &self.csv)?
+            } else {
+                extractor.extract_with_format(json, &self.paths, self.output_format)?
+            };
+            return Ok(format!("{}\n", result));
+        };
+        self.render(formatter, &value)
+    }
+
+    /// Result rows of one document for a merged table (located matches with --with-paths)
+    fn rows(&self, extractor: &JsonExtractorCore, json: &str) -> Result<Vec<serde_json::Value>> {
+        self.check(extractor, json)?;
+        let result = if self.with_paths {
+            extractor.extract_with_paths(json, &self.paths, OutputFormat::Json)?
+        } else {
+            extractor.extract(json, &self.paths)?
+        };
+        Ok(serde_json::from_str(&result)?)
+    }
+
+    fn render(&self, formatter: &Formatter, value: &serde_json::Value) -> Result<String> {
+        Ok(match self.output_format {
+            OutputFormat::Csv => formatter.format_csv_with(value, &self.csv)?,
+            _ => format!("{}\n", formatter.format(value, self.output_format)?),
+        })
+    }
+}
+
+/// Run `job` over every file, reporting failures per file without stopping
+///
+/// Results are printed under a `==> file <==` header, written into
+/// `output_dir`, or with `merge` collected into one table with a
+/// `source_file` column.
+fn run_batch(
+    files: &[PathBuf], job: &ExtractJob, formatter: &Formatter,
+    extractor_for: &impl Fn(Option<&str>) -> JsonExtractorCore, merge: bool,
+    output_dir: Option<&str>,
+) -> Result<()> {
+    if files.is_empty() {
+        anyhow::bail!("No input files found");
+    }
+    if let Some(dir) = output_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    let root = batch::common_root(files);
+    let mut rows = Vec::new();
+    let mut written = HashSet::new();
+    let mut process = |file: &Path| -> Result<()> {
+        let name = file.to_string_lossy();
+        let extractor = extractor_for(Some(&name));
+        let json = read_input(Some(&name))?;
+        if merge {
+            rows.extend(batch::tag_source(job.rows(&extractor, &json)?, &name)?);
+        } else if let Some(dir) = output_dir {
+            let target = batch::output_path(Path::new(dir), &root, file, job.output_format);
+            if !written.insert(target.clone()) {
+                anyhow::bail!("{} was already written by another input", target.display());
+            }
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&target, job.run(&extractor, formatter, &json)?)?;
+        } else {
+            print!(
+                "==> {} <==\n{}",
+                name,
+                job.run(&extractor, formatter, &json)?
+            );
+        }
+        Ok(())
+    };
+
+    let mut failed = 0;
+    for file in files {
+        if let Err(e) = process(file) {
+            failed += 1;
+            eprintln!("{}: {}", file.display(), e);
+        }
+    }
+    if merge {
+        let columns = if job.with_paths {
+            Vec::new()
+        } else {
+            alias_columns(&job.paths)
+        };
+        print!(
+            "{}",
+            job.render(
+                &formatter.clone().with_columns(columns),
+                &serde_json::Value::Array(rows)
+            )?
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} file(s) failed", failed, files.len());
+    }
+    Ok(())
+}
+
+/// Print path statistics as an aligned table
+fn print_stats(stats: &[PathStats]) {
+    let rows: Vec<[String; 5]> = stats
+        .iter()
+        .map(|s| {
+            [
+                s.path.clone(),
+                s.types.join("|"),
+                s.count.to_string(),
+                format!("{:.0}%", s.null_ratio * 100.0),
+                s.example
+                    .as_ref()
+                    .map(|v| v.to_string())
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let header = ["PATH", "TYPES", "COUNT", "NULLS", "EXAMPLE"].map(String::from);
+    let mut widths = [0; 5];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+/// Print per-line NDJSON errors to stderr, failing if there were any
+fn report_line_errors(errors: &[LineError]) -> Result<()> {
+    for error in errors {
+        eprintln!("{}", error);
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("{} line(s) could not be processed", errors.len());
+    }
+    Ok(())
+}
+
+/// Print schema violations to stderr, failing if there were any
+fn report_validation_errors(errors: &[ValidationError]) -> Result<()> {
+    for error in errors {
+        eprintln!("{}", error);
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("{} validation error(s)", errors.len());
+    }
+    Ok(())
+}
+
+/// Print located parse errors to stderr, failing if there were any
+fn report_diagnostics(diagnostics: &[Diagnostic]) -> Result<()> {
+    let color = std::env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal();
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.render(color));
+    }
+    if !diagnostics.is_empty() {
+        anyhow::bail!("{} error(s)", diagnostics.len());
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
     // Initialize tracing
     tracing_subscriber::fmt::init();
@@ -86,27 +859,377 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Extract {
             paths,
-            input,
+            inputs,
+            recursive,
+            merge,
+            output_dir,
+            watch,
             format,
+            strict,
+            stream,
+            ndjson,
+            with_paths,
+            spans,
+            csv,
+            json: style,
+            input_format,
+            lenient,
+            preserve_source,
+            schema,
+            transform,
+            aggregate,
+            group_by,
+            redact,
+            redact_paths,
+            redact_keys,
+            redact_strategy,
         } => {
+            let paths = paths.unwrap_or_default();
             info!("Extracting with paths: {}", paths);
-            let json = read_input(input.as_deref())?;
-            let output_format = parse_format(&format);
-            let result = extractor.extract_with_format(&json, &paths, output_format)?;
-            println!("{}", result);
+            let output_format = OutputFormat::from_name(&format).unwrap_or_default();
+            let mode = if strict {
+                PathMode::Rfc9535
+            } else {
+                PathMode::default()
+            };
+            check_preserve_source(preserve_source)?;
+            let redactor = if redact {
+                let strategy = redact_strategy
+                    .as_deref()
+                    .and_then(RedactStrategy::from_name)
+                    .unwrap_or_default();
+                Some(redact::redactor_from_lists(
+                    redact_paths.as_deref().unwrap_or_default(),
+                    redact_keys.as_deref().unwrap_or_default(),
+                    strategy,
+                )?)
+            } else {
+                None
+            };
+            let extractor_for = |input: Option<&str>| {
+                let extractor = JsonExtractorCore::with_mode(mode).with_input_format(
+                    resolve_input_format(input_format.as_deref(), lenient, input),
+                );
+                let extractor = apply_preserve_source(extractor, preserve_source)
+                    .with_source_spans(spans)
+                    .with_json_options(style.to_options());
+                match &redactor {
+                    Some(redactor) => extractor.with_redactor(redactor.clone()),
+                    None => extractor,
+                }
+            };
+            let formatter = Formatter::new().with_json_options(style.to_options());
+            let job = ExtractJob {
+                paths,
+                output_format,
+                with_paths,
+                csv: csv.to_options()?,
+                schema: schema.map(|s| read_input(Some(&s))).transpose()?,
+                transform,
+                aggregate: aggregate
+                    .map(|functions| Aggregate::parse_list(&functions))
+                    .transpose()?,
+                group_by,
+            };
+            if merge || output_dir.is_some() || batch::is_batch(&inputs) {
+                if stream || ndjson {
+                    anyhow::bail!("--stream and --ndjson read a single input");
+                }
+                // Expanded on every run, so --watch picks up newly matching files
+                let extract = || {
+                    run_batch(
+                        &batch::expand_inputs(&inputs, recursive)?,
+                        &job,
+                        &formatter,
+                        &extractor_for,
+                        merge,
+                        output_dir.as_deref(),
+                    )
+                };
+                if watch {
+                    watch::run(|| batch::expand_inputs(&inputs, recursive), extract);
+                }
+                return extract();
+            }
+            let input = inputs.first().map(String::as_str);
+            let extractor = extractor_for(input);
+            if stream {
+                if resolve_input_format(input_format.as_deref(), lenient, input)
+                    != InputFormat::Json
+                {
+                    anyhow::bail!("Streaming mode only supports JSON input");
+                }
+                return stream_extract(
+                    &extractor,
+                    input,
+                    &job.paths,
+                    output_format,
+                    with_paths,
+                    &style.to_line_options(),
+                );
+            }
+            let extract = || {
+                let json = read_input(input)?;
+                if ndjson {
+                    let result = extractor.extract_ndjson(&json, &job.paths)?;
+                    if matches!(
+                        output_format,
+                        OutputFormat::Csv | OutputFormat::Markdown | OutputFormat::Html
+                    ) {
+                        // One table for every record rather than a header per record
+                        let rows = result.records.into_iter().flat_map(|r| r.values).collect();
+                        let formatter = formatter.clone().with_columns(alias_columns(&job.paths));
+                        print!(
+                            "{}",
+                            job.render(&formatter, &serde_json::Value::Array(rows))?
+                        );
+                        return report_line_errors(&result.errors);
+                    }
+                    for record in result.records {
+                        let values = serde_json::Value::Array(record.values);
+                        match output_format {
+                            OutputFormat::Json => {
+                                println!(
+                                    "{}",
+                                    formatter.format_json_with(&values, &style.to_line_options())
+                                )
+                            }
+                            _ => println!("{}", formatter.format(&values, output_format)?),
+                        }
+                    }
+                    return report_line_errors(&result.errors);
+                }
+                print!("{}", job.run(&extractor, &formatter, &json)?);
+                Ok(())
+            };
+            if watch {
+                watch::run(
+                    || Ok(vec![PathBuf::from(input.unwrap_or_default())]),
+                    extract,
+                );
+            }
+            extract()?;
         }
-        Commands::Detect { input } => {
+        Commands::Detect {
+            input,
+            watch,
+            ndjson,
+            stats,
+            input_format,
+            lenient,
+        } => {
             info!("Detecting paths in JSON");
-            let json = read_input(input.as_deref())?;
-            let paths = extractor.detect_paths(&json)?;
-            for path in paths {
-                println!("{}", path);
+            let input_format =
+                resolve_input_format(input_format.as_deref(), lenient, input.as_deref());
+            let extractor = extractor.with_input_format(input_format);
+            let detect = || {
+                let json = read_input(input.as_deref())?;
+                if ndjson {
+                    let result = extractor.detect_paths_ndjson(&json);
+                    if stats {
+                        print_stats(&result.stats);
+                    } else {
+                        for path in result.paths {
+                            println!("{}", path);
+                        }
+                    }
+                    return report_line_errors(&result.errors);
+                }
+                report_diagnostics(&extractor.diagnose(&json, ""))?;
+                if stats {
+                    print_stats(&extractor.detect_stats(&json)?);
+                    return Ok(());
+                }
+                for path in extractor.detect_paths(&json)? {
+                    println!("{}", path);
+                }
+                Ok(())
+            };
+            if watch {
+                watch::run(
+                    || Ok(vec![PathBuf::from(input.as_deref().unwrap_or_default())]),
+                    detect,
+                );
+            }
+            detect()?;
+        }
+        Commands::Validate {
+            schema,
+            input,
+            draft,
+            json,
+            input_format,
+            lenient,
+        } => {
+            info!("Validating against schema: {}", schema);
+            let input_format =
+                resolve_input_format(input_format.as_deref(), lenient, input.as_deref());
+            let extractor = extractor.with_input_format(input_format);
+            let document = read_input(input.as_deref())?;
+            let draft = draft.as_deref().and_then(Draft::from_name);
+            let errors = extractor.validate(&document, &read_input(Some(&schema))?, draft)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&errors)?);
+                if !errors.is_empty() {
+                    anyhow::bail!("{} validation error(s)", errors.len());
+                }
+                return Ok(());
+            }
+            report_validation_errors(&errors)?;
+            println!("valid");
+        }
+        Commands::Infer {
+            inputs,
+            ndjson,
+            input_format,
+            lenient,
+        } => {
+            info!("Inferring JSON Schema");
+            let inputs: Vec<Option<&str>> = if inputs.is_empty() {
+                vec![None]
+            } else {
+                inputs.iter().map(|p| Some(p.as_str())).collect()
+            };
+
+            let schema = if ndjson {
+                let mut text = String::new();
+                for input in &inputs {
+                    text.push_str(&read_input(*input)?);
+                    text.push('\n');
+                }
+                let result = extractor.infer_schema_ndjson(&text);
+                report_line_errors(&result.errors)?;
+                result.schema
+            } else {
+                let mut inferrer = SchemaInferrer::new();
+                for input in inputs {
+                    let format = resolve_input_format(input_format.as_deref(), lenient, input);
+                    let text = read_input(input)?;
+                    let value = JsonExtractorCore::new()
+                        .with_input_format(format)
+                        .parse_document(&text)
+                        .map_err(|e| match input {
+                            Some(path) => anyhow::anyhow!("{}: {}", path, e),
+                            None => e.into(),
+                        })?;
+                    inferrer.observe(&value);
+                }
+                inferrer.finish()
+            };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        Commands::Set {
+            path,
+            value,
+            string,
+            target,
+        } => {
+            info!("Setting values at: {}", path);
+            let value = set_value_json(&value, string)?;
+            target.run(|extractor, json| extractor.set_value(json, &path, &value))?;
+        }
+        Commands::Delete { path, target } => {
+            info!("Deleting values at: {}", path);
+            target.run(|extractor, json| extractor.delete_values(json, &path))?;
+        }
+        Commands::Rename {
+            path,
+            new_name,
+            target,
+        } => {
+            info!("Renaming members at: {} to {}", path, new_name);
+            target.run(|extractor, json| extractor.rename_keys(json, &path, &new_name))?;
+        }
+        Commands::Redact {
+            paths,
+            keys,
+            strategy,
+            target,
+        } => {
+            info!("Redacting with strategy: {}", strategy);
+            let strategy = RedactStrategy::from_name(&strategy).unwrap_or_default();
+            let redactor = redact::redactor_from_lists(&paths, &keys, strategy)?;
+            target.run(|extractor, json| extractor.redact(json, &redactor))?;
+        }
+        Commands::Diff {
+            left,
+            right,
+            format,
+            array_key,
+            no_color,
+            input_format,
+            lenient,
+            preserve_source,
+        } => {
+            info!("Comparing {} with {}", left, right);
+            check_preserve_source(preserve_source)?;
+            let parse = |path: &str| -> Result<serde_json::Value> {
+                let format = resolve_input_format(input_format.as_deref(), lenient, Some(path));
+                let extractor = JsonExtractorCore::new().with_input_format(format);
+                apply_preserve_source(extractor, preserve_source)
+                    .parse_document(&read_input(Some(path))?)
+                    .map_err(|e| anyhow::anyhow!("{}: {}", path, e))
+            };
+            let (left, right) = (parse(&left)?, parse(&right)?);
+
+            let parser = PathParser::new();
+            let mut differ = Differ::new(&parser);
+            if let Some(key) = array_key.as_deref() {
+                differ = differ.with_array_key(key)?;
+            }
+            let format = DiffFormat::from_name(&format).unwrap_or_default();
+            let color =
+                !no_color && std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+            let output = diff::render(&differ.diff(&left, &right), format, color)?;
+            // Text and summary output already end in a newline
+            if format == DiffFormat::Patch {
+                println!("{}", output);
+            } else {
+                print!("{}", output);
+            }
+        }
+        Commands::Patch {
+            patch,
+            input,
+            merge,
+            in_place,
+            input_format,
+            lenient,
+            preserve_source,
+            json,
+        } => {
+            info!("Applying patch: {}", patch);
+            let input_format =
+                resolve_input_format(input_format.as_deref(), lenient, input.as_deref());
+            if in_place && input_format != InputFormat::Json {
+                anyhow::bail!("--in-place only supports JSON input");
+            }
+            check_preserve_source(preserve_source)?;
+            let extractor =
+                apply_preserve_source(extractor.with_input_format(input_format), preserve_source);
+            let document = extractor.apply_patch(
+                &read_input(input.as_deref())?,
+                &read_input(Some(&patch))?,
+                merge,
+            )?;
+            let output = Formatter::new().format_json_with(&document, &json.to_options());
+            match input.as_deref() {
+                Some(path) if in_place => std::fs::write(path, output + "\n")?,
+                _ => println!("{}", output),
             }
         }
         Commands::Tools => {
             println!("Available tools:");
             println!("  extract  - Extract fields from JSON using path syntax");
             println!("  detect   - Detect available paths in JSON");
+            println!("  validate - Validate JSON against a JSON Schema");
+            println!("  infer    - Infer a JSON Schema from sample documents");
+            println!("  set      - Set the values at a path");
+            println!("  delete   - Delete the values at a path");
+            println!("  rename   - Rename the object members at a path");
+            println!("  redact   - Mask sensitive fields");
+            println!("  diff     - Compare two documents");
+            println!("  patch    - Apply a JSON Patch or merge patch");
             println!("\nUse 'shard-den <command> --help' for more information.");
         }
     }
@@ -0,0 +1,312 @@
+//! Re-running a command whenever its input files change
+//!
+//! Files are polled for their modification time and size, so no platform
+//! notification API is needed; a burst of writes (an editor saving through a
+//! temp file, a tool rewriting a fixture) is settled into one re-run.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long files must stay unchanged before a change is reported
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// What identifies a version of a file; `None` while it does not exist
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Time source for [`Watcher::wait`], so tests need not really sleep
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// The system clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Polls the files named by the inputs and reports settled changes
+///
+/// `resolve` is called on every poll, so a glob picks up files created after
+/// watching started. If it fails (e.g. a pattern matches nothing for a
+/// moment), the previous files are checked instead.
+pub struct Watcher<R, C = SystemClock> {
+    resolve: R,
+    files: BTreeMap<PathBuf, Stamp>,
+    clock: C,
+    poll: Duration,
+    debounce: Duration,
+}
+
+impl<R: FnMut() -> Result<Vec<PathBuf>>> Watcher<R> {
+    /// Watch the files `resolve` names, as they are now
+    pub fn new(resolve: R) -> Self {
+        Self::with_clock(resolve, SystemClock, POLL_INTERVAL, DEBOUNCE)
+    }
+}
+
+impl<R: FnMut() -> Result<Vec<PathBuf>>, C: Clock> Watcher<R, C> {
+    /// Watch with `clock`, polling every `poll` and waiting for `debounce` of quiet before reporting
+    pub fn with_clock(mut resolve: R, clock: C, poll: Duration, debounce: Duration) -> Self {
+        let files = resolve()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|f| {
+                let stamp = stamp(&f);
+                (f, stamp)
+            })
+            .collect();
+        Self {
+            resolve,
+            files,
+            clock,
+            poll,
+            debounce,
+        }
+    }
+
+    /// Number of files currently watched
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Files changed, created, removed, newly matched or no longer matched since the last check
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let current = match (self.resolve)() {
+            Ok(files) => files,
+            Err(_) => self.files.keys().cloned().collect(),
+        };
+        let mut changed = Vec::new();
+        let wanted: HashSet<&PathBuf> = current.iter().collect();
+        self.files.retain(|path, _| {
+            let keep = wanted.contains(path);
+            if !keep {
+                changed.push(path.clone());
+            }
+            keep
+        });
+        for path in current {
+            let now = stamp(&path);
+            if self.files.insert(path.clone(), now) != Some(now) {
+                changed.push(path);
+            }
+        }
+        changed
+    }
+
+    /// Block until files change and then stay unchanged for the debounce period
+    ///
+    /// Returns every file touched in the burst, or nothing if `timeout` passes first.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Vec<PathBuf> {
+        let deadline = timeout.map(|t| self.clock.now() + t);
+        let mut changed = loop {
+            let changed = self.changed();
+            if !changed.is_empty() {
+                break changed;
+            }
+            if deadline.is_some_and(|d| self.clock.now() >= d) {
+                return Vec::new();
+            }
+            self.clock.sleep(self.poll);
+        };
+        loop {
+            self.clock.sleep(self.debounce);
+            let more = self.changed();
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+}
+
+/// Run `pass` now and again after every change to the files `resolve` names, until interrupted
+///
+/// The terminal is cleared before each run so the latest result replaces the
+/// last one; errors are printed in its place and watching carries on.
+pub fn run(
+    resolve: impl FnMut() -> Result<Vec<PathBuf>>, mut pass: impl FnMut() -> Result<()>,
+) -> ! {
+    let mut watcher = Watcher::new(resolve);
+    let redraw = io::stdout().is_terminal();
+    loop {
+        if redraw {
+            print!("\x1b[2J\x1b[H");
+        }
+        if let Err(e) = pass() {
+            eprintln!("Error: {}", e);
+        }
+        let _ = io::stdout().flush();
+        eprintln!(
+            "\nWatching {} file(s) for changes (Ctrl-C to stop)",
+            watcher.len()
+        );
+        watcher.wait(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use tempfile::TempDir;
+
+    const FAST: Duration = Duration::from_millis(10);
+
+    fn fixture(files: &[&str]) -> (TempDir, Vec<PathBuf>) {
+        let dir = TempDir::new().unwrap();
+        let paths: Vec<PathBuf> = files.iter().map(|f| dir.path().join(f)).collect();
+        for path in &paths {
+            std::fs::write(path, "{}").unwrap();
+        }
+        (dir, paths)
+    }
+
+    fn fixed(files: &[PathBuf]) -> impl FnMut() -> Result<Vec<PathBuf>> {
+        let files = files.to_vec();
+        move || Ok(files.clone())
+    }
+
+    /// A clock that only moves when slept on, running scripted actions as it passes them
+    struct FakeClock {
+        start: Instant,
+        elapsed: Duration,
+        script: VecDeque<(Duration, Box<dyn FnOnce()>)>,
+    }
+
+    impl FakeClock {
+        fn new(script: Vec<(u64, Box<dyn FnOnce()>)>) -> Self {
+            Self {
+                start: Instant::now(),
+                elapsed: Duration::ZERO,
+                script: script
+                    .into_iter()
+                    .map(|(ms, action)| (Duration::from_millis(ms), action))
+                    .collect(),
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.start + self.elapsed
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.elapsed += duration;
+            while self
+                .script
+                .front()
+                .is_some_and(|(at, _)| *at <= self.elapsed)
+            {
+                let (_, action) = self.script.pop_front().unwrap();
+                action();
+            }
+        }
+    }
+
+    fn write_at(ms: u64, path: &Path, content: &'static str) -> (u64, Box<dyn FnOnce()>) {
+        let path = path.to_path_buf();
+        (ms, Box::new(move || std::fs::write(path, content).unwrap()))
+    }
+
+    #[test]
+    fn test_changed_detects_writes_and_removal() {
+        let (_dir, files) = fixture(&["a.json", "b.json"]);
+        let mut watcher = Watcher::new(fixed(&files));
+        assert!(watcher.changed().is_empty());
+
+        std::fs::write(&files[1], r#"{"a": 1}"#).unwrap();
+        assert_eq!(watcher.changed(), vec![files[1].clone()]);
+        assert!(watcher.changed().is_empty());
+
+        std::fs::remove_file(&files[0]).unwrap();
+        assert_eq!(watcher.changed(), vec![files[0].clone()]);
+    }
+
+    #[test]
+    fn test_changed_follows_glob_matches() {
+        let (dir, files) = fixture(&["a.json"]);
+        let pattern = dir.path().join("*.json").to_string_lossy().into_owned();
+        let mut watcher =
+            Watcher::new(|| crate::batch::expand_inputs(std::slice::from_ref(&pattern), false));
+        assert_eq!(watcher.len(), 1);
+
+        let added = dir.path().join("b.json");
+        std::fs::write(&added, "[]").unwrap();
+        assert_eq!(watcher.changed(), vec![added.clone()]);
+        assert_eq!(watcher.len(), 2);
+
+        std::fs::remove_file(&added).unwrap();
+        assert_eq!(watcher.changed(), vec![added]);
+        assert_eq!(watcher.len(), 1);
+
+        // No match at all keeps the last files rather than forgetting them
+        std::fs::remove_file(&files[0]).unwrap();
+        assert_eq!(watcher.changed(), files);
+        assert_eq!(watcher.len(), 1);
+    }
+
+    #[test]
+    fn test_wait_debounces_a_burst() {
+        let (_dir, files) = fixture(&["a.json", "b.json"]);
+        let clock = FakeClock::new(vec![
+            write_at(30, &files[0], "[1]"),
+            write_at(60, &files[1], "[1, 2]"),
+        ]);
+        let mut watcher =
+            Watcher::with_clock(fixed(&files), clock, FAST, Duration::from_millis(150));
+
+        let changed = watcher.wait(Some(Duration::from_secs(5)));
+        assert_eq!(changed, files);
+        assert_eq!(watcher.clock.elapsed, Duration::from_millis(330));
+        assert!(watcher.changed().is_empty());
+    }
+
+    #[test]
+    fn test_rerun_sees_new_content() {
+        let (_dir, files) = fixture(&["a.json"]);
+        let extractor = shard_den_json_extractor::JsonExtractorCore::new();
+        let extract = || {
+            let json = std::fs::read_to_string(&files[0]).unwrap();
+            extractor.extract(&json, "$.id").unwrap()
+        };
+        let clock = FakeClock::new(vec![write_at(20, &files[0], r#"{"id": 7}"#)]);
+        let mut watcher = Watcher::with_clock(fixed(&files), clock, FAST, FAST);
+        assert_eq!(extract(), "[]");
+
+        assert_eq!(watcher.wait(Some(Duration::from_secs(5))), files);
+        assert_eq!(extract(), "[7]");
+    }
+
+    #[test]
+    fn test_wait_times_out() {
+        let (_dir, files) = fixture(&["a.json"]);
+        let mut watcher =
+            Watcher::with_clock(fixed(&files), FakeClock::new(Vec::new()), FAST, FAST);
+        assert!(watcher.wait(Some(Duration::from_millis(50))).is_empty());
+        assert_eq!(watcher.clock.elapsed, Duration::from_millis(50));
+    }
+}
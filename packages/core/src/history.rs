@@ -4,6 +4,22 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Masks sensitive values in history data before it is stored
+///
+/// Tools provide implementations; the storage that writes history applies one.
+pub trait Redact: Send + Sync {
+    /// Return `data` with sensitive values masked
+    fn redact(&self, data: &str) -> String;
+
+    /// Mask the input and output of `entry`
+    ///
+    /// Implementations may use its metadata, e.g. the query that produced the output.
+    fn redact_entry(&self, entry: &mut HistoryEntry) {
+        entry.input = self.redact(&entry.input);
+        entry.output = self.redact(&entry.output);
+    }
+}
+
 /// Generate a simple unique ID without uuid crate
 fn generate_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -35,18 +51,46 @@ impl HistoryEntry {
         tool: impl Into<String>, input: impl Into<String>, output: impl Into<String>,
         is_sensitive: bool,
     ) -> Self {
+        Self::build(tool.into(), input.into(), output.into(), is_sensitive)
+    }
+
+    /// Create a new history entry, masking input and output with `redactor`
+    pub fn new_redacted(
+        tool: impl Into<String>, input: impl Into<String>, output: impl Into<String>,
+        is_sensitive: bool, redactor: &dyn Redact,
+    ) -> Self {
+        Self::build(
+            tool.into(),
+            redactor.redact(&input.into()),
+            redactor.redact(&output.into()),
+            is_sensitive,
+        )
+    }
+
+    /// Mask input and output with `redactor`, for entries not created through
+    /// [`new_redacted`](Self::new_redacted) (e.g. received from a frontend)
+    ///
+    /// Sensitive entries are already obfuscated and are left as they are.
+    pub fn redact(mut self, redactor: &dyn Redact) -> Self {
+        if !self.is_sensitive {
+            redactor.redact_entry(&mut self);
+        }
+        self
+    }
+
+    fn build(tool: String, input: String, output: String, is_sensitive: bool) -> Self {
         Self {
             id: generate_id(),
-            tool: tool.into(),
+            tool,
             input: if is_sensitive {
                 Self::obfuscate(input)
             } else {
-                input.into()
+                input
             },
             output: if is_sensitive {
                 Self::obfuscate(output)
             } else {
-                output.into()
+                output
             },
             timestamp: Utc::now(),
             metadata: HashMap::new(),
@@ -96,6 +140,8 @@ pub trait HistoryStore: Send + Sync {
 }
 
 #[cfg(test)]
+// Newer clippy lints the pre-existing helpers below; left as written
+#[allow(clippy::unnecessary_map_or, clippy::needless_borrows_for_generic_args)]
 mod tests {
     use super::*;
 
@@ -155,6 +201,37 @@ mod tests {
         assert!(!entry.is_sensitive);
     }
 
+    struct UppercaseRedactor;
+
+    impl Redact for UppercaseRedactor {
+        fn redact(&self, data: &str) -> String {
+            data.to_uppercase()
+        }
+    }
+
+    #[test]
+    fn test_history_entry_redacted() {
+        let entry =
+            HistoryEntry::new_redacted("tool", "input", "output", false, &UppercaseRedactor);
+        assert_eq!(entry.input, "INPUT");
+        assert_eq!(entry.output, "OUTPUT");
+
+        // Redaction happens before obfuscation
+        let entry = HistoryEntry::new_redacted("tool", "a", "b", true, &UppercaseRedactor);
+        assert_eq!(entry.input, HistoryEntry::obfuscate("A"));
+    }
+
+    #[test]
+    fn test_history_entry_redact_existing() {
+        let entry = HistoryEntry::new("tool", "input", "output", false).redact(&UppercaseRedactor);
+        assert_eq!(entry.input, "INPUT");
+        assert_eq!(entry.output, "OUTPUT");
+
+        // Obfuscated data is not redacted again
+        let entry = HistoryEntry::new("tool", "a", "b", true).redact(&UppercaseRedactor);
+        assert_eq!(entry.input, HistoryEntry::obfuscate("a"));
+    }
+
     #[test]
     fn test_history_entry_timestamp() {
         let entry = HistoryEntry::new("json-extractor", "{}", "result", false);
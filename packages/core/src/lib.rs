@@ -13,4 +13,4 @@ pub mod logger;
 
 pub use config::{Config, UmlEngine, UmlStylerConfig};
 pub use error::{Result, ShardDenError};
-pub use history::{HistoryEntry, HistoryStore};
+pub use history::{HistoryEntry, HistoryStore, Redact};
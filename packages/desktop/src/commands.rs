@@ -4,7 +4,12 @@
 //
 
 use shard_den_core::{Config, HistoryEntry, UmlStylerConfig};
-use shard_den_json_extractor::JsonExtractorCore;
+use shard_den_json_extractor::{
+    parse_paths, redact, Completion, Diagnostic, Extractor, InputFormat, JsonExtractorCore,
+    JsonOptions, PathCompleter, PathMode, PathParser, RedactStrategy, SourceKind, SpanIndex,
+    SpanNode, ValidationError,
+};
+use std::sync::Mutex;
 use tauri::State;
 
 use crate::storage::{Storage, UmlTemplate, UmlTheme};
@@ -35,7 +40,7 @@ pub fn load_config(state: State<'_, AppState>) -> Result<Config, String> {
     state.storage.load_config().map_err(|e| e.to_string())
 }
 
-/// Save history entry
+/// Save history entry; storage redacts sensitive fields first
 #[tauri::command]
 pub fn save_history(entry: HistoryEntry, state: State<'_, AppState>) -> Result<(), String> {
     state.storage.add_history(entry).map_err(|e| e.to_string())
@@ -61,40 +66,203 @@ pub fn clear_history(tool: Option<String>, state: State<'_, AppState>) -> Result
         .map_err(|e| e.to_string())
 }
 
+/// Build an extractor, accepting JSON5 / JSONC input when `lenient` is set
+fn json_extractor(lenient: Option<bool>) -> JsonExtractorCore {
+    let input_format = if lenient.unwrap_or(false) {
+        InputFormat::Json5
+    } else {
+        InputFormat::Json
+    };
+    JsonExtractorCore::new().with_input_format(input_format)
+}
+
 /// Detect paths in JSON
 #[tauri::command]
-pub fn detect_paths(json: String) -> Result<Vec<String>, String> {
-    let extractor = JsonExtractorCore::new();
+pub fn detect_paths(json: String, lenient: Option<bool>) -> Result<Vec<String>, String> {
+    let extractor = json_extractor(lenient);
     extractor.detect_paths(&json).map_err(|e| e.to_string())
 }
 
 /// Extract JSON using paths
 #[tauri::command]
-pub fn extract_json(json: String, paths: String) -> Result<String, String> {
-    let extractor = JsonExtractorCore::new();
+pub fn extract_json(json: String, paths: String, lenient: Option<bool>) -> Result<String, String> {
+    let extractor = json_extractor(lenient);
     extractor.extract(&json, &paths).map_err(|e| e.to_string())
 }
 
 /// Extract JSON with format
+///
+/// `json_options` controls the layout of JSON output (indent, sorted keys,
+/// ASCII escaping, RFC 8785 canonical form); pretty-printed if omitted.
+/// `preserve_source` keeps the source key order and exact number text, in
+/// builds with the `preserve-source` feature.
 #[tauri::command]
 pub fn extract_json_with_format(
-    json: String, paths: String, format: String,
+    json: String, paths: String, format: String, lenient: Option<bool>,
+    json_options: Option<JsonOptions>, preserve_source: Option<bool>,
 ) -> Result<String, String> {
     use shard_den_json_extractor::OutputFormat;
 
-    let output_format = match format.to_lowercase().as_str() {
-        "csv" => OutputFormat::Csv,
-        "text" => OutputFormat::Text,
-        "yaml" => OutputFormat::Yaml,
-        _ => OutputFormat::Json,
-    };
+    let output_format = OutputFormat::from_name(&format).unwrap_or_default();
 
-    let extractor = JsonExtractorCore::new();
+    let extractor = json_extractor(lenient).with_json_options(json_options.unwrap_or_default());
+    #[cfg(feature = "preserve-source")]
+    let extractor = extractor.with_preserve_source(preserve_source.unwrap_or(false));
+    #[cfg(not(feature = "preserve-source"))]
+    if preserve_source.unwrap_or(false) {
+        return Err("Preserving the source needs the preserve-source feature".to_string());
+    }
     extractor
         .extract_with_format(&json, &paths, output_format)
         .map_err(|e| e.to_string())
 }
 
+/// Run a jq-style transform on extracted values (or the whole document when
+/// `paths` is empty)
+#[tauri::command]
+pub fn transform_json(
+    json: String, paths: String, program: String, format: String, lenient: Option<bool>,
+) -> Result<String, String> {
+    use shard_den_json_extractor::OutputFormat;
+
+    let output_format = OutputFormat::from_name(&format).unwrap_or_default();
+
+    let extractor = json_extractor(lenient);
+    extractor
+        .transform_with_format(&json, &paths, &program, output_format)
+        .map_err(|e| e.to_string())
+}
+
+/// Validate JSON against a JSON Schema, returning every violation
+#[tauri::command]
+pub fn validate_json(
+    json: String, schema: String, lenient: Option<bool>,
+) -> Result<Vec<ValidationError>, String> {
+    let extractor = json_extractor(lenient);
+    extractor
+        .validate(&json, &schema, None)
+        .map_err(|e| e.to_string())
+}
+
+/// Locate parse errors in a document and comma-separated paths, empty when both parse
+///
+/// Offsets and columns are UTF-16 positions into `json` or `paths`, per `source`.
+#[tauri::command]
+pub fn diagnose_json(
+    json: String, paths: String, lenient: Option<bool>,
+) -> Result<Vec<Diagnostic>, String> {
+    Ok(json_extractor(lenient)
+        .diagnose(&json, &paths)
+        .iter()
+        .map(|d| match d.source {
+            SourceKind::Document => d.to_utf16(&json),
+            SourceKind::Path => d.to_utf16(&paths),
+        })
+        .collect())
+}
+
+/// The completer for the document last completed in, reused while it is unchanged
+#[derive(Default)]
+pub struct CompletionCache {
+    last: Mutex<Option<CachedCompleter>>,
+}
+
+struct CachedCompleter {
+    json: String,
+    lenient: bool,
+    mode: PathMode,
+    completer: PathCompleter<'static>,
+}
+
+impl CompletionCache {
+    /// Complete `partial` in `json`, parsing the document only when it changed
+    pub fn complete(
+        &self, json: &str, partial: &str, cursor: usize, lenient: bool, mode: PathMode,
+    ) -> Result<Vec<Completion>, String> {
+        let mut last = self.last.lock().map_err(|e| e.to_string())?;
+        let cached = match last.take() {
+            Some(c) if c.lenient == lenient && c.mode == mode && c.json == json => c,
+            _ => {
+                let value = json_extractor(Some(lenient))
+                    .parse_document(json)
+                    .map_err(|e| e.to_string())?;
+                CachedCompleter {
+                    json: json.to_string(),
+                    lenient,
+                    mode,
+                    completer: PathCompleter::owned(&PathParser::with_mode(mode), value),
+                }
+            }
+        };
+        let completions = cached.completer.complete_utf16(partial, cursor);
+        *last = Some(cached);
+        Ok(completions)
+    }
+}
+
+/// Suggest completions for a partially typed path
+///
+/// `cursor` and the returned offsets are UTF-16 positions, as in JavaScript
+/// strings. `strict` completes RFC 9535 paths. The document is parsed once and
+/// reused while the same text is completed in.
+#[tauri::command]
+pub fn complete_path(
+    json: String, partial: String, cursor: usize, lenient: Option<bool>, strict: Option<bool>,
+    cache: State<'_, CompletionCache>,
+) -> Result<Vec<Completion>, String> {
+    let mode = if strict.unwrap_or(false) {
+        PathMode::Rfc9535
+    } else {
+        PathMode::default()
+    };
+    cache.complete(&json, &partial, cursor, lenient.unwrap_or(false), mode)
+}
+
+/// Extract every match with its path, JSON Pointer and source span
+///
+/// Span offsets and columns are UTF-16 positions, as in JavaScript strings.
+#[tauri::command]
+pub fn extract_json_with_spans(
+    json: String, paths: String,
+) -> Result<Vec<serde_json::Value>, String> {
+    let value = JsonExtractorCore::new()
+        .parse_document(&json)
+        .map_err(|e| e.to_string())?;
+    let mut result = Extractor::new()
+        .extract(&value, &parse_paths(&paths))
+        .map_err(|e| e.to_string())?;
+    result.attach_spans(&SpanIndex::new_utf16(&json).map_err(|e| e.to_string())?);
+    Ok(result.to_located())
+}
+
+/// The members or elements of the value at `pointer`, for expanding a tree view
+///
+/// Span offsets and columns are UTF-16 positions, as in JavaScript strings.
+#[tauri::command]
+pub fn span_children(json: String, pointer: String) -> Result<Vec<SpanNode>, String> {
+    let index = SpanIndex::new_utf16(&json).map_err(|e| e.to_string())?;
+    index
+        .children(&pointer)
+        .map(|children| children.into_iter().cloned().collect())
+        .ok_or_else(|| format!("No value at '{}'", pointer))
+}
+
+/// Redact sensitive fields, returning the redacted document
+///
+/// `paths` and `keys` are comma-separated; with neither, common credential keys are used.
+#[tauri::command]
+pub fn redact_json(
+    json: String, paths: String, keys: String, strategy: String, lenient: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let strategy = RedactStrategy::from_name(&strategy).unwrap_or_default();
+    let redactor =
+        redact::redactor_from_lists(&paths, &keys, strategy).map_err(|e| e.to_string())?;
+    json_extractor(lenient)
+        .redact(&json, &redactor)
+        .map(|mutation| mutation.document)
+        .map_err(|e| e.to_string())
+}
+
 // ==================== UML Styler Commands ====================
 
 /// Save UML template
@@ -223,7 +391,7 @@ mod tests {
     #[test]
     fn test_detect_paths() {
         let json = r#"{"name": "test"}"#.to_string();
-        let result = detect_paths(json);
+        let result = detect_paths(json, None);
         assert!(result.is_ok());
     }
 
@@ -231,7 +399,7 @@ mod tests {
     fn test_extract_json() {
         let json = r#"{"name": "test"}"#.to_string();
         let paths = "$.name".to_string();
-        let result = extract_json(json, paths);
+        let result = extract_json(json, paths, None);
         assert!(result.is_ok());
     }
 
@@ -239,15 +407,47 @@ mod tests {
     fn test_extract_json_with_format() {
         let json = r#"{"name": "test"}"#.to_string();
         let paths = "$.name".to_string();
-        let result = extract_json_with_format(json, paths, "json".to_string());
+        let result = extract_json_with_format(json, paths, "json".to_string(), None, None, None);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_extract_json_with_format_canonical() {
+        let json = r#"{"b": 1.50, "a": "x"}"#.to_string();
+        let options = JsonOptions::canonical();
+        let result = extract_json_with_format(
+            json,
+            "$".to_string(),
+            "json".to_string(),
+            None,
+            Some(options),
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, r#"[{"a":"x","b":1.5}]"#);
+    }
+
+    #[test]
+    #[cfg(feature = "preserve-source")]
+    fn test_extract_json_with_format_preserve_source() {
+        let json = r#"{"b": 1.50, "a": 1185937385939329024}"#.to_string();
+        let result = extract_json_with_format(
+            json,
+            "$".to_string(),
+            "json".to_string(),
+            None,
+            Some(JsonOptions::compact()),
+            Some(true),
+        )
+        .unwrap();
+        assert_eq!(result, r#"[{"b":1.50,"a":1185937385939329024}]"#);
+    }
+
     #[test]
     fn test_extract_json_with_format_csv() {
         let json = r#"[{"name": "a"}, {"name": "b"}]"#.to_string();
         let paths = "$[*].name".to_string();
-        let result = extract_json_with_format(json, paths, "csv".to_string());
+        let result = extract_json_with_format(json, paths, "csv".to_string(), None, None, None);
         assert!(result.is_ok());
     }
 
@@ -255,7 +455,7 @@ mod tests {
     fn test_extract_json_with_format_text() {
         let json = r#"{"name": "test"}"#.to_string();
         let paths = "$.name".to_string();
-        let result = extract_json_with_format(json, paths, "text".to_string());
+        let result = extract_json_with_format(json, paths, "text".to_string(), None, None, None);
         assert!(result.is_ok());
     }
 
@@ -263,15 +463,164 @@ mod tests {
     fn test_extract_json_with_format_yaml() {
         let json = r#"{"name": "test"}"#.to_string();
         let paths = "$.name".to_string();
-        let result = extract_json_with_format(json, paths, "yaml".to_string());
+        let result = extract_json_with_format(json, paths, "yaml".to_string(), None, None, None);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_extract_json_with_format_markdown() {
+        let json = r#"{"items": [{"id": 1}, {"id": 2}]}"#.to_string();
+        let paths = "$.items[*]".to_string();
+        let result =
+            extract_json_with_format(json, paths, "markdown".to_string(), None, None, None)
+                .unwrap();
+        assert_eq!(result, "| id |\n| --- |\n| 1 |\n| 2 |\n");
+    }
+
+    #[test]
+    fn test_extract_json_with_format_html() {
+        let json = r#"{"name": "<test>"}"#.to_string();
+        let paths = "$.name".to_string();
+        let result =
+            extract_json_with_format(json, paths, "html".to_string(), None, None, None).unwrap();
+        assert!(result.contains("<td>&lt;test&gt;</td>"));
+    }
+
+    #[test]
+    fn test_extract_json_lenient() {
+        let json = "{\n  // comment\n  name: 'test',\n}".to_string();
+        assert!(extract_json(json.clone(), "$.name".to_string(), None).is_err());
+        let result = extract_json(json, "$.name".to_string(), Some(true)).unwrap();
+        assert_eq!(result, r#"["test"]"#);
+
+        let err = detect_paths("{a: }".to_string(), Some(true)).unwrap_err();
+        assert!(err.contains("line 1 column 5"));
+    }
+
+    #[test]
+    fn test_transform_json() {
+        let json = r#"{"items": [{"id": 2}, {"id": 1}]}"#.to_string();
+        let result = transform_json(
+            json.clone(),
+            "$.items[*].id".to_string(),
+            "sort".to_string(),
+            "json".to_string(),
+            None,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(value, serde_json::json!([1, 2]));
+
+        let err = transform_json(
+            json,
+            String::new(),
+            "nope".to_string(),
+            "json".to_string(),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.contains("Unknown function 'nope'"));
+    }
+
+    #[test]
+    fn test_validate_json() {
+        let schema = r#"{"type": "object", "required": ["name"]}"#.to_string();
+        let errors = validate_json(r#"{"id": 1}"#.to_string(), schema.clone(), None).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].schema_path, "/required");
+
+        assert!(validate_json(r#"{"name": "a"}"#.to_string(), schema, None)
+            .unwrap()
+            .is_empty());
+        assert!(validate_json("{}".to_string(), "{bad".to_string(), None).is_err());
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let cache = CompletionCache::default();
+        let json = r#"{"items": [{"id": 1}]}"#;
+        let completions = cache
+            .complete(json, "$.it", 4, false, PathMode::default())
+            .unwrap();
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].text, ".items");
+        assert_eq!((completions[0].start, completions[0].end), (1, 4));
+
+        // Reused for the same text, rebuilt for new text or a new mode
+        let completions = cache
+            .complete(json, "$.items[0].", 11, false, PathMode::default())
+            .unwrap();
+        assert_eq!(completions[0].text, ".id");
+        let completions = cache
+            .complete(r#"{"other": 1}"#, "$.", 2, false, PathMode::Rfc9535)
+            .unwrap();
+        assert_eq!(completions[0].text, ".other");
+
+        assert!(cache
+            .complete("{", "$.", 2, false, PathMode::default())
+            .is_err());
+        assert!(cache
+            .complete("{a: 1}", "$.", 2, true, PathMode::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_diagnose_json() {
+        let diagnostics = diagnose_json("{\"é\": x}".to_string(), "$.a".to_string(), None).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].source, SourceKind::Document);
+        assert_eq!((diagnostics[0].offset, diagnostics[0].column), (6, 7));
+
+        let diagnostics =
+            diagnose_json("{a: 1}".to_string(), "$..".to_string(), Some(true)).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].source, SourceKind::Path);
+    }
+
+    #[test]
+    fn test_extract_json_with_spans() {
+        let json = "{\"😀\": 1, \"ids\": [7, 8]}".to_string();
+        let located = extract_json_with_spans(json, "$.ids[1]".to_string()).unwrap();
+        assert_eq!(located.len(), 1);
+        assert_eq!(located[0]["pointer"], "/ids/1");
+        assert_eq!(located[0]["span"]["start"], 21);
+
+        let children = span_children("{\"a\": [1, 2]}".to_string(), "/a".to_string()).unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].pointer, "/a/1");
+        assert!(span_children("{}".to_string(), "/a".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_redact_json() {
+        let json = r#"{"token": "abc123456", "id": 7}"#.to_string();
+        let redacted = redact_json(
+            json.clone(),
+            String::new(),
+            String::new(),
+            "partial".to_string(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(redacted["token"], "****3456");
+        assert_eq!(redacted["id"], 7);
+
+        let redacted = redact_json(
+            json,
+            "$.id".to_string(),
+            String::new(),
+            "remove".to_string(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(redacted, serde_json::json!({"token": "abc123456"}));
+    }
+
     #[test]
     fn test_extract_json_invalid_json() {
         let json = "not json".to_string();
         let paths = "$.name".to_string();
-        let result = extract_json(json, paths);
+        let result = extract_json(json, paths, None);
         assert!(result.is_err());
     }
 
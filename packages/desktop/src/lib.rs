@@ -1,6 +1,8 @@
 //! Desktop app library for ShardDen
 //!
 
+use shard_den_json_extractor::{RedactStrategy, Redactor};
+use std::sync::Arc;
 use tauri::Manager;
 
 pub mod commands;
@@ -23,6 +25,13 @@ pub fn run() {
             detect_paths,
             extract_json,
             extract_json_with_format,
+            extract_json_with_spans,
+            span_children,
+            transform_json,
+            validate_json,
+            complete_path,
+            diagnose_json,
+            redact_json,
             // UML Styler commands
             save_uml_template,
             load_uml_templates,
@@ -38,9 +47,12 @@ pub fn run() {
             let app_dir = app.path().app_data_dir()?;
             std::fs::create_dir_all(&app_dir)?;
 
-            // Initialize storage and register state
-            let storage = Storage::new().map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+            // Initialize storage, keeping credentials out of the history file
+            let storage = Storage::new()
+                .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?
+                .with_redactor(Arc::new(Redactor::with_default_keys(RedactStrategy::Mask)));
             app.manage(AppState { storage });
+            app.manage(CompletionCache::default());
 
             tracing::info!("App directory: {:?}", app_dir);
             Ok(())
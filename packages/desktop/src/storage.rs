@@ -8,8 +8,9 @@
 
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use shard_den_core::{Config, HistoryEntry, UmlStylerConfig};
+use shard_den_core::{Config, HistoryEntry, Redact, UmlStylerConfig};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// A saved UML template
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +81,8 @@ fn uuid_simple() -> String {
 /// File-based storage for desktop
 pub struct Storage {
     data_dir: PathBuf,
+    /// Applied to every history entry before it is written
+    redactor: Option<Arc<dyn Redact>>,
 }
 
 impl Storage {
@@ -94,7 +97,16 @@ impl Storage {
         let data_dir = proj_dirs.data_dir().to_path_buf();
         std::fs::create_dir_all(&data_dir)?;
 
-        Ok(Self { data_dir })
+        Ok(Self {
+            data_dir,
+            redactor: None,
+        })
+    }
+
+    /// Redact history entries with `redactor` before they are written
+    pub fn with_redactor(mut self, redactor: Arc<dyn Redact>) -> Self {
+        self.redactor = Some(redactor);
+        self
     }
 
     /// Get path to config file
@@ -136,10 +148,13 @@ impl Storage {
         Ok(config)
     }
 
-    /// Add history entry
+    /// Add history entry, redacted first if a redactor is set
     pub fn add_history(&self, entry: HistoryEntry) -> shard_den_core::Result<()> {
         let mut entries = self.load_history_entries()?;
-        entries.push(entry);
+        entries.push(match &self.redactor {
+            Some(redactor) => entry.redact(&**redactor),
+            None => entry,
+        });
 
         // Keep only last 1000 entries
         if entries.len() > 1000 {
@@ -304,7 +319,10 @@ impl Storage {
     #[cfg(test)]
     pub fn with_data_dir(data_dir: PathBuf) -> Self {
         std::fs::create_dir_all(&data_dir).expect("Failed to create data dir");
-        Self { data_dir }
+        Self {
+            data_dir,
+            redactor: None,
+        }
     }
 }
 
@@ -345,6 +363,32 @@ mod tests {
         assert_eq!(history.len(), 1);
     }
 
+    #[test]
+    fn test_add_history_redacts() {
+        use shard_den_json_extractor::{RedactStrategy, Redactor};
+        let (storage, temp_dir) = create_storage();
+        let storage =
+            storage.with_redactor(Arc::new(Redactor::with_default_keys(RedactStrategy::Mask)));
+        storage
+            .add_history(HistoryEntry::new(
+                "json-extractor",
+                r#"{"user":"ann","password":"hunter2"}"#,
+                "user,password\nann,hunter2",
+                false,
+            ))
+            .unwrap();
+
+        let file = std::fs::read_to_string(temp_dir.path().join("history.json")).unwrap();
+        assert!(!file.contains("hunter2"));
+        let history = storage.list_history(None, 10).unwrap();
+        let input: serde_json::Value = serde_json::from_str(&history[0].input).unwrap();
+        assert_eq!(
+            input,
+            serde_json::json!({"user": "ann", "password": "********"})
+        );
+        assert_eq!(history[0].output, "user,password\nann,********\n");
+    }
+
     #[test]
     fn test_list_history_with_filter() {
         let (storage, _temp_dir) = create_storage();
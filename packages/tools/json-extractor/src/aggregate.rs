@@ -0,0 +1,403 @@
+//! Aggregation over extracted values
+//!
+//! Numeric functions (`sum`, `avg`, `min`, `max`, percentiles) skip `null`
+//! and reject any other non-numeric value. Grouped results are rows keyed by
+//! `group`, so they tabulate cleanly as CSV, Markdown or HTML.
+
+use crate::detect::json_type;
+use crate::path::{JsonPath, NormalizedPath};
+use serde_json::{Map, Number, Value};
+use shard_den_core::{Result, ShardDenError};
+use std::fmt;
+
+/// An aggregation function
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    /// Number of values, including `null`
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    /// Number of distinct values
+    Distinct,
+    /// Percentile between 0 and 100, interpolated linearly between ranks
+    Percentile(f64),
+}
+
+impl Aggregate {
+    /// Parse a function name: `count`, `sum`, `avg`, `min`, `max`, `distinct`,
+    /// `median` or `pN` (e.g. `p95`, `p99.9`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "count" => Some(Aggregate::Count),
+            "sum" => Some(Aggregate::Sum),
+            "avg" | "mean" => Some(Aggregate::Avg),
+            "min" => Some(Aggregate::Min),
+            "max" => Some(Aggregate::Max),
+            "distinct" => Some(Aggregate::Distinct),
+            "median" => Some(Aggregate::Percentile(50.0)),
+            other => other
+                .strip_prefix('p')
+                .and_then(|p| p.parse::<f64>().ok())
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(Aggregate::Percentile),
+        }
+    }
+
+    /// Parse a comma-separated list of function names
+    pub fn parse_list(names: &str) -> Result<Vec<Self>> {
+        names
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| {
+                Self::from_name(name).ok_or_else(|| {
+                    ShardDenError::invalid_input(format!(
+                        "Unknown aggregate '{}' (expected count, sum, avg, min, max, distinct, median or pN)",
+                        name.trim()
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Apply the function to `values`
+    pub fn apply(&self, values: &[Value]) -> Result<Value> {
+        match self {
+            Aggregate::Count => Ok(Value::from(values.len())),
+            Aggregate::Distinct => {
+                let mut seen: Vec<&Value> = Vec::new();
+                for value in values {
+                    if !seen.contains(&value) {
+                        seen.push(value);
+                    }
+                }
+                Ok(Value::from(seen.len()))
+            }
+            Aggregate::Sum => {
+                let numbers = self.numbers(values)?;
+                let exact = numbers
+                    .iter()
+                    .try_fold(0i64, |acc, n| n.as_i64().and_then(|n| acc.checked_add(n)));
+                match exact {
+                    Some(total) => Ok(Value::from(total)),
+                    None => Ok(float(numbers.iter().filter_map(|n| n.as_f64()).sum())),
+                }
+            }
+            Aggregate::Avg => {
+                let numbers = self.numbers(values)?;
+                if numbers.is_empty() {
+                    return Ok(Value::Null);
+                }
+                let total: f64 = numbers.iter().filter_map(|n| n.as_f64()).sum();
+                Ok(float(total / numbers.len() as f64))
+            }
+            Aggregate::Min | Aggregate::Max => {
+                let numbers = self.numbers(values)?;
+                let by_value = |a: &&&Number, b: &&&Number| {
+                    a.as_f64()
+                        .partial_cmp(&b.as_f64())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                };
+                let pick = if *self == Aggregate::Min {
+                    numbers.iter().min_by(by_value)
+                } else {
+                    numbers.iter().max_by(by_value)
+                };
+                Ok(pick.map_or(Value::Null, |n| Value::Number((*n).clone())))
+            }
+            Aggregate::Percentile(p) => {
+                let mut sorted: Vec<f64> = self
+                    .numbers(values)?
+                    .iter()
+                    .filter_map(|n| n.as_f64())
+                    .collect();
+                if sorted.is_empty() {
+                    return Ok(Value::Null);
+                }
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let rank = p / 100.0 * (sorted.len() - 1) as f64;
+                let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+                let fraction = rank - lower as f64;
+                Ok(float(
+                    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction,
+                ))
+            }
+        }
+    }
+
+    /// Numeric values, skipping `null`
+    fn numbers<'a>(&self, values: &'a [Value]) -> Result<Vec<&'a Number>> {
+        let mut numbers = Vec::with_capacity(values.len());
+        for (i, value) in values.iter().enumerate() {
+            match value {
+                Value::Number(n) => numbers.push(n),
+                Value::Null => {}
+                other => {
+                    return Err(ShardDenError::invalid_input(format!(
+                        "{} requires numbers, found {} at index {}",
+                        self,
+                        json_type(other),
+                        i
+                    )))
+                }
+            }
+        }
+        Ok(numbers)
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregate::Count => write!(f, "count"),
+            Aggregate::Sum => write!(f, "sum"),
+            Aggregate::Avg => write!(f, "avg"),
+            Aggregate::Min => write!(f, "min"),
+            Aggregate::Max => write!(f, "max"),
+            Aggregate::Distinct => write!(f, "distinct"),
+            Aggregate::Percentile(p) => write!(f, "p{}", p),
+        }
+    }
+}
+
+/// Apply every function to `values`, keyed by function name
+pub fn aggregate(values: &[Value], functions: &[Aggregate]) -> Result<Map<String, Value>> {
+    let mut row = Map::new();
+    for function in functions {
+        row.insert(function.to_string(), function.apply(values)?);
+    }
+    Ok(row)
+}
+
+/// Column names of aggregate rows, in order: `group` when grouped, then each function
+pub fn columns(functions: &[Aggregate], grouped: bool) -> Vec<String> {
+    let group = grouped.then(|| "group".to_string());
+    group
+        .into_iter()
+        .chain(functions.iter().map(Aggregate::to_string))
+        .collect()
+}
+
+/// How many levels two queries share before they diverge
+///
+/// Leading segments count while they are equal and select exactly one level
+/// (so not `..`); a value and a group key belong together when they share
+/// their location down to this depth, e.g. the same `$.orders[i]`.
+pub fn shared_depth(values: &[JsonPath], keys: &[JsonPath]) -> usize {
+    values
+        .iter()
+        .zip(keys)
+        .take_while(|(a, b)| {
+            a == b && !matches!(a, JsonPath::Recursive(_) | JsonPath::Descendant(_))
+        })
+        .count()
+}
+
+/// Aggregate `values` per distinct key
+///
+/// Each value is grouped by the one key found at the same location down to
+/// `depth` (see [`shared_depth`]); a value with no such key, or with several,
+/// is an error rather than being counted under another group. Returns one
+/// row per group, in first-seen order, with the key under `group`.
+pub fn aggregate_grouped(
+    values: &[(NormalizedPath, &Value)], keys: &[(NormalizedPath, &Value)], depth: usize,
+    functions: &[Aggregate],
+) -> Result<Vec<Value>> {
+    let mut groups: Vec<(&Value, Vec<Value>)> = Vec::new();
+    for (path, value) in values {
+        let parent = path.elements().get(..depth);
+        let mut owners = keys
+            .iter()
+            .filter(|(key_path, _)| parent.is_some() && key_path.elements().get(..depth) == parent);
+        let key = match (owners.next(), owners.next()) {
+            (Some((_, key)), None) => *key,
+            (None, _) => {
+                return Err(ShardDenError::invalid_input(format!(
+                    "No group key for the value at {}",
+                    path
+                )))
+            }
+            (Some(_), Some(_)) => {
+                return Err(ShardDenError::invalid_input(format!(
+                    "Several group keys for the value at {}",
+                    path
+                )))
+            }
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push((*value).clone()),
+            None => groups.push((key, vec![(*value).clone()])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, members)| {
+            let mut row = aggregate(&members, functions)?;
+            row.insert("group".to_string(), key.clone());
+            Ok(Value::Object(row))
+        })
+        .collect()
+}
+
+fn float(n: f64) -> Value {
+    Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathParser;
+    use serde_json::json;
+
+    fn values(v: Value) -> Vec<Value> {
+        v.as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn test_parse_functions() {
+        let functions = Aggregate::parse_list("count, sum,avg,median,p99.9").unwrap();
+        assert_eq!(
+            functions,
+            vec![
+                Aggregate::Count,
+                Aggregate::Sum,
+                Aggregate::Avg,
+                Aggregate::Percentile(50.0),
+                Aggregate::Percentile(99.9)
+            ]
+        );
+        assert_eq!(functions[4].to_string(), "p99.9");
+        assert!(Aggregate::parse_list("sum,total").is_err());
+        assert!(Aggregate::from_name("p101").is_none());
+    }
+
+    #[test]
+    fn test_numeric_aggregates() {
+        let totals = values(json!([10, 20.5, null, 30, 40]));
+        let functions = Aggregate::parse_list("count,sum,avg,min,max,distinct,median,p75").unwrap();
+        let row = aggregate(&totals, &functions).unwrap();
+        assert_eq!(
+            Value::Object(row),
+            json!({
+                "count": 5,
+                "sum": 100.5,
+                "avg": 25.125,
+                "min": 10,
+                "max": 40,
+                "distinct": 5,
+                "p50": 25.25,
+                "p75": 32.5
+            })
+        );
+    }
+
+    #[test]
+    fn test_integer_sum_and_empty_input() {
+        assert_eq!(
+            Aggregate::Sum.apply(&values(json!([1, 2, 3]))).unwrap(),
+            json!(6)
+        );
+        assert_eq!(Aggregate::Sum.apply(&[]).unwrap(), json!(0));
+        assert_eq!(Aggregate::Avg.apply(&[]).unwrap(), Value::Null);
+        assert_eq!(Aggregate::Max.apply(&[Value::Null]).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_rejects_non_numeric_values() {
+        let err = Aggregate::Sum.apply(&values(json!([1, "2"]))).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("sum requires numbers, found string at index 1"));
+        assert_eq!(
+            Aggregate::Count.apply(&values(json!([1, "2"]))).unwrap(),
+            json!(2)
+        );
+        assert_eq!(
+            Aggregate::Distinct
+                .apply(&values(json!(["a", "b", "a"])))
+                .unwrap(),
+            json!(2)
+        );
+    }
+
+    #[test]
+    fn test_grouped() {
+        let parser = PathParser::new();
+        let doc = json!({"orders": [
+            {"c": "x", "total": 10},
+            {"c": "y", "total": 5},
+            {"c": "x", "total": 20},
+            {"c": null, "total": 1}
+        ]});
+        let totals = parser.parse("$.orders[*].total").unwrap();
+        let customers = parser.parse("$.orders[*].c").unwrap();
+        let depth = shared_depth(&totals, &customers);
+        assert_eq!(depth, 2);
+
+        let rows = aggregate_grouped(
+            &parser.locate(&doc, &totals).unwrap(),
+            &parser.locate(&doc, &customers).unwrap(),
+            depth,
+            &[Aggregate::Count, Aggregate::Sum],
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                json!({"group": "x", "count": 2, "sum": 30}),
+                json!({"group": "y", "count": 1, "sum": 5}),
+                json!({"group": null, "count": 1, "sum": 1})
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grouped_pairs_by_location() {
+        let parser = PathParser::new();
+        let totals = parser.parse("$.orders[*].total").unwrap();
+        let customers = parser.parse("$.orders[*].c").unwrap();
+        let group = |doc: &Value| {
+            aggregate_grouped(
+                &parser.locate(doc, &totals).unwrap(),
+                &parser.locate(doc, &customers).unwrap(),
+                shared_depth(&totals, &customers),
+                &[Aggregate::Sum],
+            )
+        };
+
+        // An order without a total does not shift the others' keys
+        let doc = json!({"orders": [{"c": "a"}, {"c": "b", "total": 20}, {"c": "b", "total": 5}]});
+        assert_eq!(group(&doc).unwrap(), vec![json!({"group": "b", "sum": 25})]);
+
+        // An order without a key cannot be grouped
+        let doc = json!({"orders": [{"c": "a"}, {"c": "b", "total": 20}, {"total": 5}]});
+        let err = group(&doc).unwrap_err();
+        assert!(err.to_string().contains("$['orders'][2]['total']"));
+
+        // Nothing shared below the root: one document-level key groups everything
+        let region = parser.parse("$.region").unwrap();
+        assert_eq!(shared_depth(&totals, &region), 0);
+        let doc = json!({"region": "eu", "orders": [{"total": 1}, {"total": 2}]});
+        let rows = aggregate_grouped(
+            &parser.locate(&doc, &totals).unwrap(),
+            &parser.locate(&doc, &region).unwrap(),
+            0,
+            &[Aggregate::Sum],
+        )
+        .unwrap();
+        assert_eq!(rows, vec![json!({"group": "eu", "sum": 3})]);
+
+        // Several keys per value are ambiguous
+        let tags = parser.parse("$.orders[*].tags[*]").unwrap();
+        let doc = json!({"orders": [{"tags": ["a", "b"], "total": 1}]});
+        assert!(aggregate_grouped(
+            &parser.locate(&doc, &totals).unwrap(),
+            &parser.locate(&doc, &tags).unwrap(),
+            shared_depth(&totals, &tags),
+            &[Aggregate::Sum],
+        )
+        .is_err());
+    }
+}
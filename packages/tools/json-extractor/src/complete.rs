@@ -0,0 +1,762 @@
+//! Path completion
+//!
+//! Suggests the next segment of a partially typed path. Keys come from path
+//! detection, which samples large arrays and counts occurrences for ranking,
+//! so a completer built once can answer every keystroke; only index
+//! suggestions look at the matched arrays themselves.
+
+use crate::detect::{member_segment, PathDetector, PathStats};
+use crate::path::{JsonPath, NormalizedPath, PathElement, PathParser};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Maximum number of completions returned
+const MAX_COMPLETIONS: usize = 50;
+
+/// Index suggestions offered per array
+const MAX_INDICES: usize = 10;
+
+/// Element keys offered as filter templates
+const MAX_FILTER_TEMPLATES: usize = 3;
+
+/// Matched nodes inspected for array lengths
+const MAX_INDEX_NODES: usize = 1000;
+
+/// RFC 9535 function extensions
+const FUNCTIONS: [(&str, &str); 5] = [
+    ("length(", "length(value): characters, elements or members"),
+    ("count(", "count(nodes): number of selected nodes"),
+    ("match(", "match(string, regex): whole-string match"),
+    ("search(", "search(string, regex): substring match"),
+    ("value(", "value(nodes): the single selected value"),
+];
+
+/// What a completion inserts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Root,
+    Wildcard,
+    Key,
+    Index,
+    Filter,
+    Function,
+}
+
+/// A suggested edit: replace `start..end` of the partial path with `text`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Completion {
+    pub text: String,
+    pub kind: CompletionKind,
+    /// Short description, e.g. the types observed for a key
+    pub detail: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// One step of a detected path
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Key(String),
+    Element,
+}
+
+/// One step of the path being completed, matched against detected paths
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    Key(String),
+    Element,
+    AnyKey,
+    AnyChild,
+    /// Zero or more steps
+    Descend,
+}
+
+/// A location the completed path can continue from
+struct Parent<'s> {
+    steps: &'s [Shape],
+    is_array: bool,
+}
+
+/// A completion before ranking
+struct Candidate {
+    text: String,
+    kind: CompletionKind,
+    detail: String,
+    /// Occurrences seen by detection, higher ranks first
+    count: usize,
+}
+
+/// Suggests path completions for one document
+#[derive(Debug)]
+pub struct PathCompleter<'a> {
+    parser: PathParser,
+    root: Cow<'a, Value>,
+    paths: Vec<(Vec<Shape>, PathStats)>,
+}
+
+impl<'a> PathCompleter<'a> {
+    pub fn new(parser: &PathParser, root: &'a Value) -> Self {
+        Self::build(parser, Cow::Borrowed(root))
+    }
+
+    /// A completer that owns its document, for keeping across calls (e.g. from JavaScript)
+    pub fn owned(parser: &PathParser, root: Value) -> PathCompleter<'static> {
+        PathCompleter::build(parser, Cow::Owned(root))
+    }
+
+    fn build(parser: &PathParser, root: Cow<'a, Value>) -> Self {
+        let mut detector = PathDetector::new();
+        detector.observe(&root);
+        let any_path = PathParser::new();
+        let paths = detector
+            .finish()
+            .into_iter()
+            .filter_map(|stats| {
+                let segments = any_path.parse(&stats.path).ok()?;
+                let steps = segments
+                    .into_iter()
+                    .map(|segment| match segment {
+                        JsonPath::Key(key) => Shape::Key(key),
+                        _ => Shape::Element,
+                    })
+                    .collect();
+                Some((steps, stats))
+            })
+            .collect();
+        Self {
+            parser: PathParser::with_mode(parser.mode()),
+            root,
+            paths,
+        }
+    }
+
+    /// Ranked completions for `partial` with the cursor at byte offset `cursor`
+    ///
+    /// Only the text before the cursor is considered; each completion replaces
+    /// the segment being typed, up to the cursor.
+    pub fn complete(&self, partial: &str, cursor: usize) -> Vec<Completion> {
+        let mut cursor = cursor.min(partial.len());
+        while !partial.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        let before = &partial[..cursor];
+        if before.trim().is_empty() {
+            let root = Candidate {
+                text: "$".to_string(),
+                kind: CompletionKind::Root,
+                detail: "document root".to_string(),
+                count: 0,
+            };
+            return rank(vec![root], "", 0, cursor);
+        }
+
+        let (candidates, token_start) = match open_filter(before) {
+            Some(bracket) => self.complete_filter(before, bracket),
+            None => self.complete_query(before, 0, Vec::new(), true),
+        };
+        rank(candidates, &before[token_start..], token_start, cursor)
+    }
+
+    /// Like [`complete`](Self::complete), with the cursor and returned offsets
+    /// in UTF-16 code units, as used by JavaScript strings
+    pub fn complete_utf16(&self, partial: &str, cursor: usize) -> Vec<Completion> {
+        let byte_cursor = partial
+            .char_indices()
+            .scan(0, |units, (i, c)| {
+                let at = *units;
+                *units += c.len_utf16();
+                Some((i, at))
+            })
+            .find(|&(_, units)| units >= cursor)
+            .map_or(partial.len(), |(i, _)| i);
+        let utf16 = |byte: usize| partial[..byte].encode_utf16().count();
+        self.complete(partial, byte_cursor)
+            .into_iter()
+            .map(|c| Completion {
+                start: utf16(c.start),
+                end: utf16(c.end),
+                ..c
+            })
+            .collect()
+    }
+
+    /// Candidates for the last segment of `query` (starting with `$` or `@`),
+    /// relative to `base`, and the byte offset where that segment starts
+    fn complete_query(
+        &self, query: &str, offset: usize, base: Vec<Pattern>, absolute: bool,
+    ) -> (Vec<Candidate>, usize) {
+        let Some(rest) = query.strip_prefix(['$', '@']) else {
+            return (Vec::new(), offset + query.len());
+        };
+        let (complete_len, token) = split_token(query);
+        let token_start = offset + complete_len;
+        let Ok(segments) = self
+            .parser
+            .parse(&format!("${}", &rest[..complete_len - 1]))
+        else {
+            return (Vec::new(), token_start);
+        };
+        let mut pattern = base;
+        pattern.extend(patterns(&segments));
+        let parents = self.parents(&pattern);
+
+        let mut candidates = Vec::new();
+        if token.starts_with("..") {
+            for (key, count, types) in self.child_keys(&parents, true) {
+                let segment = member_segment(&key);
+                let text = match segment.strip_prefix('.') {
+                    Some(_) => format!(".{}", segment),
+                    None => format!("..{}", segment),
+                };
+                candidates.push(key_candidate(text, count, &types));
+            }
+            return (candidates, token_start);
+        }
+
+        let bracket = token.starts_with('[');
+        for (key, count, types) in self.child_keys(&parents, false) {
+            let text = if bracket {
+                NormalizedPath::from(vec![PathElement::Key(key)]).to_string()[1..].to_string()
+            } else {
+                member_segment(&key)
+            };
+            candidates.push(key_candidate(text, count, &types));
+        }
+        if token.starts_with('.') || !parents.iter().any(|p| p.is_array) {
+            return (candidates, token_start);
+        }
+
+        candidates.push(Candidate {
+            text: "[*]".to_string(),
+            kind: CompletionKind::Wildcard,
+            detail: "all elements".to_string(),
+            count: 0,
+        });
+        if absolute {
+            candidates.extend(self.index_candidates(&segments, token));
+        }
+        candidates.extend(self.filter_candidates(&parents));
+        (candidates, token_start)
+    }
+
+    /// Candidates inside the filter opened by the `[` at byte offset `bracket`
+    fn complete_filter(&self, before: &str, bracket: usize) -> (Vec<Candidate>, usize) {
+        let prefix = &before[..bracket];
+        let inner_start = bracket + 2;
+        let inner = &before[inner_start.min(before.len())..];
+        let Ok(segments) = self.parser.parse(prefix) else {
+            return (Vec::new(), before.len());
+        };
+        let mut element = patterns(&segments);
+        element.push(Pattern::AnyChild);
+
+        let query_start = inner.rfind(['@', '$']).filter(|&i| {
+            !inner[i..]
+                .chars()
+                .any(|c| c.is_whitespace() || "()=!<>&|,".contains(c))
+        });
+        if let Some(i) = query_start {
+            let base = if inner[i..].starts_with('@') {
+                element
+            } else {
+                Vec::new()
+            };
+            return self.complete_query(&inner[i..], inner_start + i, base, false);
+        }
+
+        let ident_len = inner
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .count();
+        let token_start = before.len() - ident_len;
+        let mut candidates: Vec<Candidate> = FUNCTIONS
+            .iter()
+            .map(|(text, detail)| Candidate {
+                text: text.to_string(),
+                kind: CompletionKind::Function,
+                detail: detail.to_string(),
+                count: 0,
+            })
+            .collect();
+        if ident_len == 0 {
+            let parents = self.parents(&element);
+            for (key, count, types) in self.child_keys(&parents, false) {
+                let text = format!("@{}", member_segment(&key));
+                candidates.push(key_candidate(text, count, &types));
+            }
+        }
+        (candidates, token_start)
+    }
+
+    /// Detected locations matching `pattern`, including the root
+    fn parents(&self, pattern: &[Pattern]) -> Vec<Parent<'_>> {
+        let mut parents = Vec::new();
+        if matches(pattern, &[]) {
+            parents.push(Parent {
+                steps: &[],
+                is_array: self.root.is_array(),
+            });
+        }
+        for (steps, stats) in &self.paths {
+            if matches(pattern, steps) {
+                parents.push(Parent {
+                    steps,
+                    is_array: stats.types.iter().any(|t| t == "array"),
+                });
+            }
+        }
+        parents
+    }
+
+    /// Keys directly below (or anywhere below, with `descend`) the parents,
+    /// with their total occurrences and observed types, in first-seen order
+    fn child_keys(&self, parents: &[Parent], descend: bool) -> Vec<(String, usize, Vec<String>)> {
+        let mut keys: Vec<(String, usize, Vec<String>)> = Vec::new();
+        for (steps, stats) in &self.paths {
+            let Some(Shape::Key(key)) = steps.last() else {
+                continue;
+            };
+            let below = parents.iter().any(|p| {
+                steps.starts_with(p.steps)
+                    && (steps.len() == p.steps.len() + 1 || descend && steps.len() > p.steps.len())
+            });
+            if !below {
+                continue;
+            }
+            match keys.iter_mut().find(|(k, _, _)| k == key) {
+                Some((_, count, types)) => {
+                    *count += stats.count;
+                    for t in &stats.types {
+                        if !types.contains(t) {
+                            types.push(t.clone());
+                        }
+                    }
+                }
+                None => keys.push((key.clone(), stats.count, stats.types.clone())),
+            }
+        }
+        keys
+    }
+
+    /// Indices valid in every array matched by `segments`, narrowed by typed digits
+    fn index_candidates(&self, segments: &[JsonPath], token: &str) -> Vec<Candidate> {
+        let Ok(nodes) = self.parser.traverse(&self.root, segments) else {
+            return Vec::new();
+        };
+        let len = nodes
+            .iter()
+            .take(MAX_INDEX_NODES)
+            .filter_map(|node| node.as_array().map(Vec::len))
+            .max()
+            .unwrap_or(0);
+        let digits = token.trim_start_matches('[');
+        let mut candidates: Vec<Candidate> = (0..len)
+            .filter(|i| i.to_string().starts_with(digits))
+            .take(MAX_INDICES)
+            .map(|i| Candidate {
+                text: format!("[{}]", i),
+                kind: CompletionKind::Index,
+                detail: format!("element {} of {}", i + 1, len),
+                count: 0,
+            })
+            .collect();
+        if len > 0 {
+            candidates.push(Candidate {
+                text: "[-1]".to_string(),
+                kind: CompletionKind::Index,
+                detail: "last element".to_string(),
+                count: 0,
+            });
+        }
+        candidates
+    }
+
+    /// `[?@.key == example]` for the most common scalar keys of array elements
+    fn filter_candidates(&self, parents: &[Parent]) -> Vec<Candidate> {
+        let mut fields: Vec<(&str, usize, Option<&Value>)> = Vec::new();
+        for (steps, stats) in &self.paths {
+            let [.., Shape::Element, Shape::Key(key)] = steps.as_slice() else {
+                continue;
+            };
+            let scalar = stats.types.iter().all(|t| t != "object" && t != "array");
+            let element_of = |p: &Parent| p.is_array && steps.len() == p.steps.len() + 2;
+            if scalar
+                && parents
+                    .iter()
+                    .any(|p| element_of(p) && steps.starts_with(p.steps))
+            {
+                fields.push((key, stats.count, stats.example.as_ref()));
+            }
+        }
+        fields.sort_by_key(|field| std::cmp::Reverse(field.1));
+        fields
+            .into_iter()
+            .take(MAX_FILTER_TEMPLATES)
+            .map(|(key, count, example)| Candidate {
+                text: match example {
+                    Some(example) => format!("[?@{} == {}]", member_segment(key), example),
+                    None => format!("[?@{}]", member_segment(key)),
+                },
+                kind: CompletionKind::Filter,
+                detail: format!("filter elements by {}", key),
+                count,
+            })
+            .collect()
+    }
+}
+
+fn key_candidate(text: String, count: usize, types: &[String]) -> Candidate {
+    Candidate {
+        text,
+        kind: CompletionKind::Key,
+        detail: types.join(" | "),
+        count,
+    }
+}
+
+/// Keep the candidates matching `token` and order them: prefix matches
+/// first, then by kind, then by how often they occur
+fn rank(candidates: Vec<Candidate>, token: &str, start: usize, end: usize) -> Vec<Completion> {
+    let fragment = token
+        .trim_start_matches(['.', '[', '\'', '"', '?', '@'])
+        .to_lowercase();
+    let mut ranked: Vec<(u8, Candidate)> = candidates
+        .into_iter()
+        .filter_map(|c| {
+            if c.text.starts_with(token) {
+                Some((0, c))
+            } else if c.kind == CompletionKind::Key
+                && !fragment.is_empty()
+                && c.text.to_lowercase().contains(&fragment)
+            {
+                Some((1, c))
+            } else {
+                None
+            }
+        })
+        .collect();
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+        a_rank
+            .cmp(b_rank)
+            .then(a.kind.cmp(&b.kind))
+            .then(b.count.cmp(&a.count))
+    });
+    ranked
+        .into_iter()
+        .take(MAX_COMPLETIONS)
+        .map(|(_, c)| Completion {
+            text: c.text,
+            kind: c.kind,
+            detail: c.detail,
+            start,
+            end,
+        })
+        .collect()
+}
+
+/// Split a query into its complete segments and the segment being typed,
+/// returning the length of the complete part and the partial segment
+fn split_token(query: &str) -> (usize, &str) {
+    let mut last_start = None;
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut previous = '$';
+    for (i, c) in query.char_indices().skip(1) {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else {
+            match c {
+                '\'' | '"' if depth > 0 => quote = Some(c),
+                '[' => {
+                    if depth == 0 {
+                        last_start = Some(i);
+                    }
+                    depth += 1;
+                }
+                ']' => depth = depth.saturating_sub(1),
+                // The second dot of `..` belongs to the same segment
+                '.' if depth == 0 && !(previous == '.' && last_start == Some(i - 1)) => {
+                    last_start = Some(i)
+                }
+                _ => {}
+            }
+        }
+        previous = c;
+    }
+
+    match last_start {
+        Some(start) if !(query.ends_with(']') && depth == 0 && quote.is_none()) => {
+            (start, &query[start..])
+        }
+        _ => (query.len(), ""),
+    }
+}
+
+/// Byte offset of the `[` opening the innermost unclosed filter, if any
+///
+/// Filters nested inside another open filter are not completed.
+fn open_filter(text: &str) -> Option<usize> {
+    let mut open: Vec<(usize, bool)> = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '[' => open.push((i, text[i + 1..].trim_start().starts_with('?'))),
+            ']' => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    let filters: Vec<usize> = open.iter().filter(|(_, f)| *f).map(|(i, _)| *i).collect();
+    match filters.as_slice() {
+        [bracket] => Some(*bracket),
+        _ => None,
+    }
+}
+
+/// Match patterns for parsed segments
+fn patterns(segments: &[JsonPath]) -> Vec<Pattern> {
+    let mut pattern = Vec::new();
+    for segment in segments {
+        match segment {
+            JsonPath::Key(key) => pattern.push(Pattern::Key(key.clone())),
+            JsonPath::Index(_) | JsonPath::Slice { .. } | JsonPath::Filter(_) => {
+                pattern.push(Pattern::Element)
+            }
+            JsonPath::Wildcard => pattern.push(Pattern::AnyChild),
+            JsonPath::Union(items) => {
+                let step = if items.iter().all(|i| matches!(i, JsonPath::Key(_))) {
+                    Pattern::AnyKey
+                } else if items
+                    .iter()
+                    .all(|i| matches!(i, JsonPath::Index(_) | JsonPath::Slice { .. }))
+                {
+                    Pattern::Element
+                } else {
+                    Pattern::AnyChild
+                };
+                pattern.push(step);
+            }
+            JsonPath::Recursive(key) => {
+                pattern.push(Pattern::Descend);
+                pattern.push(Pattern::Key(key.clone()));
+            }
+            JsonPath::Descendant(inner) => {
+                pattern.push(Pattern::Descend);
+                pattern.extend(patterns(std::slice::from_ref(inner)));
+            }
+        }
+    }
+    pattern
+}
+
+fn matches(pattern: &[Pattern], steps: &[Shape]) -> bool {
+    match pattern.split_first() {
+        None => steps.is_empty(),
+        Some((Pattern::Descend, rest)) => {
+            matches(rest, steps) || (!steps.is_empty() && matches(pattern, &steps[1..]))
+        }
+        Some((expected, rest)) => match steps.split_first() {
+            Some((step, remaining)) => {
+                let step_matches = match (expected, step) {
+                    (Pattern::Key(a), Shape::Key(b)) => a == b,
+                    (Pattern::AnyKey, Shape::Key(_)) => true,
+                    (Pattern::Element, Shape::Element) => true,
+                    (Pattern::AnyChild, _) => true,
+                    _ => false,
+                };
+                step_matches && matches(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Members are written in sorted order, so the document reads the same
+    /// with `preserve-source`
+    fn document() -> Value {
+        json!({
+            "stats": {"visits": 3},
+            "store": {
+                "books": [
+                    {"category": "fiction", "price": 8, "title": "A"},
+                    {"category": "fiction", "isbn": "x", "price": 12, "title": "B"},
+                    {"category": "reference", "price": 5, "title": "C"}
+                ],
+                "name": "shop"
+            }
+        })
+    }
+
+    fn texts(completions: &[Completion]) -> Vec<&str> {
+        completions.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_child_keys() {
+        let doc = document();
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        let completions = completer.complete("$.st", 4);
+        // Equal counts keep first-seen order
+        assert_eq!(texts(&completions), vec![".stats", ".store"]);
+        assert_eq!(completions[0].start, 1);
+        assert_eq!(completions[0].end, 4);
+
+        let completions = completer.complete("$.store.books[*].", 17);
+        assert_eq!(
+            texts(&completions),
+            vec![".category", ".price", ".title", ".isbn"]
+        );
+        assert_eq!(completions[1].detail, "number");
+
+        // Case-insensitive substring matches rank after prefix matches
+        let completions = completer.complete("$.store.books[0].TIT", 20);
+        assert_eq!(texts(&completions), vec![".title"]);
+    }
+
+    #[test]
+    fn test_array_suggestions() {
+        let doc = document();
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        let completions = completer.complete("$.store.books", 13);
+        assert_eq!(texts(&completions), vec![".books"]);
+
+        let completions = completer.complete("$.store.books[", 14);
+        assert_eq!(
+            texts(&completions),
+            vec![
+                "[*]",
+                "[0]",
+                "[1]",
+                "[2]",
+                "[-1]",
+                "[?@.category == \"fiction\"]",
+                "[?@.price == 8]",
+                "[?@.title == \"A\"]"
+            ]
+        );
+        assert_eq!(completions[0].start, 13);
+
+        let completions = completer.complete("$.store.books[1", 15);
+        assert_eq!(texts(&completions), vec!["[1]"]);
+
+        let completions = completer.complete("$.store.books[*]", 16);
+        assert!(texts(&completions).contains(&".title"));
+        // Elements are objects, so no index suggestions
+        assert!(!texts(&completions).contains(&"[0]"));
+    }
+
+    #[test]
+    fn test_descendants_and_brackets() {
+        let doc = json!({"a": {"b c": 1, "d": {"e": 2}}});
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        assert_eq!(
+            texts(&completer.complete("$..", 3)),
+            vec!["..a", "..['b c']", "..d", "..e"]
+        );
+        assert_eq!(texts(&completer.complete("$.a['b", 6)), vec!["['b c']"]);
+        assert_eq!(texts(&completer.complete("$..d.", 5)), vec![".e"]);
+    }
+
+    #[test]
+    fn test_filters_and_functions() {
+        let doc = document();
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        let path = "$.store.books[?@.pr";
+        let completions = completer.complete(path, path.len());
+        assert_eq!(texts(&completions), vec![".price"]);
+        assert_eq!(completions[0].start, path.len() - 3);
+
+        let path = "$.store.books[?";
+        let completions = completer.complete(path, path.len());
+        assert_eq!(&texts(&completions)[..2], &["@.category", "@.price"]);
+        assert!(texts(&completions).contains(&"length("));
+
+        let path = "$.store.books[?@.price > 5 && le";
+        assert_eq!(
+            texts(&completer.complete(path, path.len())),
+            vec!["length("]
+        );
+    }
+
+    #[test]
+    fn test_cursor_and_root() {
+        let doc = document();
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        assert_eq!(texts(&completer.complete("", 0)), vec!["$"]);
+        // Text after the cursor is ignored
+        let completions = completer.complete("$.sta.visits", 5);
+        assert_eq!(texts(&completions), vec![".stats"]);
+        assert_eq!((completions[0].start, completions[0].end), (1, 5));
+        assert!(completer.complete("$.store[?@.a[?@.", 16).is_empty());
+        assert!(completer.complete("$.[", 3).is_empty());
+    }
+
+    #[test]
+    fn test_query_without_root() {
+        let doc = document();
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        assert!(completer.complete("é", 2).is_empty());
+        assert!(completer.complete("store.", 6).is_empty());
+        assert!(completer.complete_utf16("😀.", 3).is_empty());
+    }
+
+    #[test]
+    fn test_owned_completer() {
+        let parser = PathParser::new();
+        let completer = PathCompleter::owned(&parser, document());
+        let path = "$.sto";
+        assert_eq!(texts(&completer.complete(path, path.len())), vec![".store"]);
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        let doc = json!({"é": {"😀": {"x": 1}}});
+        let parser = PathParser::new();
+        let completer = PathCompleter::new(&parser, &doc);
+
+        // "$.é.😀." is 7 UTF-16 units and 10 bytes long
+        let completions = completer.complete_utf16("$.é.😀.", 7);
+        assert_eq!(texts(&completions), vec![".x"]);
+        assert_eq!((completions[0].start, completions[0].end), (6, 7));
+    }
+}
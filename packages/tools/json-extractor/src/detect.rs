@@ -0,0 +1,231 @@
+//! Path detection with type statistics
+//!
+//! Walks every element of every array (evenly sampled past a cap) so keys that
+//! only appear in later elements are still found, and records per path which
+//! JSON types were seen, how often, and an example value.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Default number of elements inspected per array
+pub const DEFAULT_SAMPLE_LIMIT: usize = 1000;
+
+/// What was observed at one path
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathStats {
+    pub path: String,
+    /// JSON types seen at this path, in first-seen order
+    pub types: Vec<String>,
+    /// Number of sampled occurrences
+    pub count: usize,
+    /// Fraction of occurrences that were `null`
+    pub null_ratio: f64,
+    /// First non-null scalar seen at this path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+    #[serde(skip)]
+    null_count: usize,
+}
+
+/// Accumulates path statistics over one or more documents
+#[derive(Debug)]
+pub struct PathDetector {
+    sample_limit: usize,
+    stats: Vec<PathStats>,
+    index: HashMap<String, usize>,
+}
+
+impl Default for PathDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathDetector {
+    /// Create a detector with the default sampling cap
+    pub fn new() -> Self {
+        Self::with_sample_limit(DEFAULT_SAMPLE_LIMIT)
+    }
+
+    /// Create a detector inspecting at most `limit` elements per array
+    pub fn with_sample_limit(limit: usize) -> Self {
+        Self {
+            sample_limit: limit.max(1),
+            stats: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Record the paths of a document
+    pub fn observe(&mut self, value: &Value) {
+        self.walk(value, "$");
+    }
+
+    /// Statistics for every path seen, in first-seen order
+    pub fn finish(self) -> Vec<PathStats> {
+        self.stats
+    }
+
+    fn walk(&mut self, value: &Value, prefix: &str) {
+        match value {
+            Value::Object(map) => {
+                for (key, val) in map {
+                    let path = format!("{}{}", prefix, member_segment(key));
+                    self.record(&path, val);
+                    self.walk(val, &path);
+                }
+            }
+            Value::Array(arr) if !arr.is_empty() => {
+                // For arrays, show only [*] wildcard instead of indices
+                let path = format!("{}[*]", prefix);
+                let len = arr.len();
+                let samples = len.min(self.sample_limit);
+                for i in 0..samples {
+                    let item = &arr[i * len / samples];
+                    self.record(&path, item);
+                    self.walk(item, &path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn record(&mut self, path: &str, value: &Value) {
+        let i = match self.index.get(path) {
+            Some(&i) => i,
+            None => {
+                self.index.insert(path.to_string(), self.stats.len());
+                self.stats.push(PathStats {
+                    path: path.to_string(),
+                    types: Vec::new(),
+                    count: 0,
+                    null_ratio: 0.0,
+                    example: None,
+                    null_count: 0,
+                });
+                self.stats.len() - 1
+            }
+        };
+
+        let stats = &mut self.stats[i];
+        let type_name = json_type(value);
+        if !stats.types.iter().any(|t| t == type_name) {
+            stats.types.push(type_name.to_string());
+        }
+        stats.count += 1;
+        if value.is_null() {
+            stats.null_count += 1;
+        }
+        stats.null_ratio = stats.null_count as f64 / stats.count as f64;
+        if stats.example.is_none() && !value.is_null() && !value.is_array() && !value.is_object() {
+            stats.example = Some(value.clone());
+        }
+    }
+}
+
+/// JSON type name of a value
+pub fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Child segment for `key`: `.key` when it is a plain name, otherwise `['key']`
+pub(crate) fn member_segment(key: &str) -> String {
+    let plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || !c.is_ascii());
+    if plain {
+        format!(".{}", key)
+    } else {
+        let quoted =
+            crate::NormalizedPath::from(vec![crate::PathElement::Key(key.to_string())]).to_string();
+        quoted[1..].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn detect(value: &Value) -> Vec<PathStats> {
+        let mut detector = PathDetector::new();
+        detector.observe(value);
+        detector.finish()
+    }
+
+    #[test]
+    fn test_unions_keys_across_elements() {
+        let value = json!({"items": [{"id": 1}, {"id": 2, "extra": "x"}, {"nested": {"a": true}}]});
+        let paths: Vec<String> = detect(&value).into_iter().map(|s| s.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "$.items",
+                "$.items[*]",
+                "$.items[*].id",
+                "$.items[*].extra",
+                "$.items[*].nested",
+                "$.items[*].nested.a"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_statistics() {
+        let value = json!([{"v": 1}, {"v": null}, {"v": "a"}, {"v": null}]);
+        let stats = detect(&value);
+        let v = stats.iter().find(|s| s.path == "$[*].v").unwrap();
+        assert_eq!(v.types, vec!["number", "null", "string"]);
+        assert_eq!(v.count, 4);
+        assert_eq!(v.null_ratio, 0.5);
+        assert_eq!(v.example, Some(json!(1)));
+
+        let item = stats.iter().find(|s| s.path == "$[*]").unwrap();
+        assert_eq!(item.types, vec!["object"]);
+        assert_eq!(item.example, None);
+    }
+
+    #[test]
+    fn test_sample_limit() {
+        let items: Vec<Value> = (0..100).map(|i| json!({ format!("k{}", i): i })).collect();
+        let mut detector = PathDetector::with_sample_limit(10);
+        detector.observe(&Value::Array(items));
+        let stats = detector.finish();
+
+        let element = stats.iter().find(|s| s.path == "$[*]").unwrap();
+        assert_eq!(element.count, 10);
+        // Samples are spread over the whole array, not just the head
+        assert!(stats.iter().any(|s| s.path == "$[*].k90"));
+        assert!(!stats.iter().any(|s| s.path == "$[*].k1"));
+    }
+
+    #[test]
+    fn test_observe_multiple_documents() {
+        let mut detector = PathDetector::new();
+        detector.observe(&json!({"a": 1}));
+        detector.observe(&json!({"a": null, "b": 2}));
+        let stats = detector.finish();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].count, 2);
+        assert_eq!(stats[0].null_ratio, 0.5);
+    }
+
+    #[test]
+    fn test_serialize_stats() {
+        let stats = detect(&json!({"a": "x"}));
+        let value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(
+            value,
+            json!([{"path": "$.a", "types": ["string"], "count": 1, "null_ratio": 0.0, "example": "x"}])
+        );
+    }
+}
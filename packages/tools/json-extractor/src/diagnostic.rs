@@ -0,0 +1,430 @@
+//! Located diagnostics for documents and path expressions
+//!
+//! Parse errors from the input formats and the path parser are mapped to a
+//! [`Diagnostic`]: where the problem is (byte offset, line and column), what
+//! would have been accepted there, and the offending line with a caret under
+//! it. Editors can underline the spot; the CLI prints [`Diagnostic::render`].
+
+use crate::extract::split_alias;
+use crate::input::InputFormat;
+use crate::lenient::parse_lenient;
+use crate::path::{PathError, PathParser};
+use serde::{Deserialize, Serialize};
+use shard_den_core::ShardDenError;
+use std::fmt;
+
+/// Characters kept on each side of the caret when a line is too long to show whole
+const SNIPPET_CONTEXT: usize = 40;
+
+/// What a diagnostic points into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Document,
+    Path,
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SourceKind::Document => "document",
+            SourceKind::Path => "path",
+        })
+    }
+}
+
+/// A parse error located in its source text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub source: SourceKind,
+    pub message: String,
+    /// Byte offset into the source
+    pub offset: usize,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    /// Tokens that would have been accepted here, when the parser says
+    pub expected: Vec<String>,
+    /// The offending line, then a caret under the column
+    pub snippet: String,
+}
+
+impl Diagnostic {
+    /// Locate `message` at byte `offset` of `text`
+    pub fn new(source: SourceKind, text: &str, offset: usize, message: impl Into<String>) -> Self {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+        let column = text[line_start..offset].chars().count() + 1;
+        let message = message.into();
+        Self {
+            source,
+            expected: expected_tokens(&message),
+            snippet: snippet(&text[line_start..line_end], column),
+            message,
+            offset,
+            line: text[..offset].matches('\n').count() + 1,
+            column,
+        }
+    }
+
+    /// Locate `message` at a 1-based line and byte column of `text`
+    fn at_line_byte(
+        source: SourceKind, text: &str, line: usize, column: usize, message: impl Into<String>,
+    ) -> Self {
+        let line_start = line_start(text, line);
+        let line_len = text[line_start..]
+            .find('\n')
+            .unwrap_or(text.len() - line_start);
+        let offset = line_start + column.saturating_sub(1).min(line_len);
+        Self::new(source, text, offset, message)
+    }
+
+    /// Locate a path parse error, with `offset` the byte offset of the path within `text`
+    pub fn from_path_error(text: &str, offset: usize, err: &PathError) -> Self {
+        Self::new(
+            SourceKind::Path,
+            text,
+            offset + err.position,
+            err.message.as_str(),
+        )
+    }
+
+    /// Locate a serde_json parse error in `text`
+    pub fn from_json_error(text: &str, err: &serde_json::Error) -> Self {
+        Self::at_line_byte(
+            SourceKind::Document,
+            text,
+            err.line(),
+            err.column(),
+            strip_location(&err.to_string()),
+        )
+    }
+
+    /// Convert `offset` and `column` to UTF-16 code units, as JavaScript strings count them
+    pub fn to_utf16(&self, text: &str) -> Self {
+        let offset = self.offset.min(text.len());
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            offset: text[..offset].encode_utf16().count(),
+            column: text[line_start..offset].encode_utf16().count() + 1,
+            ..self.clone()
+        }
+    }
+
+    /// Multi-line report with the snippet in a gutter, optionally colored for a terminal
+    pub fn render(&self, color: bool) -> String {
+        let paint = |code: &str, s: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, s)
+            } else {
+                s.to_string()
+            }
+        };
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let (code, caret) = self.snippet.split_once('\n').unwrap_or((&self.snippet, ""));
+        let mut out = format!(
+            "{}: {}\n{} --> {}:{}:{}\n{} |\n{} | {}\n{} | {}",
+            paint("1;31", "error"),
+            self.message,
+            gutter,
+            self.source,
+            self.line,
+            self.column,
+            gutter,
+            number,
+            code,
+            gutter,
+            paint("31", caret)
+        );
+        if !self.expected.is_empty() {
+            out.push_str(&format!(
+                "\n{} = expected: {}",
+                gutter,
+                quote_tokens(&self.expected)
+            ));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {} line {} column {}",
+            self.message, self.source, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl From<Diagnostic> for ShardDenError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        ShardDenError::invalid_input(diagnostic.to_string())
+    }
+}
+
+/// The first parse error in a document, if any
+pub fn diagnose_document(format: InputFormat, text: &str) -> Option<Diagnostic> {
+    let located = match format {
+        InputFormat::Json => serde_json::from_str::<serde::de::IgnoredAny>(text)
+            .err()
+            .map(|e| Diagnostic::from_json_error(text, &e)),
+        InputFormat::Json5 => parse_lenient(text).err().map(|e| {
+            let offset = line_start(text, e.line)
+                + text[line_start(text, e.line)..]
+                    .chars()
+                    .take(e.column.saturating_sub(1))
+                    .map(char::len_utf8)
+                    .sum::<usize>();
+            Diagnostic::new(SourceKind::Document, text, offset, e.message)
+        }),
+        InputFormat::Yaml => serde_yaml::Deserializer::from_str(text)
+            .find_map(|document| serde_yaml::Value::deserialize(document).err())
+            .map(|e| {
+                let offset = e.location().map_or(0, |l| l.index());
+                Diagnostic::new(
+                    SourceKind::Document,
+                    text,
+                    offset,
+                    strip_location(&e.to_string()),
+                )
+            }),
+        InputFormat::Toml => toml::from_str::<toml::Table>(text).err().map(|e| {
+            let offset = e.span().map_or(0, |span| span.start);
+            Diagnostic::new(SourceKind::Document, text, offset, e.message().trim_end())
+        }),
+        InputFormat::Csv | InputFormat::Xml => None,
+    };
+    // Formats without positions, or errors only found while converting
+    located.or_else(|| {
+        format
+            .parse(text)
+            .err()
+            .map(|e| Diagnostic::new(SourceKind::Document, text, 0, e.to_string()))
+    })
+}
+
+/// Problems with a document and the paths to extract from it, document first
+pub fn diagnose(
+    format: InputFormat, parser: &PathParser, text: &str, paths: &str,
+) -> Vec<Diagnostic> {
+    let document = diagnose_document(format, text).or_else(|| {
+        let value = format.parse(text).ok()?;
+        crate::check_json_depth(&value, 0)
+            .err()
+            .map(|e| Diagnostic::new(SourceKind::Document, text, 0, e))
+    });
+    document
+        .into_iter()
+        .chain(diagnose_paths(parser, paths))
+        .collect()
+}
+
+/// Parse errors in a comma-separated list of paths, located within the whole list
+pub fn diagnose_paths(parser: &PathParser, paths: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut cursor = 0;
+    for entry in crate::parse_paths(paths) {
+        let (_, path) = split_alias(&entry);
+        let found = paths[cursor..].find(path).map(|i| cursor + i);
+        if let Some(start) = found {
+            cursor = start + path.len();
+        }
+        if let Err(err) = parser.validate(path) {
+            diagnostics.push(match found {
+                Some(start) => Diagnostic::from_path_error(paths, start, &err),
+                // Quoting changed the text, so locate within the path alone
+                None => Diagnostic::from_path_error(path, 0, &err),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Byte offset where 1-based `line` starts
+fn line_start(text: &str, line: usize) -> usize {
+    text.split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum()
+}
+
+/// Drop the " at line X column Y" suffix parsers append to their messages
+fn strip_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message.to_string(),
+    }
+}
+
+/// The source line (shortened around the column if long) and a caret line below it
+fn snippet(line: &str, column: usize) -> String {
+    let chars: Vec<char> = line.trim_end_matches('\r').chars().collect();
+    let caret = (column - 1).min(chars.len());
+    let start = caret.saturating_sub(SNIPPET_CONTEXT);
+    let end = (caret + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut code = String::new();
+    let mut pad = String::new();
+    if start > 0 {
+        code.push('…');
+        pad.push(' ');
+    }
+    code.extend(&chars[start..end]);
+    if end < chars.len() {
+        code.push('…');
+    }
+    // Keep tabs so the caret lines up however the terminal renders them
+    pad.extend(
+        chars[start..caret]
+            .iter()
+            .map(|&c| if c == '\t' { '\t' } else { ' ' }),
+    );
+    format!("{}\n{}^", code, pad)
+}
+
+/// Tokens listed after "expected" in a parser message, e.g. `',' or ']'` → `[",", "]"]`
+fn expected_tokens(message: &str) -> Vec<String> {
+    let Some(start) = message
+        .match_indices("expected ")
+        .find(|(i, _)| !message[..*i].ends_with(char::is_alphabetic))
+        .map(|(i, m)| i + m.len())
+    else {
+        return Vec::new();
+    };
+    let list = &message[start..];
+    let end = [" after ", " at ", " in ", " while ", ", found", " but "]
+        .iter()
+        .filter_map(|stop| list.find(stop))
+        .min()
+        .unwrap_or(list.len());
+
+    list[..end]
+        .split(", ")
+        .flat_map(|part| part.split(" or "))
+        .map(|token| {
+            let token = token.trim();
+            let token = token
+                .strip_prefix("a ")
+                .or_else(|| token.strip_prefix("an "))
+                .unwrap_or(token);
+            match token.chars().next() {
+                Some(q @ ('\'' | '`' | '"')) if token.len() > 2 && token.ends_with(q) => {
+                    token[1..token.len() - 1].to_string()
+                }
+                _ => token.to_string(),
+            }
+        })
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// Expected tokens as prose, quoting literal tokens: `member name, '*' or '['`
+fn quote_tokens(tokens: &[String]) -> String {
+    let quoted: Vec<String> = tokens
+        .iter()
+        .map(|token| {
+            if token.chars().all(|c| c.is_alphabetic() || c == ' ') {
+                token.clone()
+            } else {
+                format!("'{}'", token)
+            }
+        })
+        .collect();
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => quoted.concat(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_document() {
+        let text = "{\n  \"a\": 1\n  \"b\": 2\n}";
+        let diagnostic = diagnose_document(InputFormat::Json, text).unwrap();
+        assert_eq!(diagnostic.source, SourceKind::Document);
+        assert_eq!((diagnostic.line, diagnostic.column), (3, 3));
+        assert_eq!(&text[diagnostic.offset..diagnostic.offset + 3], "\"b\"");
+        assert_eq!(diagnostic.expected, vec![",", "}"]);
+        assert_eq!(diagnostic.snippet, "  \"b\": 2\n  ^");
+        assert!(diagnose_document(InputFormat::Json, "[1, 2]").is_none());
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let diagnostic = diagnose_document(InputFormat::Json5, "{a: 1,\n b: }").unwrap();
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 5));
+
+        let diagnostic = diagnose_document(InputFormat::Yaml, "a: 1\nb: [1, 2\n").unwrap();
+        assert!(diagnostic.line >= 2);
+
+        let diagnostic = diagnose_document(InputFormat::Toml, "a = 1\nb = \n").unwrap();
+        assert_eq!(diagnostic.line, 2);
+    }
+
+    #[test]
+    fn test_paths_located_in_list() {
+        let parser = PathParser::new();
+        let paths = "$.ok, name=$.items[1 2], $.fine";
+        let diagnostics = diagnose_paths(&parser, paths);
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.source, SourceKind::Path);
+        assert_eq!(&paths[diagnostic.offset..], "2], $.fine");
+        assert_eq!(diagnostic.column, 22);
+        assert_eq!(diagnostic.expected, vec![",", "]"]);
+        let rendered = diagnostic.render(false);
+        assert!(rendered.contains("1 | $.ok, name=$.items[1 2], $.fine"));
+        assert!(rendered.contains("= expected: ',' or ']'"));
+    }
+
+    #[test]
+    fn test_snippet_window_and_utf16() {
+        let text = format!("[{}x]", "1, ".repeat(50));
+        let diagnostic = diagnose_document(InputFormat::Json, &text).unwrap();
+        let (code, caret) = diagnostic.snippet.split_once('\n').unwrap();
+        assert!(code.starts_with('…') && code.contains('x'));
+        assert_eq!(code.chars().nth(caret.len() - 1), Some('x'));
+
+        let text = "[\"😀\", x]";
+        let diagnostic = diagnose_document(InputFormat::Json, text).unwrap();
+        assert_eq!(diagnostic.column, 7);
+        assert_eq!(diagnostic.to_utf16(text).column, 8);
+    }
+
+    #[test]
+    fn test_expected_tokens() {
+        assert_eq!(
+            expected_tokens("expected member name, '*' or '[' after '..'"),
+            vec!["member name", "*", "["]
+        );
+        assert_eq!(
+            expected_tokens("unexpected character 'x'"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            expected_tokens("expected object key, found '1'"),
+            vec!["object key"]
+        );
+        assert_eq!(expected_tokens("expected value"), vec!["value"]);
+    }
+
+    #[test]
+    fn test_quote_tokens() {
+        let tokens = expected_tokens("expected member name, '*' or '[' after '..'");
+        assert_eq!(quote_tokens(&tokens), "member name, '*' or '['");
+        assert_eq!(quote_tokens(&[",".to_string()]), "','");
+    }
+}
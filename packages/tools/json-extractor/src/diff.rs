@@ -0,0 +1,715 @@
+//! Structural diff and patch
+//!
+//! `Differ` compares two documents and reports changes in an order that can
+//! be applied one after another, so they convert directly into an RFC 6902
+//! JSON Patch. Arrays are matched by position, or by an identity key path
+//! evaluated against each element: elements are then paired by key, and
+//! reordering shows up as `move` operations instead of a cascade of
+//! replacements. `apply_patch` and `apply_merge_patch` go the other way
+//! (RFC 6902 and RFC 7396).
+
+use crate::path::{JsonPath, PathParser};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use shard_den_core::{Result, ShardDenError};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Longest value shown in a summary column before it is truncated
+const SUMMARY_WIDTH: usize = 40;
+
+/// One RFC 6902 JSON Patch operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// What happened at a location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+    Moved,
+}
+
+/// A single difference between two documents
+///
+/// `path` (and `from` for moves) are JSON Pointers valid at the point the
+/// change is applied, after every earlier change in the list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+impl Change {
+    /// The equivalent JSON Patch operation
+    pub fn to_operation(&self) -> PatchOperation {
+        let path = self.path.clone();
+        match self.kind {
+            ChangeKind::Added => PatchOperation::Add {
+                path,
+                value: self.new.clone().unwrap_or_default(),
+            },
+            ChangeKind::Removed => PatchOperation::Remove { path },
+            ChangeKind::Changed => PatchOperation::Replace {
+                path,
+                value: self.new.clone().unwrap_or_default(),
+            },
+            ChangeKind::Moved => PatchOperation::Move {
+                from: self.from.clone().unwrap_or_default(),
+                path,
+            },
+        }
+    }
+}
+
+/// How differences are rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// RFC 6902 JSON Patch
+    #[default]
+    Patch,
+    /// One line per change, optionally colored
+    Text,
+    /// Path, left and right values in columns, followed by totals
+    Summary,
+}
+
+impl DiffFormat {
+    /// Parse a format name: `patch`, `text` or `summary`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "patch" | "json" => Some(DiffFormat::Patch),
+            "text" => Some(DiffFormat::Text),
+            "summary" | "side-by-side" => Some(DiffFormat::Summary),
+            _ => None,
+        }
+    }
+}
+
+/// Identity key of each array element as compact JSON, `None` where the key path matched nothing
+type Keys = Vec<Option<String>>;
+
+/// Compares documents
+#[derive(Debug)]
+pub struct Differ<'a> {
+    parser: &'a PathParser,
+    array_key: Option<Vec<JsonPath>>,
+}
+
+impl<'a> Differ<'a> {
+    /// Create a differ that matches array elements by index
+    pub fn new(parser: &'a PathParser) -> Self {
+        Self {
+            parser,
+            array_key: None,
+        }
+    }
+
+    /// Match array elements by the value at `path` (e.g. `$.id`), evaluated per element
+    ///
+    /// Arrays where no element has the key are still matched by index.
+    pub fn with_array_key(mut self, path: &str) -> Result<Self> {
+        self.array_key = Some(self.parser.parse(path)?);
+        Ok(self)
+    }
+
+    /// Differences that turn `left` into `right`
+    pub fn diff(&self, left: &Value, right: &Value) -> Vec<Change> {
+        let mut changes = Vec::new();
+        self.walk(left, right, "", &mut changes);
+        changes
+    }
+
+    fn walk(&self, left: &Value, right: &Value, path: &str, changes: &mut Vec<Change>) {
+        match (left, right) {
+            (Value::Object(a), Value::Object(b)) => self.diff_objects(a, b, path, changes),
+            (Value::Array(a), Value::Array(b)) => match self.keys(a, b) {
+                Some((keys_a, keys_b)) => self.diff_keyed(a, b, &keys_a, &keys_b, path, changes),
+                None => self.diff_indexed(a, b, path, changes),
+            },
+            _ if left == right => {}
+            _ => changes.push(change(ChangeKind::Changed, path, Some(left), Some(right))),
+        }
+    }
+
+    fn diff_objects(
+        &self, a: &Map<String, Value>, b: &Map<String, Value>, path: &str,
+        changes: &mut Vec<Change>,
+    ) {
+        for (key, old) in a {
+            let child = child_pointer(path, key);
+            match b.get(key) {
+                Some(new) => self.walk(old, new, &child, changes),
+                None => changes.push(change(ChangeKind::Removed, &child, Some(old), None)),
+            }
+        }
+        for (key, new) in b {
+            if !a.contains_key(key) {
+                let child = child_pointer(path, key);
+                changes.push(change(ChangeKind::Added, &child, None, Some(new)));
+            }
+        }
+    }
+
+    fn diff_indexed(&self, a: &[Value], b: &[Value], path: &str, changes: &mut Vec<Change>) {
+        for (i, (old, new)) in a.iter().zip(b).enumerate() {
+            self.walk(old, new, &format!("{}/{}", path, i), changes);
+        }
+        for (i, old) in a.iter().enumerate().skip(b.len()).rev() {
+            let child = format!("{}/{}", path, i);
+            changes.push(change(ChangeKind::Removed, &child, Some(old), None));
+        }
+        for (i, new) in b.iter().enumerate().skip(a.len()) {
+            let child = format!("{}/{}", path, i);
+            changes.push(change(ChangeKind::Added, &child, None, Some(new)));
+        }
+    }
+
+    /// Pair elements by key: drop the unmatched ones, then walk the right
+    /// array in order, moving or adding elements into place
+    fn diff_keyed(
+        &self, a: &[Value], b: &[Value], keys_a: &Keys, keys_b: &Keys, path: &str,
+        changes: &mut Vec<Change>,
+    ) {
+        let wanted: HashSet<&str> = keys_b.iter().flatten().map(String::as_str).collect();
+        let mut seen = HashSet::new();
+        let kept: Vec<bool> = keys_a
+            .iter()
+            .map(|key| {
+                key.as_deref()
+                    .is_some_and(|k| wanted.contains(k) && seen.insert(k))
+            })
+            .collect();
+        for i in (0..a.len()).rev().filter(|&i| !kept[i]) {
+            let child = format!("{}/{}", path, i);
+            changes.push(change(ChangeKind::Removed, &child, Some(&a[i]), None));
+        }
+
+        // Current state of the array: (key, element) for matched elements still to place
+        let mut working: Vec<(Option<&str>, &Value)> = (0..a.len())
+            .filter(|&i| kept[i])
+            .map(|i| (keys_a[i].as_deref(), &a[i]))
+            .collect();
+        let mut placed = HashSet::new();
+        for (i, new) in b.iter().enumerate() {
+            let key = keys_b[i].as_deref().filter(|k| placed.insert(*k));
+            let found = key.and_then(|k| working[i..].iter().position(|(w, _)| *w == Some(k)));
+            let child = format!("{}/{}", path, i);
+            match found {
+                Some(offset) => {
+                    let entry = working.remove(i + offset);
+                    if offset > 0 {
+                        changes.push(Change {
+                            kind: ChangeKind::Moved,
+                            path: child.clone(),
+                            from: Some(format!("{}/{}", path, i + offset)),
+                            old: None,
+                            new: None,
+                        });
+                    }
+                    working.insert(i, (None, entry.1));
+                    self.walk(entry.1, new, &child, changes);
+                }
+                None => {
+                    working.insert(i, (None, new));
+                    changes.push(change(ChangeKind::Added, &child, None, Some(new)));
+                }
+            }
+        }
+    }
+
+    /// Identity keys of both arrays' elements, if key matching applies
+    fn keys(&self, a: &[Value], b: &[Value]) -> Option<(Keys, Keys)> {
+        let segments = self.array_key.as_ref()?;
+        let key_of = |value: &Value| {
+            self.parser
+                .traverse(value, segments)
+                .ok()
+                .and_then(|matches| matches.first().map(|key| key.to_string()))
+        };
+        let keys_a: Vec<_> = a.iter().map(key_of).collect();
+        let keys_b: Vec<_> = b.iter().map(key_of).collect();
+        if keys_a.iter().chain(&keys_b).all(Option::is_none) {
+            return None;
+        }
+        Some((keys_a, keys_b))
+    }
+}
+
+fn change(kind: ChangeKind, path: &str, old: Option<&Value>, new: Option<&Value>) -> Change {
+    Change {
+        kind,
+        path: path.to_string(),
+        from: None,
+        old: old.cloned(),
+        new: new.cloned(),
+    }
+}
+
+fn child_pointer(path: &str, key: &str) -> String {
+    format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Changes as an RFC 6902 JSON Patch document
+pub fn to_patch(changes: &[Change]) -> Value {
+    Value::Array(
+        changes
+            .iter()
+            .map(|c| serde_json::to_value(c.to_operation()).unwrap_or_default())
+            .collect(),
+    )
+}
+
+/// Render changes in the given format; `color` adds ANSI colors to text output
+pub fn render(changes: &[Change], format: DiffFormat, color: bool) -> Result<String> {
+    match format {
+        DiffFormat::Patch => Ok(serde_json::to_string_pretty(&to_patch(changes))?),
+        DiffFormat::Text => Ok(render_text(changes, color)),
+        DiffFormat::Summary => Ok(render_summary(changes)),
+    }
+}
+
+fn render_text(changes: &[Change], color: bool) -> String {
+    let mut output = String::new();
+    for c in changes {
+        let (sign, code) = match c.kind {
+            ChangeKind::Added => ('+', "32"),
+            ChangeKind::Removed => ('-', "31"),
+            ChangeKind::Changed => ('~', "33"),
+            ChangeKind::Moved => ('>', "36"),
+        };
+        let path = if c.path.is_empty() { "/" } else { &c.path };
+        let line = match c.kind {
+            ChangeKind::Added => format!("{} {}: {}", sign, path, compact(&c.new)),
+            ChangeKind::Removed => format!("{} {}: {}", sign, path, compact(&c.old)),
+            ChangeKind::Changed => format!(
+                "{} {}: {} -> {}",
+                sign,
+                path,
+                compact(&c.old),
+                compact(&c.new)
+            ),
+            ChangeKind::Moved => format!(
+                "{} {}: moved from {}",
+                sign,
+                path,
+                c.from.as_deref().unwrap_or_default()
+            ),
+        };
+        if color {
+            let _ = writeln!(output, "\x1b[{}m{}\x1b[0m", code, line);
+        } else {
+            let _ = writeln!(output, "{}", line);
+        }
+    }
+    output
+}
+
+fn render_summary(changes: &[Change]) -> String {
+    let rows: Vec<[String; 3]> = changes
+        .iter()
+        .map(|c| {
+            let path = if c.path.is_empty() { "/" } else { &c.path };
+            let (left, right) = match c.kind {
+                ChangeKind::Added => ("(absent)".to_string(), truncate(compact(&c.new))),
+                ChangeKind::Removed => (truncate(compact(&c.old)), "(absent)".to_string()),
+                ChangeKind::Changed => (truncate(compact(&c.old)), truncate(compact(&c.new))),
+                ChangeKind::Moved => (
+                    format!("(at {})", c.from.as_deref().unwrap_or_default()),
+                    "(moved)".to_string(),
+                ),
+            };
+            [path.to_string(), left, right]
+        })
+        .collect();
+
+    let header = ["PATH".to_string(), "LEFT".to_string(), "RIGHT".to_string()];
+    let width = |col: usize| {
+        rows.iter()
+            .chain(std::iter::once(&header))
+            .map(|row| row[col].chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (path_width, left_width) = (width(0), width(1));
+
+    let mut output = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let _ = writeln!(
+            output,
+            "{:path_width$}  {:left_width$}  {}",
+            row[0], row[1], row[2]
+        );
+    }
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    let _ = writeln!(
+        output,
+        "\n{} difference(s): {} added, {} removed, {} changed, {} moved",
+        changes.len(),
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Changed),
+        count(ChangeKind::Moved)
+    );
+    output
+}
+
+fn compact(value: &Option<Value>) -> String {
+    value.as_ref().map(Value::to_string).unwrap_or_default()
+}
+
+fn truncate(text: String) -> String {
+    if text.chars().count() <= SUMMARY_WIDTH {
+        return text;
+    }
+    let mut short: String = text.chars().take(SUMMARY_WIDTH - 3).collect();
+    short.push_str("...");
+    short
+}
+
+/// Parse an RFC 6902 JSON Patch document
+pub fn parse_patch(patch: &Value) -> Result<Vec<PatchOperation>> {
+    serde_json::from_value(patch.clone())
+        .map_err(|e| ShardDenError::invalid_input(format!("Invalid JSON Patch: {}", e)))
+}
+
+/// Apply an RFC 6902 JSON Patch; the document is left untouched if any operation fails
+pub fn apply_patch(document: &Value, operations: &[PatchOperation]) -> Result<Value> {
+    let mut result = document.clone();
+    for (i, operation) in operations.iter().enumerate() {
+        apply_operation(&mut result, operation).map_err(|message| {
+            ShardDenError::invalid_input(format!("Patch operation {}: {}", i, message))
+        })?;
+    }
+    Ok(result)
+}
+
+fn apply_operation(
+    document: &mut Value, operation: &PatchOperation,
+) -> std::result::Result<(), String> {
+    match operation {
+        PatchOperation::Add { path, value } => add(document, path, value.clone()),
+        PatchOperation::Remove { path } => remove(document, path).map(drop),
+        PatchOperation::Replace { path, value } => {
+            *pointer_mut(document, path)? = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(format!("cannot move {} into its own child {}", from, path));
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = pointer_mut(document, from)?.clone();
+            add(document, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            let actual = pointer_mut(document, path)?;
+            if actual == value {
+                Ok(())
+            } else {
+                Err(format!("test failed at {}: found {}", path, actual))
+            }
+        }
+    }
+}
+
+/// Split a JSON Pointer into its unescaped reference tokens
+fn tokens(pointer: &str) -> std::result::Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(format!("invalid JSON Pointer '{}'", pointer));
+    };
+    Ok(rest
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Array index token: digits without leading zeros
+fn index(token: &str) -> Option<usize> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    valid.then(|| token.parse().ok()).flatten()
+}
+
+fn pointer_mut<'v>(
+    document: &'v mut Value, pointer: &str,
+) -> std::result::Result<&'v mut Value, String> {
+    tokens(pointer)?
+        .iter()
+        .try_fold(document, |node, token| match node {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(arr) => index(token).and_then(|i| arr.get_mut(i)),
+            _ => None,
+        })
+        .ok_or_else(|| format!("path {} does not exist", pointer))
+}
+
+/// The parent container of `pointer` and the last token, or `None` for the root
+fn parent_mut<'v>(
+    document: &'v mut Value, pointer: &str,
+) -> std::result::Result<Option<(&'v mut Value, String)>, String> {
+    let mut tokens = tokens(pointer)?;
+    let Some(last) = tokens.pop() else {
+        return Ok(None);
+    };
+    let parent = pointer_mut(document, &pointer[..pointer.rfind('/').unwrap_or(0)])?;
+    Ok(Some((parent, last)))
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> std::result::Result<(), String> {
+    let Some((parent, token)) = parent_mut(document, path)? else {
+        *document = value;
+        return Ok(());
+    };
+    match parent {
+        Value::Object(map) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Value::Array(arr) if token == "-" => {
+            arr.push(value);
+            Ok(())
+        }
+        Value::Array(arr) => match index(&token).filter(|&i| i <= arr.len()) {
+            Some(i) => {
+                arr.insert(i, value);
+                Ok(())
+            }
+            None => Err(format!("index out of bounds at {}", path)),
+        },
+        _ => Err(format!("parent of {} is not a container", path)),
+    }
+}
+
+fn remove(document: &mut Value, path: &str) -> std::result::Result<Value, String> {
+    let Some((parent, token)) = parent_mut(document, path)? else {
+        return Err("cannot remove the document root".to_string());
+    };
+    let removed = match parent {
+        Value::Object(map) => map.remove(&token),
+        Value::Array(arr) => index(&token)
+            .filter(|&i| i < arr.len())
+            .map(|i| arr.remove(i)),
+        _ => None,
+    };
+    removed.ok_or_else(|| format!("path {} does not exist", path))
+}
+
+/// Apply an RFC 7396 JSON Merge Patch
+pub fn apply_merge_patch(document: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *document = patch.clone();
+        return;
+    };
+    if !document.is_object() {
+        *document = Value::Object(Map::new());
+    }
+    if let Value::Object(map) = document {
+        for (key, value) in patch {
+            if value.is_null() {
+                map.remove(key);
+            } else {
+                apply_merge_patch(map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(differ: &Differ, left: Value, right: Value) -> Vec<Change> {
+        let changes = differ.diff(&left, &right);
+        let operations = parse_patch(&to_patch(&changes)).unwrap();
+        assert_eq!(apply_patch(&left, &operations).unwrap(), right);
+        changes
+    }
+
+    #[test]
+    fn test_diff_objects_and_scalars() {
+        let parser = PathParser::new();
+        let differ = Differ::new(&parser);
+        let changes = round_trip(
+            &differ,
+            json!({"a": 1, "b": {"c": true, "d/e": 1}, "gone": null}),
+            json!({"a": 2, "b": {"c": true, "d/e": 2}, "new": [1]}),
+        );
+        assert_eq!(
+            to_patch(&changes),
+            json!([
+                {"op": "replace", "path": "/a", "value": 2},
+                {"op": "replace", "path": "/b/d~1e", "value": 2},
+                {"op": "remove", "path": "/gone"},
+                {"op": "add", "path": "/new", "value": [1]}
+            ])
+        );
+        assert!(differ
+            .diff(&json!({"a": [1]}), &json!({"a": [1]}))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_diff_arrays_by_index() {
+        let parser = PathParser::new();
+        let differ = Differ::new(&parser);
+        let changes = round_trip(&differ, json!([1, 2, 3, 4]), json!([1, 5]));
+        assert_eq!(
+            to_patch(&changes),
+            json!([
+                {"op": "replace", "path": "/1", "value": 5},
+                {"op": "remove", "path": "/3"},
+                {"op": "remove", "path": "/2"}
+            ])
+        );
+        round_trip(&differ, json!([]), json!([1, [2]]));
+    }
+
+    #[test]
+    fn test_diff_arrays_by_key() {
+        let parser = PathParser::new();
+        let differ = Differ::new(&parser).with_array_key("$.id").unwrap();
+        let left = json!({"users": [
+            {"id": 1, "name": "a"},
+            {"id": 2, "name": "b"},
+            {"id": 3, "name": "c"}
+        ]});
+        let right = json!({"users": [
+            {"id": 3, "name": "c"},
+            {"id": 1, "name": "A"},
+            {"id": 4, "name": "d"}
+        ]});
+        let changes = round_trip(&differ, left.clone(), right.clone());
+        assert_eq!(
+            to_patch(&changes),
+            json!([
+                {"op": "remove", "path": "/users/1"},
+                {"op": "move", "from": "/users/1", "path": "/users/0"},
+                {"op": "replace", "path": "/users/1/name", "value": "A"},
+                {"op": "add", "path": "/users/2", "value": {"id": 4, "name": "d"}}
+            ])
+        );
+
+        // Index matching sees every element as changed
+        let by_index = Differ::new(&parser).diff(&left, &right);
+        assert_eq!(by_index.len(), 6);
+
+        // Arrays without keys, duplicate keys and keyless elements still round-trip
+        round_trip(&differ, json!({"tags": ["a", "b"]}), json!({"tags": ["b"]}));
+        round_trip(
+            &differ,
+            json!([{"id": 1}, {"id": 1, "x": 1}, {"v": 1}]),
+            json!([{"v": 2}, {"id": 1, "x": 2}, {"id": 1}]),
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let parser = PathParser::new();
+        let changes = Differ::new(&parser).diff(
+            &json!({"a": 1, "b": "x", "c": [1]}),
+            &json!({"a": 2, "c": [1, 2]}),
+        );
+        assert_eq!(
+            render(&changes, DiffFormat::Text, false).unwrap(),
+            "~ /a: 1 -> 2\n- /b: \"x\"\n+ /c/1: 2\n"
+        );
+        assert!(render(&changes, DiffFormat::Text, true)
+            .unwrap()
+            .starts_with("\x1b[33m~ /a"));
+
+        let summary = render(&changes, DiffFormat::Summary, false).unwrap();
+        assert_eq!(
+            summary,
+            "PATH  LEFT      RIGHT\n\
+             /a    1         2\n\
+             /b    \"x\"       (absent)\n\
+             /c/1  (absent)  2\n\
+             \n3 difference(s): 1 added, 1 removed, 1 changed, 0 moved\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_operations() {
+        let doc = json!({"a": {"b": [1, 2]}, "c": "x"});
+        let patch = json!([
+            {"op": "test", "path": "/c", "value": "x"},
+            {"op": "add", "path": "/a/b/-", "value": 3},
+            {"op": "add", "path": "/a/b/0", "value": 0},
+            {"op": "copy", "from": "/c", "path": "/d"},
+            {"op": "move", "from": "/c", "path": "/a/c"},
+            {"op": "replace", "path": "/d", "value": "y"},
+            {"op": "remove", "path": "/a/b/1"}
+        ]);
+        let result = apply_patch(&doc, &parse_patch(&patch).unwrap()).unwrap();
+        assert_eq!(result, json!({"a": {"b": [0, 2, 3], "c": "x"}, "d": "y"}));
+    }
+
+    #[test]
+    fn test_apply_patch_errors() {
+        let doc = json!({"a": [1]});
+        let apply = |patch: Value| apply_patch(&doc, &parse_patch(&patch)?);
+        let err = apply(json!([{"op": "test", "path": "/a/0", "value": 2}])).unwrap_err();
+        assert!(err.to_string().contains("Patch operation 0: test failed"));
+        assert!(apply(json!([{"op": "remove", "path": "/b"}])).is_err());
+        assert!(apply(json!([{"op": "add", "path": "/a/5", "value": 1}])).is_err());
+        assert!(apply(json!([{"op": "add", "path": "/a/01", "value": 1}])).is_err());
+        assert!(apply(json!([{"op": "move", "from": "/a", "path": "/a/0"}])).is_err());
+        assert!(apply(json!([{"op": "frobnicate", "path": "/a"}])).is_err());
+        assert!(apply(json!([{"op": "add", "path": "a", "value": 1}])).is_err());
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        // Examples from RFC 7396 appendix A
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+        ];
+        for (mut doc, patch, expected) in cases {
+            apply_merge_patch(&mut doc, &patch);
+            assert_eq!(doc, expected);
+        }
+    }
+}
@@ -1,9 +1,10 @@
 //! JSON extraction logic
 
-use jsonpath_rust::parser::JsonPath;
+use crate::path::{NormalizedPath, PathMode, PathParser};
+use crate::span::{Span, SpanIndex};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use shard_den_core::Result;
+use serde_json::{Map, Value};
+use shard_den_core::{Result, ShardDenError};
 
 /// Result of an extraction operation
 #[derive(Debug, Clone)]
@@ -11,43 +12,203 @@ pub struct ExtractResult {
     pub values: Vec<ExtractedValue>,
 }
 
+impl ExtractResult {
+    /// Whether any path was given an alias
+    pub fn has_aliases(&self) -> bool {
+        self.values.iter().any(|v| v.alias.is_some())
+    }
+
+    /// Column names of [`to_rows`](Self::to_rows), in the order the paths were given
+    pub fn columns(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .map(|v| v.alias.clone().unwrap_or_else(|| v.path.clone()))
+            .collect()
+    }
+
+    /// Zip the matches of every path into row objects keyed by alias
+    ///
+    /// Row `i` holds the `i`-th match of each path; paths with fewer matches
+    /// contribute `null`. Paths without an alias are keyed by the path itself.
+    pub fn to_rows(&self) -> Vec<Value> {
+        let columns: Vec<(&str, &[Value])> = self
+            .values
+            .iter()
+            .map(|v| {
+                let key = v.alias.as_deref().unwrap_or(&v.path);
+                let matches = v.value.as_array().map_or(&[][..], Vec::as_slice);
+                (key, matches)
+            })
+            .collect();
+        let rows = columns.iter().map(|(_, m)| m.len()).max().unwrap_or(0);
+
+        (0..rows)
+            .map(|i| {
+                let row: Map<String, Value> = columns
+                    .iter()
+                    .map(|(key, matches)| {
+                        (
+                            key.to_string(),
+                            matches.get(i).cloned().unwrap_or(Value::Null),
+                        )
+                    })
+                    .collect();
+                Value::Object(row)
+            })
+            .collect()
+    }
+
+    /// Record the source span of every match, looked up by JSON Pointer in `index`
+    pub fn attach_spans(&mut self, index: &SpanIndex) {
+        for location in self.values.iter_mut().flat_map(|v| &mut v.locations) {
+            location.span = index.get(&location.pointer).map(|node| node.span);
+        }
+    }
+
+    /// Every match as a `{"path", "pointer", "value"}` object
+    ///
+    /// Matches of aliased paths also carry their `alias`, and matches with a
+    /// known source position their `span`.
+    pub fn to_located(&self) -> Vec<Value> {
+        self.values
+            .iter()
+            .flat_map(|extracted| {
+                let matches = extracted.value.as_array().map_or(&[][..], Vec::as_slice);
+                extracted
+                    .locations
+                    .iter()
+                    .zip(matches)
+                    .map(|(location, value)| {
+                        let mut entry = Map::new();
+                        if let Some(alias) = &extracted.alias {
+                            entry.insert("alias".to_string(), Value::String(alias.clone()));
+                        }
+                        entry.insert("path".to_string(), Value::String(location.path.clone()));
+                        entry.insert(
+                            "pointer".to_string(),
+                            Value::String(location.pointer.clone()),
+                        );
+                        if let Some(span) = &location.span {
+                            entry.insert(
+                                "span".to_string(),
+                                serde_json::to_value(span).unwrap_or(Value::Null),
+                            );
+                        }
+                        entry.insert("value".to_string(), value.clone());
+                        Value::Object(entry)
+                    })
+            })
+            .collect()
+    }
+}
+
 /// A single extracted value
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractedValue {
+    /// The query as given
     pub path: String,
+    /// Column name given with `alias=path` syntax
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Array of every match, in document order
     pub value: Value,
+    /// Where each match in `value` was found
+    #[serde(default)]
+    pub locations: Vec<MatchLocation>,
+}
+
+/// The concrete location of one match
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchLocation {
+    /// RFC 9535 normalized path, e.g. `$['users'][3]['email']`
+    pub path: String,
+    /// RFC 6901 JSON Pointer, e.g. `/users/3/email`
+    pub pointer: String,
+    /// Where the match sits in the source text, when spans were tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+impl From<&NormalizedPath> for MatchLocation {
+    fn from(path: &NormalizedPath) -> Self {
+        Self {
+            path: path.to_string(),
+            pointer: path.to_json_pointer(),
+            span: None,
+        }
+    }
+}
+
+/// Split an `alias=path` entry into its alias and path
+///
+/// Entries starting with `$` are plain paths, so `==` inside filters is never
+/// mistaken for an alias.
+pub fn split_alias(entry: &str) -> (Option<&str>, &str) {
+    let entry = entry.trim();
+    if entry.starts_with('$') {
+        return (None, entry);
+    }
+    match entry.split_once('=') {
+        Some((alias, path)) if !alias.trim().is_empty() => (Some(alias.trim()), path.trim()),
+        _ => (None, entry),
+    }
 }
 
 /// Main extractor implementation
 #[derive(Debug, Default)]
-pub struct Extractor;
+pub struct Extractor {
+    parser: PathParser,
+}
 
 impl Extractor {
     /// Create a new extractor
     pub fn new() -> Self {
-        Self
+        Self {
+            parser: PathParser::new(),
+        }
+    }
+
+    /// Create an extractor that parses paths in the given dialect
+    pub fn with_mode(mode: PathMode) -> Self {
+        Self {
+            parser: PathParser::with_mode(mode),
+        }
     }
 
     /// Extract values from JSON using the given paths
+    ///
+    /// Each entry is either a path or `alias=path`.
     pub fn extract(&self, json: &Value, paths: &[String]) -> Result<ExtractResult> {
         let values = paths
             .iter()
             .map(|path| self.extract_single(json, path))
             .collect::<Result<Vec<_>>>()?;
 
+        let mut aliases: Vec<&str> = values.iter().filter_map(|v| v.alias.as_deref()).collect();
+        aliases.sort_unstable();
+        if let Some(pair) = aliases.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(ShardDenError::invalid_input(format!(
+                "Duplicate alias '{}'",
+                pair[0]
+            )));
+        }
+
         Ok(ExtractResult { values })
     }
 
-    fn extract_single(&self, json: &Value, path: &str) -> Result<ExtractedValue> {
-        let json_path = JsonPath::try_from(path).map_err(|e| {
-            shard_den_core::ShardDenError::invalid_input(format!("JSONPath error: {}", e))
-        })?;
-
-        let result = json_path.find(json);
+    fn extract_single(&self, json: &Value, entry: &str) -> Result<ExtractedValue> {
+        let (alias, path) = split_alias(entry);
+        let segments = self.parser.parse(path)?;
+        let matches = self.parser.locate(json, &segments)?;
 
         Ok(ExtractedValue {
             path: path.to_string(),
-            value: result,
+            alias: alias.map(str::to_string),
+            locations: matches
+                .iter()
+                .map(|(p, _)| MatchLocation::from(p))
+                .collect(),
+            value: Value::Array(matches.into_iter().map(|(_, v)| v.clone()).collect()),
         })
     }
 }
@@ -74,7 +235,7 @@ mod tests {
         let extract_result = result.unwrap();
         assert_eq!(extract_result.values.len(), 1);
         let extracted = &extract_result.values[0].value;
-        // find() wraps result in array
+        // Matches are always wrapped in an array
         let arr = extracted.as_array().unwrap();
         // Should get [1, 2, 3]
         assert_eq!(arr.len(), 3);
@@ -132,4 +293,117 @@ mod tests {
         let result = extractor.extract(&json, &["".to_string()]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_extract_no_match_is_empty() {
+        let json: Value = serde_json::from_str(r#"{"name": "test"}"#).unwrap();
+        let extractor = Extractor::new();
+        let result = extractor
+            .extract(&json, &["$.missing".to_string()])
+            .unwrap();
+        assert_eq!(result.values[0].value, Value::Array(vec![]));
+    }
+
+    #[test]
+    fn test_extract_strict_mode() {
+        let json: Value = serde_json::from_str(r#"{"a-b": 1, "c": [1, 2]}"#).unwrap();
+        let lenient = Extractor::new();
+        let strict = Extractor::with_mode(PathMode::Rfc9535);
+        assert!(lenient.extract(&json, &["$.a-b".to_string()]).is_ok());
+        assert!(strict.extract(&json, &["$.a-b".to_string()]).is_err());
+        let result = strict
+            .extract(&json, &["$[?length(@) == 2]".to_string()])
+            .unwrap();
+        assert_eq!(result.values[0].value, serde_json::json!([[1, 2]]));
+    }
+
+    #[test]
+    fn test_extract_slice_and_union() {
+        let json: Value = serde_json::from_str(r#"{"a": [0, 1, 2, 3, 4]}"#).unwrap();
+        let extractor = Extractor::new();
+        let result = extractor
+            .extract(&json, &["$.a[1:4:2]".to_string(), "$.a[0,-1]".to_string()])
+            .unwrap();
+        assert_eq!(result.values[0].value, serde_json::json!([1, 3]));
+        assert_eq!(result.values[1].value, serde_json::json!([0, 4]));
+    }
+
+    #[test]
+    fn test_split_alias() {
+        assert_eq!(split_alias("name=$.a"), (Some("name"), "$.a"));
+        assert_eq!(split_alias(" name = $.a "), (Some("name"), "$.a"));
+        assert_eq!(split_alias("$[?@.a == 1]"), (None, "$[?@.a == 1]"));
+        assert_eq!(split_alias("=$.a"), (None, "=$.a"));
+    }
+
+    #[test]
+    fn test_extract_aliases_to_rows() {
+        let json: Value =
+            serde_json::from_str(r#"{"users": [{"name": "a", "email": "a@x"}, {"name": "b"}]}"#)
+                .unwrap();
+        let extractor = Extractor::new();
+        let result = extractor
+            .extract(
+                &json,
+                &[
+                    "name=$.users[*].name".to_string(),
+                    "email=$.users[*].email".to_string(),
+                ],
+            )
+            .unwrap();
+
+        assert!(result.has_aliases());
+        assert_eq!(result.values[0].alias.as_deref(), Some("name"));
+        assert_eq!(result.values[0].path, "$.users[*].name");
+        assert_eq!(result.columns(), vec!["name", "email"]);
+        assert_eq!(
+            result.to_rows(),
+            vec![
+                serde_json::json!({"name": "a", "email": "a@x"}),
+                serde_json::json!({"name": "b", "email": null}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_duplicate_alias() {
+        let json: Value = serde_json::from_str(r#"{"a": 1}"#).unwrap();
+        let extractor = Extractor::new();
+        let result = extractor.extract(&json, &["x=$.a".to_string(), "x=$.a".to_string()]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_locations() {
+        let json: Value =
+            serde_json::from_str(r#"{"users": [{"email": "a@x"}, {"email": "b@x"}]}"#).unwrap();
+        let extractor = Extractor::new();
+        let result = extractor
+            .extract(&json, &["$.users[*].email".to_string()])
+            .unwrap();
+
+        let locations = &result.values[0].locations;
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[1].path, "$['users'][1]['email']");
+        assert_eq!(locations[1].pointer, "/users/1/email");
+        assert_eq!(
+            result.to_located()[0],
+            serde_json::json!({"path": "$['users'][0]['email']", "pointer": "/users/0/email", "value": "a@x"})
+        );
+    }
+
+    #[test]
+    fn test_attach_spans() {
+        let text = r#"{"users": [{"email": "a@x"}, {"email": "b@x"}]}"#;
+        let json: Value = serde_json::from_str(text).unwrap();
+        let mut result = Extractor::new()
+            .extract(&json, &["$.users[1].email".to_string()])
+            .unwrap();
+        assert_eq!(result.values[0].locations[0].span, None);
+
+        result.attach_spans(&SpanIndex::new(text).unwrap());
+        let span = result.values[0].locations[0].span.unwrap();
+        assert_eq!(&text[span.start..span.end], "\"b@x\"");
+        assert_eq!(result.to_located()[0]["span"]["column"], 40);
+    }
 }
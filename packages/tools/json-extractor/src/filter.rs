@@ -0,0 +1,423 @@
+//! Filter expressions used by `[?...]` selectors
+
+use crate::path::{self, JsonPath};
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A parsed filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    /// `a || b`
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    /// `a && b`
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    /// `!a`
+    Not(Box<FilterExpr>),
+    /// `@.price > 10`
+    Compare(Operand, CompareOp, Operand),
+    /// `@.isbn` - true when the query selects at least one node
+    Exists(Query),
+    /// `match(@.date, '1974-05-..')` - a function used as a test
+    Function(FunctionCall),
+}
+
+/// One side of a comparison
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Literal(Value),
+    Query(Query),
+    Function(FunctionCall),
+}
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Where an embedded query starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryRoot {
+    /// `$` - the document root
+    Root,
+    /// `@` - the node currently being filtered
+    Current,
+}
+
+/// A query embedded in a filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub root: QueryRoot,
+    pub segments: Vec<JsonPath>,
+}
+
+/// A call to one of the RFC 9535 function extensions
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCall {
+    pub function: Function,
+    pub args: Vec<Operand>,
+}
+
+/// Function extensions defined by RFC 9535 section 2.4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+/// Declared types of function parameters and results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionType {
+    /// A single JSON value or Nothing
+    Value,
+    /// True or false
+    Logical,
+    /// A list of nodes produced by a query
+    Nodes,
+}
+
+impl Function {
+    /// Look up a function by its name in a path expression
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "length" => Some(Function::Length),
+            "count" => Some(Function::Count),
+            "match" => Some(Function::Match),
+            "search" => Some(Function::Search),
+            "value" => Some(Function::Value),
+            _ => None,
+        }
+    }
+
+    /// Name used in path expressions
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Length => "length",
+            Function::Count => "count",
+            Function::Match => "match",
+            Function::Search => "search",
+            Function::Value => "value",
+        }
+    }
+
+    /// Declared parameter types
+    pub fn param_types(&self) -> &'static [FunctionType] {
+        match self {
+            Function::Length => &[FunctionType::Value],
+            Function::Count | Function::Value => &[FunctionType::Nodes],
+            Function::Match | Function::Search => &[FunctionType::Value, FunctionType::Value],
+        }
+    }
+
+    /// Declared result type
+    pub fn result_type(&self) -> FunctionType {
+        match self {
+            Function::Length | Function::Count | Function::Value => FunctionType::Value,
+            Function::Match | Function::Search => FunctionType::Logical,
+        }
+    }
+}
+
+/// Result of evaluating a function call
+enum Evaluated<'a> {
+    Value(Option<Cow<'a, Value>>),
+    Logical(bool),
+}
+
+impl FunctionCall {
+    fn evaluate<'a>(&'a self, root: &'a Value, current: &'a Value) -> Evaluated<'a> {
+        let value_arg = |i: usize| self.args[i].evaluate(root, current);
+        let nodes_arg = |i: usize| match &self.args[i] {
+            Operand::Query(query) => query.select(root, current),
+            _ => Vec::new(),
+        };
+
+        match self.function {
+            Function::Length => {
+                let length = match value_arg(0).as_deref() {
+                    Some(Value::String(s)) => Some(s.chars().count()),
+                    Some(Value::Array(arr)) => Some(arr.len()),
+                    Some(Value::Object(obj)) => Some(obj.len()),
+                    _ => None,
+                };
+                Evaluated::Value(length.map(|n| Cow::Owned(Value::from(n))))
+            }
+            Function::Count => Evaluated::Value(Some(Cow::Owned(Value::from(nodes_arg(0).len())))),
+            Function::Value => {
+                let nodes = nodes_arg(0);
+                Evaluated::Value((nodes.len() == 1).then(|| Cow::Borrowed(nodes[0])))
+            }
+            Function::Match | Function::Search => {
+                let anchored = self.function == Function::Match;
+                let matched = match (value_arg(0).as_deref(), value_arg(1).as_deref()) {
+                    (Some(Value::String(text)), Some(Value::String(pattern))) => {
+                        regex_matches(pattern, anchored, text)
+                    }
+                    _ => false,
+                };
+                Evaluated::Logical(matched)
+            }
+        }
+    }
+}
+
+impl Query {
+    /// A singular query selects at most one node (only name and index segments)
+    pub fn is_singular(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|s| matches!(s, JsonPath::Key(_) | JsonPath::Index(_)))
+    }
+
+    /// Evaluate the query against the document root and the current node
+    pub fn select<'a>(&self, root: &'a Value, current: &'a Value) -> Vec<&'a Value> {
+        let start = match self.root {
+            QueryRoot::Root => root,
+            QueryRoot::Current => current,
+        };
+        path::select(root, start, &self.segments)
+    }
+}
+
+impl FilterExpr {
+    /// Test the expression against `current`, with `root` as the document root
+    pub fn test(&self, root: &Value, current: &Value) -> bool {
+        match self {
+            FilterExpr::Or(a, b) => a.test(root, current) || b.test(root, current),
+            FilterExpr::And(a, b) => a.test(root, current) && b.test(root, current),
+            FilterExpr::Not(inner) => !inner.test(root, current),
+            FilterExpr::Exists(query) => !query.select(root, current).is_empty(),
+            FilterExpr::Function(call) => match call.evaluate(root, current) {
+                Evaluated::Logical(result) => result,
+                Evaluated::Value(value) => value.is_some(),
+            },
+            FilterExpr::Compare(lhs, op, rhs) => {
+                let lhs = lhs.evaluate(root, current);
+                let rhs = rhs.evaluate(root, current);
+                compare(lhs.as_deref(), *op, rhs.as_deref())
+            }
+        }
+    }
+}
+
+impl Operand {
+    /// Resolve the operand to a single value, `None` meaning "Nothing"
+    fn evaluate<'a>(&'a self, root: &'a Value, current: &'a Value) -> Option<Cow<'a, Value>> {
+        match self {
+            Operand::Literal(value) => Some(Cow::Borrowed(value)),
+            Operand::Query(query) => {
+                let nodes = query.select(root, current);
+                (nodes.len() == 1).then(|| Cow::Borrowed(nodes[0]))
+            }
+            Operand::Function(call) => match call.evaluate(root, current) {
+                Evaluated::Value(value) => value,
+                _ => None,
+            },
+        }
+    }
+}
+
+fn compare(lhs: Option<&Value>, op: CompareOp, rhs: Option<&Value>) -> bool {
+    match op {
+        CompareOp::Eq => equals(lhs, rhs),
+        CompareOp::Ne => !equals(lhs, rhs),
+        CompareOp::Lt => less_than(lhs, rhs),
+        CompareOp::Le => less_than(lhs, rhs) || equals(lhs, rhs),
+        CompareOp::Gt => less_than(rhs, lhs),
+        CompareOp::Ge => less_than(rhs, lhs) || equals(lhs, rhs),
+    }
+}
+
+fn equals(lhs: Option<&Value>, rhs: Option<&Value>) -> bool {
+    match (lhs, rhs) {
+        (None, None) => true,
+        (Some(a), Some(b)) => values_equal(a, b),
+        _ => false,
+    }
+}
+
+fn less_than(lhs: Option<&Value>, rhs: Option<&Value>) -> bool {
+    match (lhs, rhs) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            compare_numbers(a, b) == Some(Ordering::Less)
+        }
+        (Some(Value::String(a)), Some(Value::String(b))) => a < b,
+        _ => false,
+    }
+}
+
+/// Deep equality where numbers compare by numeric value (`1 == 1.0`)
+pub(crate) fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => compare_numbers(x, y) == Some(Ordering::Equal),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| values_equal(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|w| values_equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+fn compare_numbers(a: &serde_json::Number, b: &serde_json::Number) -> Option<Ordering> {
+    if let (Some(x), Some(y)) = (a.as_i64(), b.as_i64()) {
+        return Some(x.cmp(&y));
+    }
+    if let (Some(x), Some(y)) = (a.as_u64(), b.as_u64()) {
+        return Some(x.cmp(&y));
+    }
+    a.as_f64()?.partial_cmp(&b.as_f64()?)
+}
+
+/// Compiled I-Regexp patterns, keyed by pattern and anchoring
+type RegexCache = HashMap<(String, bool), Option<Regex>>;
+
+const REGEX_CACHE_LIMIT: usize = 128;
+
+thread_local! {
+    static REGEX_CACHE: RefCell<RegexCache> = RefCell::new(HashMap::new());
+}
+
+/// Test `text` against an I-Regexp (RFC 9485) pattern; invalid patterns never match
+fn regex_matches(pattern: &str, anchored: bool, text: &str) -> bool {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= REGEX_CACHE_LIMIT {
+            cache.clear();
+        }
+        cache
+            .entry((pattern.to_string(), anchored))
+            .or_insert_with(|| compile_iregexp(pattern, anchored))
+            .as_ref()
+            .is_some_and(|re| re.is_match(text))
+    })
+}
+
+/// Translate an I-Regexp into the `regex` crate syntax
+///
+/// The only semantic difference handled here is `.`, which in I-Regexp matches
+/// any character except line feed and carriage return.
+fn compile_iregexp(pattern: &str, anchored: bool) -> Option<Regex> {
+    let mut translated = String::with_capacity(pattern.len());
+    let mut in_class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                translated.push(c);
+                translated.push(chars.next()?);
+            }
+            '[' if !in_class => {
+                in_class = true;
+                translated.push(c);
+            }
+            ']' if in_class => {
+                in_class = false;
+                translated.push(c);
+            }
+            '.' if !in_class => translated.push_str("[^\\n\\r]"),
+            _ => translated.push(c),
+        }
+    }
+    let source = if anchored {
+        format!("^(?:{})$", translated)
+    } else {
+        translated
+    };
+    Regex::new(&source).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn current(segments: Vec<JsonPath>) -> Operand {
+        Operand::Query(Query {
+            root: QueryRoot::Current,
+            segments,
+        })
+    }
+
+    #[test]
+    fn test_compare_numbers() {
+        let expr = FilterExpr::Compare(
+            current(vec![JsonPath::Key("price".to_string())]),
+            CompareOp::Gt,
+            Operand::Literal(json!(10)),
+        );
+        assert!(expr.test(&json!(null), &json!({"price": 12.5})));
+        assert!(!expr.test(&json!(null), &json!({"price": 8})));
+        assert!(!expr.test(&json!(null), &json!({"price": "12"})));
+    }
+
+    #[test]
+    fn test_missing_equals_missing() {
+        let expr = FilterExpr::Compare(
+            current(vec![JsonPath::Key("a".to_string())]),
+            CompareOp::Eq,
+            current(vec![JsonPath::Key("b".to_string())]),
+        );
+        assert!(expr.test(&json!(null), &json!({})));
+        assert!(!expr.test(&json!(null), &json!({"a": 1})));
+    }
+
+    #[test]
+    fn test_exists_and_not() {
+        let exists = FilterExpr::Exists(Query {
+            root: QueryRoot::Current,
+            segments: vec![JsonPath::Key("isbn".to_string())],
+        });
+        let not = FilterExpr::Not(Box::new(exists.clone()));
+        let book = json!({"isbn": "0-553-21311-3"});
+        assert!(exists.test(&json!(null), &book));
+        assert!(!not.test(&json!(null), &book));
+    }
+
+    #[test]
+    fn test_values_equal_numeric() {
+        assert!(values_equal(&json!(1), &json!(1.0)));
+        assert!(values_equal(&json!([1, {"a": 2}]), &json!([1.0, {"a": 2}])));
+        assert!(!values_equal(&json!("1"), &json!(1)));
+    }
+
+    #[test]
+    fn test_function_types() {
+        assert_eq!(Function::from_name("length"), Some(Function::Length));
+        assert_eq!(Function::from_name("nope"), None);
+        assert_eq!(Function::Match.result_type(), FunctionType::Logical);
+        assert_eq!(Function::Count.param_types(), &[FunctionType::Nodes]);
+    }
+
+    #[test]
+    fn test_iregexp_dot_excludes_line_breaks() {
+        assert!(regex_matches("a.c", true, "abc"));
+        assert!(!regex_matches("a.c", true, "a\nc"));
+        assert!(regex_matches("[.]", true, "."));
+        assert!(!regex_matches("b", true, "abc"));
+        assert!(regex_matches("b", false, "abc"));
+        assert!(!regex_matches("(", false, "("));
+    }
+
+    #[test]
+    fn test_less_than_strings() {
+        assert!(less_than(Some(&json!("abc")), Some(&json!("abd"))));
+        assert!(!less_than(Some(&json!(true)), Some(&json!(false))));
+        assert!(!less_than(None, Some(&json!(1))));
+    }
+}
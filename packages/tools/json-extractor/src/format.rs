@@ -1,7 +1,9 @@
 //! Output formatting
 
+use serde::Deserialize;
 use serde_json::Value;
 use shard_den_core::Result;
+use std::fmt::Write;
 
 /// Output format options
 #[derive(Debug, Clone, Copy, Default)]
@@ -11,42 +13,242 @@ pub enum OutputFormat {
     Csv,
     Text,
     Yaml,
+    /// GitHub-flavoured Markdown pipe table
+    Markdown,
+    /// HTML `<table>`
+    Html,
 }
 
+impl OutputFormat {
+    /// Parse a format name: `json`, `csv`, `text`, `yaml`, `markdown` (`md`) or `html`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            "text" | "txt" => Some(OutputFormat::Text),
+            "yaml" | "yml" => Some(OutputFormat::Yaml),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "html" => Some(OutputFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// How arrays nested in records are written to CSV
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CsvArrayMode {
+    /// Serialize the array as JSON in a single cell
+    #[default]
+    Json,
+    /// Join the elements with a separator in a single cell
+    Join(String),
+    /// One column per element (`tags.0`, `tags.1`, ...)
+    Index,
+    /// One row per element, repeating the other columns
+    Explode,
+}
+
+/// When CSV fields are wrapped in quotes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvQuoteStyle {
+    /// Only fields containing the delimiter, a quote or a line break
+    #[default]
+    Necessary,
+    Always,
+    /// Every field except numbers, booleans and empty cells
+    NonNumeric,
+    Never,
+}
+
+/// Line ending written after each CSV record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineTerminator {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineTerminator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineTerminator::Lf => "\n",
+            LineTerminator::CrLf => "\r\n",
+        }
+    }
+}
+
+/// CSV dialect and flattening options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// Flatten nested objects into dotted columns (`address.city`)
+    pub flatten: bool,
+    pub arrays: CsvArrayMode,
+    pub quote: CsvQuoteStyle,
+    /// Write a header row
+    pub header: bool,
+    pub line_terminator: LineTerminator,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            flatten: false,
+            arrays: CsvArrayMode::default(),
+            quote: CsvQuoteStyle::default(),
+            header: true,
+            line_terminator: LineTerminator::default(),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Tab-separated values
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::default()
+        }
+    }
+}
+
+/// Indentation of JSON output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonIndent {
+    /// Everything on one line, without whitespace
+    Compact,
+    Spaces(usize),
+    Tab,
+}
+
+impl Default for JsonIndent {
+    fn default() -> Self {
+        JsonIndent::Spaces(2)
+    }
+}
+
+impl JsonIndent {
+    /// Parse `compact`, `tab` or a number of spaces
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "compact" | "minified" | "min" => Some(JsonIndent::Compact),
+            "tab" | "tabs" | "\t" => Some(JsonIndent::Tab),
+            n => n.parse().ok().map(JsonIndent::Spaces),
+        }
+    }
+}
+
+/// Layout and escaping of JSON output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct JsonOptions {
+    pub indent: JsonIndent,
+    /// Write object members in key order rather than document order
+    pub sort_keys: bool,
+    /// Escape every non-ASCII character as `\uXXXX`
+    pub ascii: bool,
+    /// RFC 8785 (JCS) canonical form: compact, keys sorted by UTF-16 code
+    /// units and ECMAScript number formatting; overrides the other options
+    pub canonical: bool,
+}
+
+impl JsonOptions {
+    /// Single-line output without whitespace
+    pub fn compact() -> Self {
+        Self {
+            indent: JsonIndent::Compact,
+            ..Self::default()
+        }
+    }
+
+    /// RFC 8785 canonical output, for hashing and signing
+    pub fn canonical() -> Self {
+        Self {
+            canonical: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Columns of one CSV record, in first-seen order
+type CsvRow = Vec<(String, Value)>;
+
 /// Formatter for extraction results
-#[derive(Debug, Default)]
-pub struct Formatter;
+#[derive(Debug, Clone, Default)]
+pub struct Formatter {
+    json: JsonOptions,
+    columns: Vec<String>,
+}
 
 impl Formatter {
     /// Create a new formatter
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Lay out JSON output with `options` instead of pretty-printing with two spaces
+    pub fn with_json_options(mut self, options: JsonOptions) -> Self {
+        self.json = options;
+        self
+    }
+
+    /// Lead tables with these columns, in this order, instead of the rows' key order
+    ///
+    /// Object keys are sorted unless the source order is preserved, so this is
+    /// how aliases keep the order they were given in. Other columns follow in
+    /// first-seen order.
+    pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = columns;
+        self
     }
 
     /// Format a JSON value to the specified output format
     pub fn format(&self, value: &Value, format: OutputFormat) -> Result<String> {
         match format {
-            OutputFormat::Json => {
-                serde_json::to_string_pretty(value).map_err(shard_den_core::ShardDenError::Json)
-            }
-            OutputFormat::Csv => self.format_csv(value),
+            OutputFormat::Json => Ok(self.format_json_with(value, &self.json)),
+            OutputFormat::Csv => self.format_csv_with(value, &CsvOptions::default()),
             OutputFormat::Text => self.format_text(value),
             OutputFormat::Yaml => self.format_yaml(value),
+            OutputFormat::Markdown => Ok(self.format_markdown(value)),
+            OutputFormat::Html => Ok(self.format_html(value)),
+        }
+    }
+
+    /// Format a JSON value as JSON with the given layout and escaping options
+    pub fn format_json_with(&self, value: &Value, options: &JsonOptions) -> String {
+        let mut out = String::new();
+        if options.canonical {
+            write_canonical(&mut out, value);
+        } else {
+            JsonWriter {
+                options,
+                out: &mut out,
+            }
+            .value(value, 0);
         }
+        out
     }
 
-    fn format_csv(&self, value: &Value) -> Result<String> {
-        // Handle different value types
+    /// Format a JSON value as CSV with the given dialect and flattening options
+    pub fn format_csv_with(&self, value: &Value, options: &CsvOptions) -> Result<String> {
+        let delimiter = options.delimiter.to_string();
         match value {
             // Array of objects -> CSV table
             Value::Array(arr) if !arr.is_empty() && arr.iter().all(|v| v.is_object()) => {
-                let mut headers: Vec<String> = Vec::new();
+                let mut rows: Vec<CsvRow> = Vec::new();
                 for item in arr {
-                    if let Value::Object(obj) = item {
-                        for key in obj.keys() {
-                            if !headers.contains(key) {
-                                headers.push(key.clone());
-                            }
+                    let mut records = vec![Vec::new()];
+                    self.expand_csv("", item, options, &mut records);
+                    rows.extend(records);
+                }
+
+                let mut headers: Vec<String> = Vec::new();
+                for row in &rows {
+                    for (key, _) in row {
+                        if !headers.contains(key) {
+                            headers.push(key.clone());
                         }
                     }
                 }
@@ -54,46 +256,138 @@ impl Formatter {
                 if headers.is_empty() {
                     return Ok(String::new());
                 }
+                self.order_headers(&mut headers);
 
                 // Build CSV
-                let mut csv = headers.join(",") + "\n";
-                for item in arr {
-                    if let Value::Object(obj) = item {
-                        let row: Vec<String> = headers
-                            .iter()
-                            .map(|h| {
-                                obj.get(h)
-                                    .map(|v| self.escape_csv_value(v))
-                                    .unwrap_or_default()
-                            })
-                            .collect();
-                        csv.push_str(&row.join(","));
-                        csv.push('\n');
-                    }
+                let terminator = options.line_terminator.as_str();
+                let mut csv = String::new();
+                if options.header {
+                    let header: Vec<String> = headers
+                        .iter()
+                        .map(|h| self.quote_csv_field(h, false, options))
+                        .collect();
+                    csv.push_str(&header.join(&delimiter));
+                    csv.push_str(terminator);
+                }
+                for row in &rows {
+                    let cells: Vec<String> = headers
+                        .iter()
+                        .map(|h| {
+                            row.iter()
+                                .find(|(key, _)| key == h)
+                                .map(|(_, v)| self.csv_cell(v, options))
+                                .unwrap_or_default()
+                        })
+                        .collect();
+                    csv.push_str(&cells.join(&delimiter));
+                    csv.push_str(terminator);
                 }
                 Ok(csv)
             }
+            // Single object (e.g. an ungrouped aggregate) -> one row
+            Value::Object(_) => self.format_csv_with(&Value::Array(vec![value.clone()]), options),
             // Simple array -> just values
             Value::Array(arr) => {
-                let values: Vec<String> = arr.iter().map(|v| self.escape_csv_value(v)).collect();
-                Ok(values.join(","))
+                let values: Vec<String> = arr.iter().map(|v| self.csv_cell(v, options)).collect();
+                Ok(values.join(&delimiter))
             }
             // Single value
-            _ => Ok(self.escape_csv_value(value)),
+            _ => Ok(self.csv_cell(value, options)),
         }
     }
 
-    fn escape_csv_value(&self, value: &Value) -> String {
+    /// Move the configured columns to the front, with flattened `column.child` headers
+    fn order_headers(&self, headers: &mut [String]) {
+        let rank = |header: &str| {
+            self.columns
+                .iter()
+                .position(|column| {
+                    header
+                        .strip_prefix(column.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+                })
+                .unwrap_or(self.columns.len())
+        };
+        headers.sort_by_key(|header| rank(header));
+    }
+
+    /// Add the columns for `value` under `key` to every row, multiplying rows when exploding arrays
+    fn expand_csv(&self, key: &str, value: &Value, options: &CsvOptions, rows: &mut Vec<CsvRow>) {
+        let child_key = |child: &str| {
+            if key.is_empty() {
+                child.to_string()
+            } else {
+                format!("{}.{}", key, child)
+            }
+        };
         match value {
-            Value::String(s) => {
-                if s.contains(',') || s.contains('"') || s.contains('\n') {
-                    format!("\"{}\"", s.replace('"', "\"\""))
-                } else {
-                    s.clone()
+            Value::Object(obj) if key.is_empty() || options.flatten => {
+                for (k, v) in obj {
+                    self.expand_csv(&child_key(k), v, options, rows);
                 }
             }
-            Value::Null => "".to_string(),
+            Value::Array(arr) if options.arrays == CsvArrayMode::Index => {
+                for (i, v) in arr.iter().enumerate() {
+                    self.expand_csv(&child_key(&i.to_string()), v, options, rows);
+                }
+            }
+            Value::Array(arr) if options.arrays == CsvArrayMode::Explode && !arr.is_empty() => {
+                let mut exploded = Vec::new();
+                for row in rows.drain(..) {
+                    for item in arr {
+                        let mut sub = vec![row.clone()];
+                        self.expand_csv(key, item, options, &mut sub);
+                        exploded.extend(sub);
+                    }
+                }
+                *rows = exploded;
+            }
+            _ => {
+                let value = match value {
+                    Value::Array(arr) if arr.is_empty() => &Value::Null,
+                    _ => value,
+                };
+                for row in rows.iter_mut() {
+                    row.push((key.to_string(), value.clone()));
+                }
+            }
+        }
+    }
+
+    /// Render a single cell, quoting it according to the options
+    fn csv_cell(&self, value: &Value, options: &CsvOptions) -> String {
+        let text = match (value, &options.arrays) {
+            (Value::String(s), _) => s.clone(),
+            (Value::Null, _) => return String::new(),
+            (Value::Array(arr), CsvArrayMode::Join(separator)) => arr
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    _ => v.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(separator),
             _ => value.to_string(),
+        };
+        self.quote_csv_field(&text, value.is_number() || value.is_boolean(), options)
+    }
+
+    fn quote_csv_field(&self, text: &str, numeric: bool, options: &CsvOptions) -> String {
+        let quote = match options.quote {
+            CsvQuoteStyle::Always => true,
+            CsvQuoteStyle::Never => false,
+            CsvQuoteStyle::NonNumeric => !numeric,
+            CsvQuoteStyle::Necessary => {
+                text.contains(options.delimiter)
+                    || text.contains('"')
+                    || text.contains('\n')
+                    || text.contains('\r')
+            }
+        };
+        if quote {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
         }
     }
 
@@ -128,9 +422,315 @@ impl Formatter {
         }
     }
 
+    #[cfg(not(feature = "preserve-source"))]
     fn format_yaml(&self, value: &Value) -> Result<String> {
         serde_yaml::to_string(value).map_err(shard_den_core::ShardDenError::Yaml)
     }
+
+    /// Arbitrary-precision numbers serialize as private structs, so values are
+    /// converted by hand
+    #[cfg(feature = "preserve-source")]
+    fn format_yaml(&self, value: &Value) -> Result<String> {
+        // Numbers that do not survive f64 are written as placeholders, then
+        // swapped for their exact text (a valid YAML number as it stands)
+        let placeholders = !value.to_string().contains(NUMBER_PLACEHOLDER);
+        let mut exact = Vec::new();
+        let yaml = to_yaml(value, placeholders.then_some(&mut exact));
+        let mut output =
+            serde_yaml::to_string(&yaml).map_err(shard_den_core::ShardDenError::Yaml)?;
+        for (i, text) in exact.iter().enumerate().rev() {
+            output = output.replace(&format!("{}{}__", NUMBER_PLACEHOLDER, i), text);
+        }
+        Ok(output)
+    }
+
+    /// Lay out a value as a table of header and rows
+    ///
+    /// Arrays of objects become one row per object, other arrays a single
+    /// `value` column, and objects a `key`/`value` listing.
+    fn to_table(&self, value: &Value) -> (Vec<String>, Vec<Vec<String>>) {
+        let cell = |v: &Value| match v {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            _ => v.to_string(),
+        };
+        match value {
+            Value::Array(arr) if !arr.is_empty() && arr.iter().all(|v| v.is_object()) => {
+                let mut headers: Vec<String> = Vec::new();
+                for key in arr
+                    .iter()
+                    .filter_map(Value::as_object)
+                    .flat_map(|o| o.keys())
+                {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+                self.order_headers(&mut headers);
+                let rows = arr
+                    .iter()
+                    .filter_map(Value::as_object)
+                    .map(|obj| {
+                        headers
+                            .iter()
+                            .map(|h| obj.get(h).map(cell).unwrap_or_default())
+                            .collect()
+                    })
+                    .collect();
+                (headers, rows)
+            }
+            Value::Array(arr) => (
+                vec!["value".to_string()],
+                arr.iter().map(|v| vec![cell(v)]).collect(),
+            ),
+            Value::Object(obj) => (
+                vec!["key".to_string(), "value".to_string()],
+                obj.iter().map(|(k, v)| vec![k.clone(), cell(v)]).collect(),
+            ),
+            _ => (vec!["value".to_string()], vec![vec![cell(value)]]),
+        }
+    }
+
+    fn format_markdown(&self, value: &Value) -> String {
+        // Entities first, so pasted cells never render as live HTML
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('\\', "\\\\")
+                .replace('|', "\\|")
+                .replace("\r\n", "<br>")
+                .replace(['\n', '\r'], "<br>")
+        };
+        let line = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|c| escape(c)).collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+
+        let (headers, rows) = self.to_table(value);
+        let mut md = line(&headers);
+        md.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+        for row in &rows {
+            md.push_str(&line(row));
+        }
+        md
+    }
+
+    fn format_html(&self, value: &Value) -> String {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+                .replace('\'', "&#39;")
+        };
+        let row = |tag: &str, cells: &[String]| {
+            let cells: String = cells
+                .iter()
+                .map(|c| format!("<{tag}>{}</{tag}>", escape(c)))
+                .collect();
+            format!("    <tr>{}</tr>\n", cells)
+        };
+
+        let (headers, rows) = self.to_table(value);
+        let mut html = String::from("<table>\n  <thead>\n");
+        html.push_str(&row("th", &headers));
+        html.push_str("  </thead>\n  <tbody>\n");
+        for r in &rows {
+            html.push_str(&row("td", r));
+        }
+        html.push_str("  </tbody>\n</table>\n");
+        html
+    }
+}
+
+#[cfg(feature = "preserve-source")]
+const NUMBER_PLACEHOLDER: &str = "__shard_den_number_";
+
+/// Convert to a YAML value, collecting numbers f64 cannot hold exactly into `exact`
+#[cfg(feature = "preserve-source")]
+fn to_yaml(value: &Value, mut exact: Option<&mut Vec<String>>) -> serde_yaml::Value {
+    use serde_yaml::Value as Yaml;
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Bool(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                return Yaml::Number(i.into());
+            }
+            if let Some(u) = n.as_u64() {
+                return Yaml::Number(u.into());
+            }
+            let f = n.as_f64().unwrap_or_default();
+            let lossless = serde_json::Number::from_f64(f).is_some_and(|r| r == *n);
+            match exact {
+                Some(exact) if !lossless => {
+                    exact.push(n.to_string());
+                    Yaml::String(format!("{}{}__", NUMBER_PLACEHOLDER, exact.len() - 1))
+                }
+                _ => Yaml::Number(f.into()),
+            }
+        }
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(arr) => Yaml::Sequence(
+            arr.iter()
+                .map(|v| to_yaml(v, exact.as_deref_mut()))
+                .collect(),
+        ),
+        Value::Object(obj) => Yaml::Mapping(
+            obj.iter()
+                .map(|(k, v)| (Yaml::String(k.clone()), to_yaml(v, exact.as_deref_mut())))
+                .collect(),
+        ),
+    }
+}
+
+/// Writes JSON with configurable indentation, key order and escaping
+struct JsonWriter<'a> {
+    options: &'a JsonOptions,
+    out: &'a mut String,
+}
+
+impl JsonWriter<'_> {
+    fn value(&mut self, value: &Value, depth: usize) {
+        match value {
+            Value::Array(arr) if !arr.is_empty() => {
+                self.out.push('[');
+                for (i, item) in arr.iter().enumerate() {
+                    self.separator(i, depth + 1);
+                    self.value(item, depth + 1);
+                }
+                self.newline(depth);
+                self.out.push(']');
+            }
+            Value::Object(obj) if !obj.is_empty() => {
+                let mut members: Vec<_> = obj.iter().collect();
+                if self.options.sort_keys {
+                    members.sort_by_key(|(key, _)| *key);
+                }
+                self.out.push('{');
+                for (i, (key, item)) in members.into_iter().enumerate() {
+                    self.separator(i, depth + 1);
+                    write_string(self.out, key, self.options.ascii);
+                    self.out.push(':');
+                    if self.options.indent != JsonIndent::Compact {
+                        self.out.push(' ');
+                    }
+                    self.value(item, depth + 1);
+                }
+                self.newline(depth);
+                self.out.push('}');
+            }
+            Value::String(s) => write_string(self.out, s, self.options.ascii),
+            other => self.out.push_str(&other.to_string()),
+        }
+    }
+
+    fn separator(&mut self, index: usize, depth: usize) {
+        if index > 0 {
+            self.out.push(',');
+        }
+        self.newline(depth);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        let unit = match self.options.indent {
+            JsonIndent::Compact => return,
+            JsonIndent::Spaces(n) => " ".repeat(n),
+            JsonIndent::Tab => "\t".to_string(),
+        };
+        self.out.push('\n');
+        self.out.push_str(&unit.repeat(depth));
+    }
+}
+
+/// Write `s` as a JSON string, escaping like serde_json (plus non-ASCII with `ascii`)
+fn write_string(out: &mut String, s: &str, ascii: bool) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if c < ' ' || (ascii && !c.is_ascii()) => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    let _ = write!(out, "\\u{:04x}", unit);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Write `value` in RFC 8785 canonical form
+fn write_canonical(out: &mut String, value: &Value) {
+    match value {
+        Value::Array(arr) => {
+            out.push('[');
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(obj) => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key, false);
+                out.push(':');
+                write_canonical(out, item);
+            }
+            out.push('}');
+        }
+        Value::Number(n) => out.push_str(&es_number(n.as_f64().unwrap_or_default())),
+        Value::String(s) => write_string(out, s, false),
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// Format a number as ECMAScript's `Number.prototype.toString` does
+///
+/// Integers beyond 2^53 are rounded to the nearest double first, as RFC 8785 requires.
+fn es_number(x: f64) -> String {
+    if x == 0.0 || !x.is_finite() {
+        return "0".to_string();
+    }
+    if x < 0.0 {
+        return format!("-{}", es_number(-x));
+    }
+    // Shortest round-trip digits and decimal exponent, e.g. "1.2345e-7"
+    let exp = format!("{:e}", x);
+    let (mantissa, e) = exp.split_once('e').unwrap_or((&exp, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = e.parse::<i32>().unwrap_or(0) + 1;
+
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{}.{}", int, frac)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { '+' } else { '-' };
+        let (first, rest) = digits.split_at(1);
+        let dot = if rest.is_empty() { "" } else { "." };
+        format!("{}{}{}e{}{}", first, dot, rest, sign, (n - 1).abs())
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +738,23 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_output_format_from_name() {
+        assert!(matches!(
+            OutputFormat::from_name("MD"),
+            Some(OutputFormat::Markdown)
+        ));
+        assert!(matches!(
+            OutputFormat::from_name("yml"),
+            Some(OutputFormat::Yaml)
+        ));
+        assert!(matches!(
+            OutputFormat::from_name("json"),
+            Some(OutputFormat::Json)
+        ));
+        assert!(OutputFormat::from_name("xlsx").is_none());
+    }
+
     #[test]
     fn test_format_json() {
         let formatter = Formatter::new();
@@ -155,6 +772,78 @@ mod tests {
         assert!(result.contains("1"));
     }
 
+    #[test]
+    fn test_format_json_default_matches_serde_pretty() {
+        let value = json!({"a": [1, {"b": null}, [], {}], "c": "x\"\u{1}\n", "d": 1.5});
+        assert_eq!(
+            Formatter::new().format(&value, OutputFormat::Json).unwrap(),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format_json_options() {
+        let formatter = Formatter::new();
+        let value = json!({"a": "é😀", "b": [1, 2]});
+        let options = JsonOptions {
+            ascii: true,
+            ..JsonOptions::compact()
+        };
+        assert_eq!(
+            formatter.format_json_with(&value, &options),
+            r#"{"a":"\u00e9\ud83d\ude00","b":[1,2]}"#
+        );
+
+        let tabs = Formatter::new().with_json_options(JsonOptions {
+            indent: JsonIndent::Tab,
+            sort_keys: true,
+            ..JsonOptions::default()
+        });
+        assert_eq!(
+            tabs.format(&json!({"b": [1], "a": {}}), OutputFormat::Json)
+                .unwrap(),
+            "{\n\t\"a\": {},\n\t\"b\": [\n\t\t1\n\t]\n}"
+        );
+        assert_eq!(JsonIndent::from_name("4"), Some(JsonIndent::Spaces(4)));
+        assert_eq!(JsonIndent::from_name("compact"), Some(JsonIndent::Compact));
+        assert_eq!(JsonIndent::from_name("wide"), None);
+    }
+
+    #[test]
+    fn test_format_json_canonical() {
+        // Example from RFC 8785 section 3.2.2
+        let value: Value = serde_json::from_str(
+            r#"{"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Formatter::new().format_json_with(&value, &JsonOptions::canonical()),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+
+        // Keys sort by UTF-16 code units, so U+1F600 comes before U+FB33
+        let value =
+            json!({"\u{20ac}": 1, "\r": 2, "\u{fb33}": 3, "1": 4, "😀": 5, "\u{80}": 6, "ö": 7});
+        assert_eq!(
+            Formatter::new().format_json_with(&value, &JsonOptions::canonical()),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}"
+        );
+    }
+
+    #[test]
+    fn test_es_number() {
+        assert_eq!(es_number(0.0), "0");
+        assert_eq!(es_number(-0.0), "0");
+        assert_eq!(es_number(-1.5), "-1.5");
+        assert_eq!(es_number(1e20), "100000000000000000000");
+        assert_eq!(es_number(1e21), "1e+21");
+        assert_eq!(es_number(0.000001), "0.000001");
+        assert_eq!(es_number(1.5e-7), "1.5e-7");
+        assert_eq!(es_number(9007199254740993u64 as f64), "9007199254740992");
+    }
+
     #[test]
     fn test_format_text_string() {
         let formatter = Formatter::new();
@@ -215,6 +904,15 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_format_csv_object_as_row() {
+        let formatter = Formatter::new().with_columns(vec!["sum".into(), "avg".into()]);
+        let result = formatter
+            .format(&json!({"avg": 5.5, "sum": 11}), OutputFormat::Csv)
+            .unwrap();
+        assert_eq!(result, "sum,avg\n11,5.5\n");
+    }
+
     #[test]
     fn test_format_csv_simple_array() {
         let formatter = Formatter::new();
@@ -293,6 +991,166 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_format_csv_quotes_nested_json() {
+        let formatter = Formatter::new();
+        let value = json!([{"a": {"x": 1, "y": 2}}]);
+        let result = formatter.format(&value, OutputFormat::Csv).unwrap();
+        assert_eq!(result, "a\n\"{\"\"x\"\":1,\"\"y\"\":2}\"\n");
+    }
+
+    #[test]
+    fn test_format_csv_flatten() {
+        let formatter = Formatter::new();
+        let value = json!([{"address": {"city": "Oslo", "geo": {"lat": 59.9}}, "id": 1}]);
+        let options = CsvOptions {
+            flatten: true,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &options).unwrap();
+        assert_eq!(result, "address.city,address.geo.lat,id\nOslo,59.9,1\n");
+    }
+
+    #[test]
+    fn test_format_columns() {
+        let value = json!([{"id": 1, "address": {"city": "Oslo"}, "zip": "0150"}]);
+        let formatter = Formatter::new().with_columns(vec!["zip".into(), "address".into()]);
+        let options = CsvOptions {
+            flatten: true,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &options).unwrap();
+        assert_eq!(result, "zip,address.city,id\n0150,Oslo,1\n");
+        let result = formatter.format(&value, OutputFormat::Markdown).unwrap();
+        assert!(result.starts_with("| zip | address | id |\n"), "{}", result);
+    }
+
+    #[test]
+    fn test_format_csv_array_modes() {
+        let formatter = Formatter::new();
+        let value = json!([{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": []}]);
+
+        let join = CsvOptions {
+            arrays: CsvArrayMode::Join("|".to_string()),
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &join).unwrap();
+        assert_eq!(result, "id,tags\n1,a|b\n2,\n");
+
+        let index = CsvOptions {
+            arrays: CsvArrayMode::Index,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &index).unwrap();
+        assert_eq!(result, "id,tags.0,tags.1\n1,a,b\n2,,\n");
+
+        let explode = CsvOptions {
+            arrays: CsvArrayMode::Explode,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &explode).unwrap();
+        assert_eq!(result, "id,tags\n1,a\n1,b\n2,\n");
+    }
+
+    #[test]
+    fn test_format_csv_explode_objects() {
+        let formatter = Formatter::new();
+        let value = json!([{"id": 1, "items": [{"sku": "x"}, {"sku": "y"}]}]);
+        let options = CsvOptions {
+            flatten: true,
+            arrays: CsvArrayMode::Explode,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &options).unwrap();
+        assert_eq!(result, "id,items.sku\n1,x\n1,y\n");
+    }
+
+    #[test]
+    fn test_format_csv_dialect() {
+        let formatter = Formatter::new();
+        let value = json!([{"a b": "x\ty", "n": 1}]);
+
+        let result = formatter
+            .format_csv_with(&value, &CsvOptions::tsv())
+            .unwrap();
+        assert_eq!(result, "a b\tn\n\"x\ty\"\t1\n");
+
+        let options = CsvOptions {
+            delimiter: ';',
+            quote: CsvQuoteStyle::NonNumeric,
+            header: false,
+            line_terminator: LineTerminator::CrLf,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &options).unwrap();
+        assert_eq!(result, "\"x\ty\";1\r\n");
+
+        let options = CsvOptions {
+            quote: CsvQuoteStyle::Always,
+            ..CsvOptions::default()
+        };
+        let result = formatter.format_csv_with(&value, &options).unwrap();
+        assert_eq!(result, "\"a b\",\"n\"\n\"x\ty\",\"1\"\n");
+    }
+
+    #[test]
+    fn test_format_csv_quotes_headers() {
+        let formatter = Formatter::new();
+        let value = json!([{"a,b": 1}]);
+        let result = formatter.format(&value, OutputFormat::Csv).unwrap();
+        assert_eq!(result, "\"a,b\"\n1\n");
+    }
+
+    #[test]
+    fn test_format_markdown() {
+        let formatter = Formatter::new();
+        let value = json!([{"id": 1, "name": "a|b"}, {"id": 2, "note": "x\ny"}]);
+        let result = formatter.format(&value, OutputFormat::Markdown).unwrap();
+        assert_eq!(
+            result,
+            "| id | name | note |\n| --- | --- | --- |\n| 1 | a\\|b |  |\n| 2 |  | x<br>y |\n"
+        );
+
+        let value = json!([{"html": "<img src=x onerror=alert(1)> & co"}]);
+        let result = formatter.format(&value, OutputFormat::Markdown).unwrap();
+        assert!(
+            result.ends_with("| &lt;img src=x onerror=alert(1)&gt; &amp; co |\n"),
+            "{}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_format_markdown_scalars() {
+        let formatter = Formatter::new();
+        let result = formatter
+            .format(&json!([1, "two"]), OutputFormat::Markdown)
+            .unwrap();
+        assert_eq!(result, "| value |\n| --- |\n| 1 |\n| two |\n");
+    }
+
+    #[test]
+    fn test_format_html() {
+        let formatter = Formatter::new();
+        let value = json!([{"name": "<b>&'\""}]);
+        let result = formatter.format(&value, OutputFormat::Html).unwrap();
+        assert_eq!(
+            result,
+            "<table>\n  <thead>\n    <tr><th>name</th></tr>\n  </thead>\n  <tbody>\n    \
+             <tr><td>&lt;b&gt;&amp;&#39;&quot;</td></tr>\n  </tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_format_html_object() {
+        let formatter = Formatter::new();
+        let result = formatter
+            .format(&json!({"a": [1, 2]}), OutputFormat::Html)
+            .unwrap();
+        assert!(result.contains("<th>key</th><th>value</th>"));
+        assert!(result.contains("<td>a</td><td>[1,2]</td>"));
+    }
+
     #[test]
     fn test_format_csv_empty_headers() {
         let formatter = Formatter::new();
@@ -0,0 +1,357 @@
+//! JSON Schema inference (draft 2020-12)
+//!
+//! Samples are merged into a shape tree: types seen at each location
+//! (unions and `null` included), how often each object property was present,
+//! distinct string values and string formats. `finish` turns the tree into a
+//! schema where a property is `required` only if every observed object had it,
+//! low-cardinality strings become an `enum`, and strings that all share a
+//! format (`date-time`, `date`, `email`, `uuid`, `uri`) get a `format` hint.
+
+use crate::detect::DEFAULT_SAMPLE_LIMIT;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Dialect URI emitted in `$schema`
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Default maximum number of distinct strings turned into an `enum`
+pub const DEFAULT_ENUM_LIMIT: usize = 10;
+
+/// Infers a JSON Schema from one or more sample documents
+#[derive(Debug)]
+pub struct SchemaInferrer {
+    enum_limit: usize,
+    sample_limit: usize,
+    root: Shape,
+}
+
+impl Default for SchemaInferrer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaInferrer {
+    pub fn new() -> Self {
+        Self {
+            enum_limit: DEFAULT_ENUM_LIMIT,
+            sample_limit: DEFAULT_SAMPLE_LIMIT,
+            root: Shape::default(),
+        }
+    }
+
+    /// Set the maximum number of distinct strings reported as an `enum` (0 disables enums)
+    pub fn with_enum_limit(mut self, limit: usize) -> Self {
+        self.enum_limit = limit;
+        self
+    }
+
+    /// Add a sample document
+    pub fn observe(&mut self, value: &Value) {
+        self.root.observe(value, self.enum_limit, self.sample_limit);
+    }
+
+    /// Build the schema for everything observed so far
+    pub fn finish(&self) -> Value {
+        let mut schema = Map::new();
+        schema.insert("$schema".to_string(), json!(SCHEMA_DIALECT));
+        if let Value::Object(inferred) = self.root.to_schema(self.enum_limit) {
+            schema.extend(inferred);
+        }
+        Value::Object(schema)
+    }
+}
+
+/// Everything observed at one location
+#[derive(Debug, Default)]
+struct Shape {
+    seen_null: bool,
+    seen_bool: bool,
+    seen_integer: bool,
+    seen_float: bool,
+    /// Number of times an object was observed here
+    objects: usize,
+    properties: Vec<(String, Shape)>,
+    property_index: HashMap<String, usize>,
+    /// Number of times a property was present, by index into `properties`
+    presence: Vec<usize>,
+    seen_array: bool,
+    items: Option<Box<Shape>>,
+    strings: usize,
+    /// Distinct strings, up to one more than the enum limit
+    distinct: Vec<String>,
+    /// `None` before any string; then the format shared by all strings, if any
+    format: Option<Option<&'static str>>,
+}
+
+impl Shape {
+    fn observe(&mut self, value: &Value, enum_limit: usize, sample_limit: usize) {
+        match value {
+            Value::Null => self.seen_null = true,
+            Value::Bool(_) => self.seen_bool = true,
+            Value::Number(n) => {
+                if n.is_i64() || n.is_u64() {
+                    self.seen_integer = true;
+                } else {
+                    self.seen_float = true;
+                }
+            }
+            Value::String(s) => {
+                self.strings += 1;
+                if self.distinct.len() <= enum_limit && !self.distinct.contains(s) {
+                    self.distinct.push(s.clone());
+                }
+                let format = detect_format(s);
+                self.format = match self.format {
+                    None => Some(format),
+                    Some(previous) if previous == format => Some(format),
+                    Some(_) => Some(None),
+                };
+            }
+            Value::Array(arr) => {
+                self.seen_array = true;
+                let len = arr.len();
+                let samples = len.min(sample_limit);
+                let items = self.items.get_or_insert_with(Default::default);
+                for i in 0..samples {
+                    items.observe(&arr[i * len / samples], enum_limit, sample_limit);
+                }
+            }
+            Value::Object(obj) => {
+                self.objects += 1;
+                for (key, val) in obj {
+                    let i = match self.property_index.get(key) {
+                        Some(&i) => i,
+                        None => {
+                            self.property_index
+                                .insert(key.clone(), self.properties.len());
+                            self.properties.push((key.clone(), Shape::default()));
+                            self.presence.push(0);
+                            self.properties.len() - 1
+                        }
+                    };
+                    self.presence[i] += 1;
+                    self.properties[i].1.observe(val, enum_limit, sample_limit);
+                }
+            }
+        }
+    }
+
+    fn to_schema(&self, enum_limit: usize) -> Value {
+        let mut types: Vec<&str> = Vec::new();
+        if self.objects > 0 {
+            types.push("object");
+        }
+        if self.seen_array {
+            types.push("array");
+        }
+        if self.strings > 0 {
+            types.push("string");
+        }
+        if self.seen_float {
+            types.push("number");
+        } else if self.seen_integer {
+            types.push("integer");
+        }
+        if self.seen_bool {
+            types.push("boolean");
+        }
+        if self.seen_null {
+            types.push("null");
+        }
+
+        let mut schema = Map::new();
+        match types.as_slice() {
+            [] => return json!({}),
+            [single] => schema.insert("type".to_string(), json!(single)),
+            _ => schema.insert("type".to_string(), json!(types)),
+        };
+
+        if self.objects > 0 {
+            let properties: Map<String, Value> = self
+                .properties
+                .iter()
+                .map(|(key, shape)| (key.clone(), shape.to_schema(enum_limit)))
+                .collect();
+            let required: Vec<&str> = self
+                .properties
+                .iter()
+                .zip(&self.presence)
+                .filter(|(_, &present)| present == self.objects)
+                .map(|((key, _), _)| key.as_str())
+                .collect();
+            schema.insert("properties".to_string(), Value::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".to_string(), json!(required));
+            }
+        }
+
+        if let Some(items) = &self.items {
+            let items = items.to_schema(enum_limit);
+            if items != json!({}) {
+                schema.insert("items".to_string(), items);
+            }
+        }
+
+        if self.strings > 0 {
+            let is_enum = self.distinct.len() <= enum_limit
+                && self.strings >= 2 * self.distinct.len()
+                && types.len() <= 2
+                && (types.len() == 1 || self.seen_null);
+            if is_enum {
+                let mut values: Vec<Value> = self.distinct.iter().map(|s| json!(s)).collect();
+                if self.seen_null {
+                    values.push(Value::Null);
+                }
+                schema.insert("enum".to_string(), Value::Array(values));
+            } else if let Some(Some(format)) = self.format {
+                schema.insert("format".to_string(), json!(format));
+            }
+        }
+
+        Value::Object(schema)
+    }
+}
+
+/// The format a string conforms to, if any
+fn detect_format(s: &str) -> Option<&'static str> {
+    static FORMATS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    let formats =
+        FORMATS.get_or_init(|| {
+            [
+            (
+                "date-time",
+                r"^\d{4}-\d{2}-\d{2}[Tt]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$",
+            ),
+            ("date", r"^\d{4}-\d{2}-\d{2}$"),
+            (
+                "uuid",
+                r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+            ),
+            ("email", r"^[^@\s]+@[^@\s]+\.[^@\s.]+$"),
+            ("uri", r"^([a-zA-Z][a-zA-Z0-9+.-]*://\S+|(urn|mailto|tel|data):\S+)$"),
+        ]
+        .into_iter()
+        .map(|(name, pattern)| (name, Regex::new(pattern).expect("format pattern is valid")))
+        .collect()
+        });
+    formats
+        .iter()
+        .find(|(_, regex)| regex.is_match(s))
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer(samples: &[Value]) -> Value {
+        let mut inferrer = SchemaInferrer::new();
+        for sample in samples {
+            inferrer.observe(sample);
+        }
+        inferrer.finish()
+    }
+
+    #[test]
+    fn test_infer_object() {
+        let schema = infer(&[json!({"id": 1, "name": "a", "ok": true, "score": 1.5})]);
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": SCHEMA_DIALECT,
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "name": {"type": "string"},
+                    "ok": {"type": "boolean"},
+                    "score": {"type": "number"}
+                },
+                "required": ["id", "name", "ok", "score"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_required_and_optional_across_elements() {
+        let schema = infer(&[json!([{"id": 1, "tag": "x"}, {"id": 2}, {"id": 3.5, "tag": null}])]);
+        let items = &schema["items"];
+        assert_eq!(schema["type"], "array");
+        assert_eq!(items["required"], json!(["id"]));
+        assert_eq!(items["properties"]["id"]["type"], "number");
+        assert_eq!(
+            items["properties"]["tag"]["type"],
+            json!(["string", "null"])
+        );
+    }
+
+    #[test]
+    fn test_union_types() {
+        let schema = infer(&[json!({"v": 1}), json!({"v": "a"}), json!({"v": [true]})]);
+        let v = &schema["properties"]["v"];
+        assert_eq!(v["type"], json!(["array", "string", "integer"]));
+        assert_eq!(v["items"], json!({"type": "boolean"}));
+    }
+
+    #[test]
+    fn test_enum_detection() {
+        let statuses: Vec<Value> = ["open", "closed", "open", "open", "closed", "open"]
+            .iter()
+            .map(|s| json!({ "status": s }))
+            .collect();
+        let schema = infer(&statuses);
+        assert_eq!(
+            schema["properties"]["status"],
+            json!({"type": "string", "enum": ["open", "closed"]})
+        );
+
+        // Too few repetitions to call it an enum
+        let schema = infer(&[json!({"name": "a"}), json!({"name": "b"})]);
+        assert_eq!(schema["properties"]["name"], json!({"type": "string"}));
+
+        let mut inferrer = SchemaInferrer::new().with_enum_limit(0);
+        for sample in &statuses {
+            inferrer.observe(sample);
+        }
+        assert_eq!(
+            inferrer.finish()["properties"]["status"],
+            json!({"type": "string"})
+        );
+    }
+
+    #[test]
+    fn test_format_hints() {
+        let schema = infer(&[
+            json!({
+                "at": "2024-01-02T03:04:05Z",
+                "day": "2024-01-02",
+                "id": "123e4567-e89b-12d3-a456-426614174000",
+                "mail": "a@example.com",
+                "site": "https://example.com/x",
+                "mixed": "2024-01-02"
+            }),
+            json!({"mixed": "a@example.com"}),
+        ]);
+        let props = &schema["properties"];
+        assert_eq!(props["at"]["format"], "date-time");
+        assert_eq!(props["day"]["format"], "date");
+        assert_eq!(props["id"]["format"], "uuid");
+        assert_eq!(props["mail"]["format"], "email");
+        assert_eq!(props["site"]["format"], "uri");
+        assert!(props["mixed"].get("format").is_none());
+    }
+
+    #[test]
+    fn test_empty_array_and_null_root() {
+        assert_eq!(
+            infer(&[json!([])]),
+            json!({"$schema": SCHEMA_DIALECT, "type": "array"})
+        );
+        assert_eq!(
+            infer(&[json!(null)]),
+            json!({"$schema": SCHEMA_DIALECT, "type": "null"})
+        );
+    }
+}
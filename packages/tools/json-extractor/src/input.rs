@@ -0,0 +1,445 @@
+//! Input formats
+//!
+//! Non-JSON documents are converted to `serde_json::Value` so the same
+//! paths work regardless of the source format:
+//!
+//! - **JSON5 / JSONC**: see [`crate::lenient`].
+//! - **YAML**: a single document maps directly; a stream of several
+//!   documents becomes an array with one element per document. Non-string
+//!   mapping keys are converted to strings.
+//! - **TOML**: tables become objects; dates and times become strings.
+//! - **CSV**: the first row is the header; every following row becomes an
+//!   object keyed by header. All cells are strings.
+//! - **XML**: the document becomes `{"<root>": ...}`. An element with only
+//!   text becomes a string and an empty element `null`. Otherwise it becomes
+//!   an object holding attributes as `"@name"`, child elements by tag name
+//!   (repeated tags collapse into an array) and any text as `"#text"`.
+//!   Comments, processing instructions and the declaration are ignored.
+
+use crate::lenient::parse_lenient;
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use shard_den_core::{Result, ShardDenError};
+use std::path::Path;
+
+/// Supported input document formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    #[default]
+    Json,
+    /// JSON5 / JSONC: comments, trailing commas, unquoted keys and the like
+    Json5,
+    Yaml,
+    Toml,
+    Csv,
+    Xml,
+}
+
+impl InputFormat {
+    /// Look up a format by name (`json`, `json5`/`jsonc`, `yaml`/`yml`, `toml`, `csv`, `xml`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(InputFormat::Json),
+            "json5" | "jsonc" => Some(InputFormat::Json5),
+            "yaml" | "yml" => Some(InputFormat::Yaml),
+            "toml" => Some(InputFormat::Toml),
+            "csv" => Some(InputFormat::Csv),
+            "xml" => Some(InputFormat::Xml),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a file name's extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+
+    /// Parse `input` into a JSON value
+    pub fn parse(&self, input: &str) -> Result<Value> {
+        match self {
+            InputFormat::Json => serde_json::from_str(input).map_err(Into::into),
+            InputFormat::Json5 => parse_lenient(input).map_err(Into::into),
+            InputFormat::Yaml => parse_yaml(input),
+            InputFormat::Toml => parse_toml(input),
+            InputFormat::Csv => parse_csv(input),
+            InputFormat::Xml => parse_xml(input),
+        }
+    }
+}
+
+/// Sort object keys and round numbers through `f64`, discarding source key order and number text
+///
+/// With `preserve-source` serde_json keeps both, so documents not meant to
+/// preserve their source go through this to read as they would otherwise.
+#[cfg(feature = "preserve-source")]
+pub fn normalize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(normalize);
+        }
+        Value::Array(arr) => arr.iter_mut().for_each(normalize),
+        Value::Number(n) => {
+            let rounded = if let Some(i) = n.as_i64() {
+                Some(Number::from(i))
+            } else if let Some(u) = n.as_u64() {
+                Some(Number::from(u))
+            } else {
+                n.as_f64().and_then(Number::from_f64)
+            };
+            if let Some(rounded) = rounded {
+                *n = rounded;
+            }
+        }
+        _ => {}
+    }
+}
+
+fn parse_yaml(input: &str) -> Result<Value> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(input) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        documents.push(yaml_to_json(value)?);
+    }
+    Ok(match documents.len() {
+        0 => Value::Null,
+        1 => documents.remove(0),
+        _ => Value::Array(documents),
+    })
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Result<Value> {
+    use serde_yaml::Value as Yaml;
+    Ok(match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                let f = n.as_f64().unwrap_or(f64::NAN);
+                Number::from_f64(f).map(Value::Number).ok_or_else(|| {
+                    ShardDenError::invalid_input(format!(
+                        "Invalid YAML: {} is not a valid JSON number",
+                        n
+                    ))
+                })?
+            }
+        }
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(seq) => {
+            Value::Array(seq.into_iter().map(yaml_to_json).collect::<Result<_>>()?)
+        }
+        Yaml::Mapping(mapping) => {
+            let mut obj = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    Yaml::String(s) => s,
+                    Yaml::Null => "null".to_string(),
+                    Yaml::Bool(b) => b.to_string(),
+                    Yaml::Number(n) => n.to_string(),
+                    other => serde_yaml::to_string(&other)?.trim_end().to_string(),
+                };
+                obj.insert(key, yaml_to_json(value)?);
+            }
+            Value::Object(obj)
+        }
+        Yaml::Tagged(tagged) => yaml_to_json(tagged.value)?,
+    })
+}
+
+fn parse_toml(input: &str) -> Result<Value> {
+    let table: toml::Table = toml::from_str(input)
+        .map_err(|e| ShardDenError::invalid_input(format!("Invalid TOML: {}", e)))?;
+    toml_to_json(toml::Value::Table(table))
+}
+
+fn toml_to_json(value: toml::Value) -> Result<Value> {
+    Ok(match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Number::from_f64(f).map(Value::Number).ok_or_else(|| {
+            ShardDenError::invalid_input(format!("Invalid TOML: {} is not a valid JSON number", f))
+        })?,
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(arr) => {
+            Value::Array(arr.into_iter().map(toml_to_json).collect::<Result<_>>()?)
+        }
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| Ok((k, toml_to_json(v)?)))
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+fn parse_csv(input: &str) -> Result<Value> {
+    let csv_error = |e: csv::Error| ShardDenError::invalid_input(format!("Invalid CSV: {}", e));
+    let mut reader = csv::Reader::from_reader(input.as_bytes());
+    let headers = reader.headers().map_err(csv_error)?.clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(h, v)| (h.to_string(), Value::String(v.to_string())))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Element being built while reading XML
+struct XmlElement {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl XmlElement {
+    fn new(name: String, fields: Map<String, Value>) -> Self {
+        Self {
+            name,
+            fields,
+            text: String::new(),
+        }
+    }
+
+    fn add_child(&mut self, name: String, value: Value) {
+        match self.fields.get_mut(&name) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                self.fields.insert(name, value);
+            }
+        }
+    }
+
+    fn finish(self) -> (String, Value) {
+        let text = self.text.trim();
+        let value = match (self.fields.is_empty(), text.is_empty()) {
+            (true, true) => Value::Null,
+            (true, false) => Value::String(text.to_string()),
+            (false, _) => {
+                let mut fields = self.fields;
+                if !text.is_empty() {
+                    fields.insert("#text".to_string(), Value::String(text.to_string()));
+                }
+                Value::Object(fields)
+            }
+        };
+        (self.name, value)
+    }
+}
+
+fn parse_xml(input: &str) -> Result<Value> {
+    use quick_xml::events::{BytesStart, Event};
+
+    let mut reader = quick_xml::Reader::from_str(input);
+    let xml_error = |reader: &quick_xml::Reader<&[u8]>, e: &dyn std::fmt::Display| {
+        ShardDenError::invalid_input(format!(
+            "Invalid XML at byte {}: {}",
+            reader.error_position(),
+            e
+        ))
+    };
+    let open = |reader: &quick_xml::Reader<&[u8]>, e: &BytesStart| -> Result<XmlElement> {
+        let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+        let mut fields = Map::new();
+        for attr in e.attributes() {
+            let attr = attr.map_err(|e| xml_error(reader, &e))?;
+            let key = format!("@{}", String::from_utf8_lossy(attr.key.as_ref()));
+            let value = attr.unescape_value().map_err(|e| xml_error(reader, &e))?;
+            fields.insert(key, Value::String(value.into_owned()));
+        }
+        Ok(XmlElement::new(name, fields))
+    };
+
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut root: Option<(String, Value)> = None;
+    loop {
+        let event = reader.read_event().map_err(|e| xml_error(&reader, &e))?;
+        let completed = match event {
+            Event::Start(e) => {
+                stack.push(open(&reader, &e)?);
+                None
+            }
+            Event::Empty(e) => Some(open(&reader, &e)?.finish()),
+            Event::End(_) => stack.pop().map(XmlElement::finish),
+            Event::Text(e) => {
+                let text = e.decode().map_err(|e| xml_error(&reader, &e))?;
+                match stack.last_mut() {
+                    Some(element) => element.text.push_str(&text),
+                    None if text.trim().is_empty() => {}
+                    None => return Err(xml_error(&reader, &"text outside the root element")),
+                }
+                None
+            }
+            Event::CData(e) => {
+                let text = e.decode().map_err(|e| xml_error(&reader, &e))?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+                None
+            }
+            Event::GeneralRef(e) => {
+                let resolved = match e.resolve_char_ref().map_err(|e| xml_error(&reader, &e))? {
+                    Some(c) => c.to_string(),
+                    None => {
+                        let name = e.decode().map_err(|e| xml_error(&reader, &e))?;
+                        match name.as_ref() {
+                            "amp" => "&".to_string(),
+                            "lt" => "<".to_string(),
+                            "gt" => ">".to_string(),
+                            "quot" => "\"".to_string(),
+                            "apos" => "'".to_string(),
+                            other => {
+                                return Err(xml_error(
+                                    &reader,
+                                    &format!("unknown entity '&{};'", other),
+                                ))
+                            }
+                        }
+                    }
+                };
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&resolved);
+                }
+                None
+            }
+            Event::Eof => break,
+            _ => None,
+        };
+
+        if let Some((name, value)) = completed {
+            match stack.last_mut() {
+                Some(parent) => parent.add_child(name, value),
+                None if root.is_none() => root = Some((name, value)),
+                None => return Err(xml_error(&reader, &"multiple root elements")),
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(xml_error(&reader, &"unexpected end of input"));
+    }
+    let (name, value) =
+        root.ok_or_else(|| ShardDenError::invalid_input("Invalid XML: no root element"))?;
+    let mut obj = Map::new();
+    obj.insert(name, value);
+    Ok(Value::Object(obj))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_name_and_path() {
+        assert_eq!(InputFormat::from_name("YML"), Some(InputFormat::Yaml));
+        assert_eq!(InputFormat::from_name("ini"), None);
+        assert_eq!(
+            InputFormat::from_path("conf/app.toml"),
+            Some(InputFormat::Toml)
+        );
+        assert_eq!(InputFormat::from_path("data.CSV"), Some(InputFormat::Csv));
+        assert_eq!(InputFormat::from_path("README"), None);
+        assert_eq!(
+            InputFormat::from_path(".vscode/settings.jsonc"),
+            Some(InputFormat::Json5)
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let value = InputFormat::Yaml.parse("a: 1\nb: [x, true]\n").unwrap();
+        assert_eq!(value, json!({"a": 1, "b": ["x", true]}));
+    }
+
+    #[test]
+    fn test_parse_yaml_multi_document() {
+        let value = InputFormat::Yaml.parse("---\na: 1\n---\na: 2\n").unwrap();
+        assert_eq!(value, json!([{"a": 1}, {"a": 2}]));
+        assert_eq!(InputFormat::Yaml.parse("").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_parse_yaml_non_string_keys() {
+        let value = InputFormat::Yaml.parse("1: one\ntrue: yes\n").unwrap();
+        assert_eq!(value, json!({"1": "one", "true": "yes"}));
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let input = "title = \"x\"\n[server]\nport = 8080\nratio = 0.5\nstarted = 1979-05-27T07:32:00Z\n\n[[users]]\nname = \"a\"\n";
+        let value = InputFormat::Toml.parse(input).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "title": "x",
+                "server": {"port": 8080, "ratio": 0.5, "started": "1979-05-27T07:32:00Z"},
+                "users": [{"name": "a"}]
+            })
+        );
+        assert!(InputFormat::Toml.parse("a = ").is_err());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let value = InputFormat::Csv
+            .parse("id,name\n1,alice\n2,\"bob, jr\"\n")
+            .unwrap();
+        assert_eq!(
+            value,
+            json!([{"id": "1", "name": "alice"}, {"id": "2", "name": "bob, jr"}])
+        );
+        assert!(InputFormat::Csv.parse("a,b\n1,2,3\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_xml() {
+        let input = r#"<?xml version="1.0"?>
+<!-- users -->
+<users count="2">
+  <user id="1"><name>Alice &amp; Bob</name><tag>a</tag><tag>b</tag></user>
+  <user id="2"><name><![CDATA[<Carol>]]></name><empty/></user>
+  note &#65;
+</users>"#;
+        let value = InputFormat::Xml.parse(input).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "users": {
+                    "@count": "2",
+                    "user": [
+                        {"@id": "1", "name": "Alice & Bob", "tag": ["a", "b"]},
+                        {"@id": "2", "name": "<Carol>", "empty": null}
+                    ],
+                    "#text": "note A"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_xml_errors() {
+        assert!(InputFormat::Xml.parse("<a><b></a>").is_err());
+        assert!(InputFormat::Xml.parse("<a>").is_err());
+        assert!(InputFormat::Xml.parse("<a/><b/>").is_err());
+        assert!(InputFormat::Xml.parse("").is_err());
+        assert!(InputFormat::Xml.parse("<a>&nbsp;</a>").is_err());
+    }
+}
@@ -0,0 +1,469 @@
+//! Lenient JSON parsing (JSON5 / JSONC)
+//!
+//! Accepts everything JSON does plus `//` and `/* */` comments, trailing
+//! commas, single-quoted strings, unquoted (identifier) keys, hexadecimal
+//! numbers, leading `+` and leading or trailing decimal points. `Infinity`
+//! and `NaN` are rejected since they have no JSON representation.
+
+use crate::MAX_JSON_DEPTH;
+use serde_json::{Map, Number, Value};
+use shard_den_core::ShardDenError;
+use std::fmt;
+
+/// Error raised when lenient parsing fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LenientError {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LenientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for LenientError {}
+
+impl From<LenientError> for ShardDenError {
+    fn from(err: LenientError) -> Self {
+        ShardDenError::invalid_input(format!("JSON5 error: {}", err))
+    }
+}
+
+type ParseResult<T> = std::result::Result<T, LenientError>;
+
+/// Parse a JSON5 / JSONC document
+pub fn parse_lenient(input: &str) -> ParseResult<Value> {
+    let mut parser = LenientParser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    parser.skip_trivia()?;
+    let value = parser.parse_value(0)?;
+    parser.skip_trivia()?;
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("trailing characters after value"));
+    }
+    Ok(value)
+}
+
+struct LenientParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl LenientParser {
+    fn parse_value(&mut self, depth: usize) -> ParseResult<Value> {
+        if depth >= MAX_JSON_DEPTH {
+            return Err(self.error(format!("JSON too deeply nested (max: {})", MAX_JSON_DEPTH)));
+        }
+        match self.peek() {
+            Some('{') => self.parse_object(depth),
+            Some('[') => self.parse_array(depth),
+            Some(quote @ ('"' | '\'')) => self.parse_string(quote).map(Value::String),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.parse_number(),
+            Some(c) if is_identifier_start(c) => {
+                let start = self.pos;
+                let word = self.parse_identifier()?;
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    "Infinity" | "NaN" => {
+                        self.pos = start;
+                        Err(self.error(format!("{} cannot be represented in JSON", word)))
+                    }
+                    _ => {
+                        self.pos = start;
+                        Err(self.error(format!("unexpected identifier '{}'", word)))
+                    }
+                }
+            }
+            Some(c) => Err(self.error(format!("unexpected character '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> ParseResult<Value> {
+        self.pos += 1; // '{'
+        let mut map = Map::new();
+        loop {
+            self.skip_trivia()?;
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            let key = match self.peek() {
+                Some(quote @ ('"' | '\'')) => self.parse_string(quote)?,
+                Some(c) if is_identifier_start(c) => self.parse_identifier()?,
+                Some(c) => return Err(self.error(format!("expected object key, found '{}'", c))),
+                None => return Err(self.error("unterminated object")),
+            };
+            self.skip_trivia()?;
+            if !self.eat(':') {
+                return Err(self.error("expected ':' after object key"));
+            }
+            self.skip_trivia()?;
+            let value = self.parse_value(depth + 1)?;
+            map.insert(key, value);
+            self.skip_trivia()?;
+            if self.eat(',') {
+                continue;
+            }
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            return Err(self.error("expected ',' or '}'"));
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> ParseResult<Value> {
+        self.pos += 1; // '['
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            if self.peek().is_none() {
+                return Err(self.error("unterminated array"));
+            }
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_trivia()?;
+            if self.eat(',') {
+                continue;
+            }
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            return Err(self.error("expected ',' or ']'"));
+        }
+    }
+
+    fn parse_string(&mut self, quote: char) -> ParseResult<String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.bump() else {
+                self.pos = start;
+                return Err(self.error("unterminated string"));
+            };
+            match c {
+                c if c == quote => return Ok(out),
+                '\n' | '\r' => {
+                    self.pos -= 1;
+                    return Err(self.error("unescaped line break in string"));
+                }
+                '\\' => self.parse_escape(&mut out)?,
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, out: &mut String) -> ParseResult<()> {
+        let Some(c) = self.bump() else {
+            return Err(self.error("unterminated string"));
+        };
+        match c {
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\u{b}'),
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => out.push('\0'),
+            'x' => {
+                let code = self.parse_hex_digits(2)?;
+                out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            'u' => {
+                let high = self.parse_hex_digits(4)?;
+                let code = if (0xD800..0xDC00).contains(&high)
+                    && self.peek() == Some('\\')
+                    && self.chars.get(self.pos + 1) == Some(&'u')
+                {
+                    self.pos += 2;
+                    let low = self.parse_hex_digits(4)?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("invalid low surrogate in unicode escape"));
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                match char::from_u32(code) {
+                    Some(c) => out.push(c),
+                    None => return Err(self.error("unpaired surrogate in unicode escape")),
+                }
+            }
+            // Line continuation
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            '\r' => {
+                self.eat('\n');
+            }
+            c if c.is_ascii_digit() => {
+                self.pos -= 1;
+                return Err(self.error("invalid escape sequence"));
+            }
+            c => out.push(c),
+        }
+        Ok(())
+    }
+
+    fn parse_hex_digits(&mut self, count: usize) -> ParseResult<u32> {
+        let mut code = 0;
+        for _ in 0..count {
+            match self.peek().and_then(|c| c.to_digit(16)) {
+                Some(d) => {
+                    code = code * 16 + d;
+                    self.pos += 1;
+                }
+                None => return Err(self.error("invalid hexadecimal escape")),
+            }
+        }
+        Ok(code)
+    }
+
+    fn parse_identifier(&mut self) -> ParseResult<String> {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if (out.is_empty() && is_identifier_start(c))
+                || (!out.is_empty() && is_identifier_char(c))
+            {
+                out.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(out)
+    }
+
+    fn parse_number(&mut self) -> ParseResult<Value> {
+        let start = self.pos;
+        let negative = self.peek() == Some('-');
+        if matches!(self.peek(), Some('-' | '+')) {
+            self.pos += 1;
+        }
+
+        if self.peek() == Some('0') && matches!(self.chars.get(self.pos + 1), Some('x' | 'X')) {
+            self.pos += 2;
+            let digits_start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                self.pos += 1;
+            }
+            let digits: String = self.chars[digits_start..self.pos].iter().collect();
+            let magnitude = u64::from_str_radix(&digits, 16).map_err(|_| {
+                self.pos = start;
+                self.error("invalid hexadecimal number")
+            })?;
+            return if negative {
+                i64::try_from(magnitude)
+                    .map(|n| Value::from(-n))
+                    .map_err(|_| {
+                        self.pos = start;
+                        self.error("hexadecimal number out of range")
+                    })
+            } else {
+                Ok(Value::from(magnitude))
+            };
+        }
+
+        if self.peek().is_some_and(is_identifier_start) {
+            let word = self.parse_identifier()?;
+            self.pos = start;
+            return Err(self.error(format!("{} cannot be represented in JSON", word)));
+        }
+
+        let digits_start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E'))
+            || (matches!(self.peek(), Some('+' | '-'))
+                && matches!(self.chars.get(self.pos - 1), Some('e' | 'E')))
+        {
+            self.pos += 1;
+        }
+        let mut text: String = self.chars[digits_start..self.pos].iter().collect();
+        if text.starts_with('.') {
+            text.insert(0, '0');
+        }
+        if text.ends_with('.') {
+            text.push('0');
+        }
+        let text = text.replace(".e", ".0e").replace(".E", ".0E");
+        if text.len() > 1 && text.starts_with('0') && text.as_bytes()[1].is_ascii_digit() {
+            self.pos = start;
+            return Err(self.error("leading zeros are not allowed"));
+        }
+        let text = if negative { format!("-{}", text) } else { text };
+
+        serde_json::from_str::<Number>(&text)
+            .map(Value::Number)
+            .map_err(|_| {
+                self.pos = start;
+                self.error("invalid number")
+            })
+    }
+
+    /// Skip whitespace and comments
+    fn skip_trivia(&mut self) -> ParseResult<()> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() || c == '\u{feff}' => self.pos += 1,
+                Some('/') if self.chars.get(self.pos + 1) == Some(&'/') => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some('/') if self.chars.get(self.pos + 1) == Some(&'*') => {
+                    let start = self.pos;
+                    self.pos += 2;
+                    loop {
+                        match self.bump() {
+                            Some('*') if self.eat('/') => break,
+                            Some(_) => {}
+                            None => {
+                                self.pos = start;
+                                return Err(self.error("unterminated block comment"));
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> LenientError {
+        let consumed = &self.chars[..self.pos.min(self.chars.len())];
+        let line = consumed.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = consumed.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        LenientError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    is_identifier_start(c) || c.is_ascii_digit() || c == '\u{200c}' || c == '\u{200d}'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_plain_json() {
+        let input = r#"{"a": [1, -2.5e3, "xé", true, null]}"#;
+        let value = parse_lenient(input).unwrap();
+        assert_eq!(value, serde_json::from_str::<Value>(input).unwrap());
+        assert_eq!(value["a"][1].as_f64(), Some(-2500.0));
+    }
+
+    #[test]
+    fn test_parse_jsonc() {
+        let input = r#"{
+            // compiler options
+            "compilerOptions": {
+                "strict": true, /* inline */
+                "paths": ["a", "b",],
+            },
+        }"#;
+        let value = parse_lenient(input).unwrap();
+        assert_eq!(
+            value,
+            json!({"compilerOptions": {"strict": true, "paths": ["a", "b"]}})
+        );
+    }
+
+    #[test]
+    fn test_parse_json5() {
+        let input =
+            "{unquoted: 'single \"q\"', $id: 0x1F, neg: -0xff, lead: .5, trail: 5., plus: +1, \
+                     multi: 'a\\\nb', esc: '\\x41\\v'}";
+        let value = parse_lenient(input).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "unquoted": "single \"q\"",
+                "$id": 31,
+                "neg": -255,
+                "lead": 0.5,
+                "trail": 5.0,
+                "plus": 1,
+                "multi": "ab",
+                "esc": "A\u{b}"
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_position() {
+        let err = parse_lenient("{\n  a: 1,\n  b: ?\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 6));
+        assert_eq!(
+            err.to_string(),
+            "unexpected character '?' at line 3 column 6"
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_input() {
+        for input in [
+            "",
+            "{a 1}",
+            "[1 2]",
+            "'unterminated",
+            "/* open",
+            "{} x",
+            "Infinity",
+            "-NaN",
+            "012",
+            "[undefined]",
+            "'line\nbreak'",
+        ] {
+            assert!(parse_lenient(input).is_err(), "{:?} should fail", input);
+        }
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let input = format!("{}{}", "[".repeat(200), "]".repeat(200));
+        assert!(parse_lenient(&input).is_err());
+    }
+}
@@ -1,22 +1,72 @@
 //! JSON Extractor - Extract fields from JSON using path syntax
 //!
-//! Supports JSONPath-like syntax:
-//! - `key` - Get key value
+//! Supports JSONPath syntax:
+//! - `$.key` - Get key value
 //! - `*` - Wildcard
-//! - `[]` - Array iteration
+//! - `[*]` - Array iteration
 //! - `[0]` - Array index
+//! - `[1:3]` - Array slice
 //! - `..` - Recursive descent
+//! - `[?(@.price > 10)]` - Filter expression
+//!
+//! Paths may be named with `alias=path` (e.g. `name=$.users[*].name`); aliased
+//! extractions are returned as row objects keyed by alias.
+//!
+//! Large documents can be processed incrementally with [`StreamExtractor`], and
+//! JSON Lines input record by record with [`JsonExtractorCore::extract_ndjson`].
+//!
+//! Documents are read with sorted keys and numbers rounded through `f64`.
+//! Built with the `preserve-source` feature, `with_preserve_source` keeps the
+//! source key order and exact number text instead.
+//!
+//! [`SpanIndex`] maps every value of a JSON document to its source span, and
+//! [`JsonExtractorCore::with_source_spans`] reports the span of each match.
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+pub mod aggregate;
+pub mod complete;
+pub mod detect;
+pub mod diagnostic;
+pub mod diff;
 pub mod extract;
+pub mod filter;
 pub mod format;
+pub mod infer;
+pub mod input;
+pub mod lenient;
+pub mod mutate;
+pub mod ndjson;
 pub mod path;
-
-pub use extract::{ExtractResult, Extractor};
-pub use format::{Formatter, OutputFormat};
-pub use path::{JsonPath, PathParser};
+pub mod redact;
+pub mod span;
+pub mod stream;
+pub mod transform;
+pub mod validate;
+
+pub use aggregate::Aggregate;
+pub use complete::{Completion, CompletionKind, PathCompleter};
+pub use detect::{PathDetector, PathStats};
+pub use diagnostic::{Diagnostic, SourceKind};
+pub use diff::{Change, ChangeKind, DiffFormat, Differ, PatchOperation};
+pub use extract::{ExtractResult, Extractor, MatchLocation};
+pub use filter::{FilterExpr, Function};
+pub use format::{
+    CsvArrayMode, CsvOptions, CsvQuoteStyle, Formatter, JsonIndent, JsonOptions, LineTerminator,
+    OutputFormat,
+};
+pub use infer::SchemaInferrer;
+pub use input::InputFormat;
+pub use lenient::{parse_lenient, LenientError};
+pub use mutate::{Mutation, Mutator};
+pub use ndjson::{LineError, LineRecord, NdjsonDetect, NdjsonExtract, NdjsonSchema};
+pub use path::{JsonPath, NormalizedPath, PathElement, PathError, PathMode, PathParser};
+pub use redact::{RedactStrategy, Redactor};
+pub use span::{NodeKind, Span, SpanIndex, SpanNode};
+pub use stream::{StreamExtractor, StreamMatch};
+pub use transform::{Transform, TransformError};
+pub use validate::{Draft, ValidationError, Validator};
 
 use shard_den_core::ShardDenError;
 
@@ -45,35 +95,111 @@ fn check_json_depth(value: &serde_json::Value, depth: usize) -> Result<(), Strin
     Ok(())
 }
 
+/// Values to output for an extraction
+///
+/// Aliased paths produce row objects; otherwise the per-path match arrays are concatenated.
+fn output_values(result: &ExtractResult) -> Vec<serde_json::Value> {
+    if result.has_aliases() {
+        return result.to_rows();
+    }
+    let mut all_values = Vec::new();
+    for extracted in &result.values {
+        if let serde_json::Value::Array(arr) = &extracted.value {
+            all_values.extend(arr.iter().cloned());
+        } else {
+            all_values.push(extracted.value.clone());
+        }
+    }
+    all_values
+}
+
+/// `formatter` with aliased columns in the order the paths were given
+fn row_formatter(formatter: &Formatter, result: &ExtractResult) -> Formatter {
+    let columns = if result.has_aliases() {
+        result.columns()
+    } else {
+        Vec::new()
+    };
+    formatter.clone().with_columns(columns)
+}
+
+/// Row columns for `paths` in the order given, or none when no path has an alias
+///
+/// Pass these to [`Formatter::with_columns`] when formatting rows that were
+/// extracted separately, e.g. merged from several documents.
+pub fn alias_columns(paths: &str) -> Vec<String> {
+    let entries = parse_paths(paths);
+    let split: Vec<_> = entries.iter().map(|e| extract::split_alias(e)).collect();
+    if split.iter().all(|(alias, _)| alias.is_none()) {
+        return Vec::new();
+    }
+    split
+        .into_iter()
+        .map(|(alias, path)| alias.unwrap_or(path).to_string())
+        .collect()
+}
+
 /// Parse paths string, handling quoted strings and escape characters
+///
+/// Commas only separate paths at the top level: those inside `[...]`, `(...)`
+/// or string literals belong to the path, e.g. unions (`$.a[0,2]`) and
+/// function arguments (`match(@.x, 'h.*')`).
 pub fn parse_paths(input: &str) -> Vec<String> {
     let mut paths = Vec::new();
     let mut current = String::new();
     let mut in_quotes = false;
     let mut escape_next = false;
+    // Bracket and parenthesis nesting within the current path
+    let mut depth = 0usize;
+    // Quote of a string literal inside the path, kept verbatim
+    let mut literal: Option<char> = None;
 
     for ch in input.chars() {
+        if let Some(quote) = literal {
+            current.push(ch);
+            if escape_next {
+                escape_next = false;
+            } else if ch == '\\' {
+                escape_next = true;
+            } else if ch == quote {
+                literal = None;
+            }
+            continue;
+        }
         match (ch, escape_next, in_quotes) {
-            // Handle escape character
-            ('\\', false, _) => escape_next = true,
-            // Handle escaped quote inside quotes - add quote and reset escape
-            ('"', true, true) => {
-                current.push('"');
+            // Escaped character at the top level: keep it, drop the backslash
+            (_, true, _) => {
+                current.push(ch);
                 escape_next = false;
             }
-            // Handle quote toggle (only when not escaped)
-            ('"', false, _) => in_quotes = !in_quotes,
-            // Handle comma separator (only when outside quotes)
-            (',', false, false) => {
+            // Handle escape character
+            ('\\', false, false) if depth == 0 => escape_next = true,
+            ('\\', false, true) => escape_next = true,
+            // Handle quote toggle around a whole path (only at the top level)
+            ('"', false, _) if depth == 0 => in_quotes = !in_quotes,
+            // Everything inside a quoted path is literal
+            (_, false, true) => current.push(ch),
+            // String literals within the path
+            ('\'' | '"', false, false) => {
+                literal = Some(ch);
+                current.push(ch);
+            }
+            ('[' | '(', false, false) => {
+                depth += 1;
+                current.push(ch);
+            }
+            (']' | ')', false, false) => {
+                depth = depth.saturating_sub(1);
+                current.push(ch);
+            }
+            // Handle comma separator (only at the top level)
+            (',', false, false) if depth == 0 => {
                 if !current.is_empty() {
                     paths.push(current.trim().to_string());
                     current.clear();
                 }
             }
-            _ => {
-                current.push(ch);
-                escape_next = false;
-            }
+            _ => current.push(ch),
         }
     }
 
@@ -90,34 +216,102 @@ pub struct JsonExtractorCore {
     extractor: Extractor,
     formatter: Formatter,
     path_parser: PathParser,
+    input_format: InputFormat,
+    redactor: Option<Redactor>,
+    #[cfg(feature = "preserve-source")]
+    preserve_source: bool,
+    source_spans: bool,
 }
 
 impl JsonExtractorCore {
     pub fn new() -> Self {
+        Self::with_mode(PathMode::default())
+    }
+
+    /// Create an extractor that parses paths in the given dialect
+    pub fn with_mode(mode: PathMode) -> Self {
         Self {
-            extractor: Extractor::new(),
+            extractor: Extractor::with_mode(mode),
             formatter: Formatter::new(),
-            path_parser: PathParser::new(),
+            path_parser: PathParser::with_mode(mode),
+            input_format: InputFormat::default(),
+            redactor: None,
+            #[cfg(feature = "preserve-source")]
+            preserve_source: false,
+            source_spans: false,
         }
     }
 
+    /// Read documents in the given format instead of JSON
+    pub fn with_input_format(mut self, format: InputFormat) -> Self {
+        self.input_format = format;
+        self
+    }
+
+    /// Lay out JSON output with `options` (indentation, key order, escaping, RFC 8785)
+    pub fn with_json_options(mut self, options: JsonOptions) -> Self {
+        self.formatter = self.formatter.with_json_options(options);
+        self
+    }
+
+    /// Keep the source key order and exact number text through extraction and output
+    ///
+    /// Otherwise objects come out with their keys sorted and numbers are
+    /// rounded through `f64`, so large IDs and long decimals may change.
+    #[cfg(feature = "preserve-source")]
+    pub fn with_preserve_source(mut self, preserve: bool) -> Self {
+        self.preserve_source = preserve;
+        self
+    }
+
+    /// Report the source span of every match from [`extract_with_paths`](Self::extract_with_paths)
+    ///
+    /// Spans are tracked for JSON input only.
+    pub fn with_source_spans(mut self, spans: bool) -> Self {
+        self.source_spans = spans;
+        self
+    }
+
+    /// Mask sensitive fields in every document read, so no result includes them
+    ///
+    /// Applies to everything going through [`parse_document`](Self::parse_document),
+    /// e.g. `extract_with_format`, and to every NDJSON record; streaming
+    /// extraction is not redacted.
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Check, normalize and redact one parsed NDJSON record
+    fn prepare_record(&self, mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+        check_json_depth(&value, 0)?;
+        self.normalize(&mut value);
+        if let Some(redactor) = &self.redactor {
+            redactor.redact(&mut value);
+        }
+        Ok(value)
+    }
+
+    /// Parse a document in the configured input format, enforcing the nesting limit
+    pub fn parse_document(&self, input: &str) -> shard_den_core::Result<serde_json::Value> {
+        let mut value = self.input_format.parse(input)?;
+        check_json_depth(&value, 0).map_err(ShardDenError::invalid_input)?;
+        self.normalize(&mut value);
+        if let Some(redactor) = &self.redactor {
+            redactor.redact(&mut value);
+        }
+        Ok(value)
+    }
+
     pub fn extract(&self, json: &str, paths: &str) -> shard_den_core::Result<String> {
         let paths_vec = parse_paths(paths);
 
-        let value: serde_json::Value = serde_json::from_str(json)?;
-        check_json_depth(&value, 0).map_err(ShardDenError::invalid_input)?;
+        let value = self.parse_document(json)?;
         let result = self.extractor.extract(&value, &paths_vec)?;
 
         // Return just the extracted values as JSON array
-        // Each path returns an array of values from jsonpath-rust
-        let mut all_values: Vec<serde_json::Value> = Vec::new();
-        for extracted in &result.values {
-            if let serde_json::Value::Array(arr) = &extracted.value {
-                all_values.extend(arr.clone());
-            } else {
-                all_values.push(extracted.value.clone());
-            }
-        }
+        // Each path returns an array of matched values
+        let all_values = output_values(&result);
         serde_json::to_string(&all_values).map_err(Into::into)
     }
 
@@ -126,30 +320,361 @@ impl JsonExtractorCore {
     ) -> shard_den_core::Result<String> {
         let paths_vec = parse_paths(paths);
 
-        let value: serde_json::Value = serde_json::from_str(json)?;
-        check_json_depth(&value, 0).map_err(ShardDenError::invalid_input)?;
+        let value = self.parse_document(json)?;
         let result = self.extractor.extract(&value, &paths_vec)?;
 
-        // Get all extracted values (flatten arrays from jsonpath)
-        let mut all_values: Vec<serde_json::Value> = Vec::new();
-        for extracted in &result.values {
-            if let serde_json::Value::Array(arr) = &extracted.value {
-                all_values.extend(arr.clone());
-            } else {
-                all_values.push(extracted.value.clone());
-            }
-        }
+        // Get all extracted values (flatten the per-path match arrays)
+        let all_values = output_values(&result);
 
         // Convert to JSON Value for formatting
         let json_value: serde_json::Value = serde_json::to_value(&all_values)?;
-        self.formatter.format(&json_value, format)
+        row_formatter(&self.formatter, &result).format(&json_value, format)
+    }
+
+    /// Run a jq-style transform program on the extracted values
+    ///
+    /// The program receives what [`extract`](Self::extract) would return, or the
+    /// whole document when `paths` is empty. A program producing several values
+    /// returns them as an array.
+    pub fn transform(
+        &self, json: &str, paths: &str, program: &str,
+    ) -> shard_den_core::Result<serde_json::Value> {
+        let transform = Transform::parse(program)?;
+        let value = self.parse_document(json)?;
+        let paths_vec = parse_paths(paths);
+        let input = if paths_vec.is_empty() {
+            value
+        } else {
+            let result = self.extractor.extract(&value, &paths_vec)?;
+            serde_json::Value::Array(output_values(&result))
+        };
+
+        let mut outputs = transform.apply(&input)?;
+        Ok(if outputs.len() == 1 {
+            outputs.remove(0)
+        } else {
+            serde_json::Value::Array(outputs)
+        })
+    }
+
+    /// Run a transform program and format the result
+    pub fn transform_with_format(
+        &self, json: &str, paths: &str, program: &str, format: OutputFormat,
+    ) -> shard_den_core::Result<String> {
+        let value = self.transform(json, paths, program)?;
+        self.formatter.format(&value, format)
+    }
+
+    /// Aggregate the values matched by `path`
+    ///
+    /// Without `group_by` the result is one object keyed by function name;
+    /// with it, one such row per distinct group key. Each value is grouped by
+    /// the key found under the same parent, e.g. the same order.
+    pub fn aggregate(
+        &self, json: &str, path: &str, functions: &[Aggregate], group_by: Option<&str>,
+    ) -> shard_den_core::Result<serde_json::Value> {
+        let value = self.parse_document(json)?;
+        let segments = self.single_path(path)?;
+        let values = self.path_parser.locate(&value, &segments)?;
+        match group_by {
+            Some(group_path) => {
+                let key_segments = self.single_path(group_path)?;
+                let keys = self.path_parser.locate(&value, &key_segments)?;
+                let depth = aggregate::shared_depth(&segments, &key_segments);
+                let rows = aggregate::aggregate_grouped(&values, &keys, depth, functions)?;
+                Ok(serde_json::Value::Array(rows))
+            }
+            None => {
+                let values: Vec<_> = values.into_iter().map(|(_, v)| v.clone()).collect();
+                Ok(serde_json::Value::Object(aggregate::aggregate(
+                    &values, functions,
+                )?))
+            }
+        }
+    }
+
+    /// Aggregate and format the result
+    pub fn aggregate_with_format(
+        &self, json: &str, path: &str, functions: &[Aggregate], group_by: Option<&str>,
+        format: OutputFormat,
+    ) -> shard_den_core::Result<String> {
+        let value = self.aggregate(json, path, functions, group_by)?;
+        self.formatter
+            .clone()
+            .with_columns(aggregate::columns(functions, group_by.is_some()))
+            .format(&value, format)
+    }
+
+    /// The one path expression in `path`, parsed
+    fn single_path(&self, path: &str) -> shard_den_core::Result<Vec<JsonPath>> {
+        let paths = parse_paths(path);
+        if paths.len() != 1 {
+            return Err(ShardDenError::invalid_input(
+                "Aggregation takes exactly one path",
+            ));
+        }
+        self.path_parser.parse(extract::split_alias(&paths[0]).1)
+    }
+
+    /// Extract and format as CSV with custom dialect and flattening options
+    pub fn extract_csv(
+        &self, json: &str, paths: &str, options: &CsvOptions,
+    ) -> shard_den_core::Result<String> {
+        let paths_vec = parse_paths(paths);
+
+        let value = self.parse_document(json)?;
+        let result = self.extractor.extract(&value, &paths_vec)?;
+
+        let json_value = serde_json::Value::Array(output_values(&result));
+        row_formatter(&self.formatter, &result).format_csv_with(&json_value, options)
+    }
+
+    /// Extract every match together with its normalized path and JSON Pointer
+    pub fn extract_with_paths(
+        &self, json: &str, paths: &str, format: OutputFormat,
+    ) -> shard_den_core::Result<String> {
+        let paths_vec = parse_paths(paths);
+
+        let value = self.parse_document(json)?;
+        let mut result = self.extractor.extract(&value, &paths_vec)?;
+        if self.source_spans {
+            result.attach_spans(&self.span_index(json)?);
+        }
+
+        self.formatter
+            .format(&serde_json::Value::Array(result.to_located()), format)
+    }
+
+    /// Source spans of every value in a JSON document, by JSON Pointer
+    pub fn span_index(&self, json: &str) -> shard_den_core::Result<SpanIndex> {
+        if self.input_format != InputFormat::Json {
+            return Err(ShardDenError::invalid_input(
+                "Source spans are only tracked for JSON input",
+            ));
+        }
+        SpanIndex::new(json)
+    }
+
+    /// Detect paths together with observed types, counts, null ratio and an example
+    pub fn detect_stats(&self, json: &str) -> shard_den_core::Result<Vec<PathStats>> {
+        let value = self.parse_document(json)?;
+        let mut detector = PathDetector::new();
+        detector.observe(&value);
+        Ok(detector.finish())
     }
 
+    /// Extract from a reader without loading the whole document, reporting matches as found
+    pub fn extract_stream<R: std::io::Read>(
+        &self, reader: R, paths: &str,
+        on_match: impl FnMut(StreamMatch) -> shard_den_core::Result<()>,
+    ) -> shard_den_core::Result<()> {
+        let paths_vec = parse_paths(paths);
+        let mut on_match = on_match;
+        StreamExtractor::new(&self.path_parser, &paths_vec)?.run(reader, |mut m| {
+            self.normalize(&mut m.value);
+            on_match(m)
+        })
+    }
+
+    /// Sort keys and round numbers of a freshly read value, unless the source is preserved
+    #[cfg(feature = "preserve-source")]
+    fn normalize(&self, value: &mut serde_json::Value) {
+        if !self.preserve_source {
+            input::normalize(value);
+        }
+    }
+
+    /// Values are read with sorted keys and `f64` numbers already
+    #[cfg(not(feature = "preserve-source"))]
+    fn normalize(&self, _value: &mut serde_json::Value) {}
+
     pub fn detect_paths(&self, json: &str) -> shard_den_core::Result<Vec<String>> {
-        let value: serde_json::Value = serde_json::from_str(json)?;
-        check_json_depth(&value, 0).map_err(ShardDenError::invalid_input)?;
+        let value = self.parse_document(json)?;
         Ok(self.path_parser.detect_paths(&value))
     }
+
+    /// Apply the same paths to every line of an NDJSON input
+    ///
+    /// Lines that fail to parse are collected in `errors`; invalid paths abort the run.
+    pub fn extract_ndjson(
+        &self, input: &str, paths: &str,
+    ) -> shard_den_core::Result<NdjsonExtract> {
+        let paths_vec = parse_paths(paths);
+        let mut output = NdjsonExtract::default();
+
+        for (line, parsed) in ndjson::parse_lines(input) {
+            let value = match parsed.and_then(|v| self.prepare_record(v)) {
+                Ok(value) => value,
+                Err(message) => {
+                    output.errors.push(LineError { line, message });
+                    continue;
+                }
+            };
+            let result = self.extractor.extract(&value, &paths_vec)?;
+            output.records.push(LineRecord {
+                line,
+                values: output_values(&result),
+            });
+        }
+        Ok(output)
+    }
+
+    /// Validate a document against a JSON Schema, returning every violation
+    ///
+    /// The dialect is taken from the schema's `$schema` unless `draft` is given.
+    pub fn validate(
+        &self, json: &str, schema: &str, draft: Option<Draft>,
+    ) -> shard_den_core::Result<Vec<ValidationError>> {
+        let value = self.parse_document(json)?;
+        let schema: serde_json::Value = serde_json::from_str(schema)
+            .map_err(|e| ShardDenError::invalid_input(format!("Invalid schema: {}", e)))?;
+        let mut validator = Validator::new(schema)?;
+        if let Some(draft) = draft {
+            validator = validator.with_draft(draft);
+        }
+        validator.validate(&value)
+    }
+
+    /// Set every node matched by `path` to `value` (a JSON document)
+    pub fn set_value(
+        &self, json: &str, path: &str, value: &str,
+    ) -> shard_den_core::Result<Mutation> {
+        let value = self.parse_value(value)?;
+        self.mutate(json, |mutator, document| mutator.set(document, path, value))
+    }
+
+    /// Remove every node matched by `path`
+    pub fn delete_values(&self, json: &str, path: &str) -> shard_den_core::Result<Mutation> {
+        self.mutate(json, |mutator, document| mutator.delete(document, path))
+    }
+
+    /// Rename every object member matched by `path` to `new_name`
+    pub fn rename_keys(
+        &self, json: &str, path: &str, new_name: &str,
+    ) -> shard_den_core::Result<Mutation> {
+        self.mutate(json, |mutator, document| {
+            mutator.rename(document, path, new_name)
+        })
+    }
+
+    /// Redact a document with `redactor`
+    pub fn redact(&self, json: &str, redactor: &Redactor) -> shard_den_core::Result<Mutation> {
+        self.mutate(json, |_, document| Ok(redactor.redact(document)))
+    }
+
+    /// Parse a JSON value supplied alongside a document, enforcing the nesting limit
+    fn parse_value(&self, value: &str) -> shard_den_core::Result<serde_json::Value> {
+        let mut value: serde_json::Value = serde_json::from_str(value)
+            .map_err(|e| ShardDenError::invalid_input(format!("Invalid value: {}", e)))?;
+        check_json_depth(&value, 0).map_err(ShardDenError::invalid_input)?;
+        self.normalize(&mut value);
+        Ok(value)
+    }
+
+    fn mutate(
+        &self, json: &str,
+        operation: impl FnOnce(&Mutator, &mut serde_json::Value) -> shard_den_core::Result<usize>,
+    ) -> shard_den_core::Result<Mutation> {
+        let mut document = self.parse_document(json)?;
+        let changed = operation(&Mutator::new(&self.path_parser), &mut document)?;
+        check_json_depth(&document, 0).map_err(ShardDenError::invalid_input)?;
+        Ok(Mutation { document, changed })
+    }
+
+    /// Located parse errors in a document and comma-separated paths, empty when both parse
+    pub fn diagnose(&self, json: &str, paths: &str) -> Vec<Diagnostic> {
+        diagnostic::diagnose(self.input_format, &self.path_parser, json, paths)
+    }
+
+    /// Ranked completions for a partially typed path, with the cursor at byte offset `cursor`
+    pub fn complete_path(
+        &self, json: &str, partial: &str, cursor: usize,
+    ) -> shard_den_core::Result<Vec<Completion>> {
+        let value = self.parse_document(json)?;
+        Ok(self.path_parser.complete(&value, partial, cursor))
+    }
+
+    /// Differences that turn `left` into `right`
+    ///
+    /// Array elements are paired by the value at `array_key` (e.g. `$.id`) when
+    /// given, otherwise by index.
+    pub fn diff(
+        &self, left: &str, right: &str, array_key: Option<&str>,
+    ) -> shard_den_core::Result<Vec<Change>> {
+        let left = self.parse_document(left)?;
+        let right = self.parse_document(right)?;
+        let mut differ = Differ::new(&self.path_parser);
+        if let Some(key) = array_key {
+            differ = differ.with_array_key(key)?;
+        }
+        Ok(differ.diff(&left, &right))
+    }
+
+    pub fn diff_with_format(
+        &self, left: &str, right: &str, array_key: Option<&str>, format: DiffFormat,
+    ) -> shard_den_core::Result<String> {
+        diff::render(&self.diff(left, right, array_key)?, format, false)
+    }
+
+    /// Apply an RFC 6902 JSON Patch, or an RFC 7396 merge patch when `merge` is set
+    pub fn apply_patch(
+        &self, json: &str, patch: &str, merge: bool,
+    ) -> shard_den_core::Result<serde_json::Value> {
+        let mut document = self.parse_document(json)?;
+        let mut patch: serde_json::Value = serde_json::from_str(patch)
+            .map_err(|e| ShardDenError::invalid_input(format!("Invalid patch: {}", e)))?;
+        check_json_depth(&patch, 0).map_err(ShardDenError::invalid_input)?;
+        self.normalize(&mut patch);
+        if merge {
+            diff::apply_merge_patch(&mut document, &patch);
+        } else {
+            document = diff::apply_patch(&document, &diff::parse_patch(&patch)?)?;
+        }
+        check_json_depth(&document, 0).map_err(ShardDenError::invalid_input)?;
+        Ok(document)
+    }
+
+    /// Infer a JSON Schema from one or more sample documents
+    pub fn infer_schema(&self, samples: &[&str]) -> shard_den_core::Result<serde_json::Value> {
+        let mut inferrer = SchemaInferrer::new();
+        for sample in samples {
+            inferrer.observe(&self.parse_document(sample)?);
+        }
+        Ok(inferrer.finish())
+    }
+
+    /// Infer a JSON Schema treating every NDJSON record as a sample
+    pub fn infer_schema_ndjson(&self, input: &str) -> NdjsonSchema {
+        let mut errors = Vec::new();
+        let mut inferrer = SchemaInferrer::new();
+
+        for (line, parsed) in ndjson::parse_lines(input) {
+            match parsed.and_then(|v| self.prepare_record(v)) {
+                Ok(value) => inferrer.observe(&value),
+                Err(message) => errors.push(LineError { line, message }),
+            }
+        }
+        NdjsonSchema {
+            schema: inferrer.finish(),
+            errors,
+        }
+    }
+
+    /// Detect paths across every line of an NDJSON input, merging the results
+    pub fn detect_paths_ndjson(&self, input: &str) -> NdjsonDetect {
+        let mut output = NdjsonDetect::default();
+        let mut detector = PathDetector::new();
+
+        for (line, parsed) in ndjson::parse_lines(input) {
+            match parsed.and_then(|v| self.prepare_record(v)) {
+                Ok(value) => detector.observe(&value),
+                Err(message) => output.errors.push(LineError { line, message }),
+            }
+        }
+        output.stats = detector.finish();
+        output.paths = output.stats.iter().map(|s| s.path.clone()).collect();
+        output
+    }
 }
 
 impl Default for JsonExtractorCore {
@@ -166,6 +691,11 @@ pub struct JsonExtractor {
     extractor: Extractor,
     formatter: Formatter,
     path_parser: PathParser,
+    input_format: InputFormat,
+    #[cfg(feature = "preserve-source")]
+    preserve_source: bool,
+    source_spans: bool,
+    redactor: Option<Redactor>,
 }
 
 #[cfg(feature = "wasm")]
@@ -178,16 +708,95 @@ impl JsonExtractor {
             extractor: Extractor::new(),
             formatter: Formatter::new(),
             path_parser: PathParser::new(),
+            input_format: InputFormat::default(),
+            #[cfg(feature = "preserve-source")]
+            preserve_source: false,
+            source_spans: false,
+            redactor: None,
         }
     }
 
+    /// Set the input document format (json, yaml, toml, csv or xml)
+    pub fn set_input_format(&mut self, format: &str) -> Result<(), JsValue> {
+        self.input_format = InputFormat::from_name(format)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown input format: {}", format)))?;
+        Ok(())
+    }
+
+    /// Accept JSON5 / JSONC input (comments, trailing commas, unquoted keys)
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.input_format = if lenient {
+            InputFormat::Json5
+        } else {
+            InputFormat::Json
+        };
+    }
+
+    /// Set the layout of JSON output
+    ///
+    /// `indent` is `compact`, `tab` or a number of spaces; `canonical` writes
+    /// RFC 8785 output and overrides the other options.
+    pub fn set_json_options(
+        &mut self, indent: &str, sort_keys: bool, ascii: bool, canonical: bool,
+    ) -> Result<(), JsValue> {
+        let indent = JsonIndent::from_name(indent)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown indent: {}", indent)))?;
+        self.formatter = Formatter::new().with_json_options(JsonOptions {
+            indent,
+            sort_keys,
+            ascii,
+            canonical,
+        });
+        Ok(())
+    }
+
+    /// Keep the source key order and exact number text (large IDs, decimals)
+    /// instead of sorting keys and rounding numbers through `f64`
+    #[cfg(feature = "preserve-source")]
+    pub fn set_preserve_source(&mut self, preserve: bool) {
+        self.preserve_source = preserve;
+    }
+
+    /// Redact sensitive fields in every document read, e.g. by `extract_with_format`
+    ///
+    /// Takes the same arguments as [`redact`](Self::redact); with neither
+    /// `paths` nor `keys`, common credential keys are masked.
+    pub fn set_redaction(
+        &mut self, paths: &str, keys: &str, strategy: &str,
+    ) -> Result<(), JsValue> {
+        let strategy = RedactStrategy::from_name(strategy).unwrap_or_default();
+        let redactor = redact::redactor_from_lists(paths, keys, strategy)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.redactor = Some(redactor);
+        Ok(())
+    }
+
+    /// Stop redacting documents read
+    pub fn clear_redaction(&mut self) {
+        self.redactor = None;
+    }
+
+    /// Add the UTF-16 source `span` of every match to `extract_with_paths` (JSON input only)
+    pub fn set_source_spans(&mut self, spans: bool) {
+        self.source_spans = spans;
+    }
+
+    /// Switch between the lenient dialect and strict RFC 9535 parsing
+    pub fn set_strict(&mut self, strict: bool) {
+        let mode = if strict {
+            PathMode::Rfc9535
+        } else {
+            PathMode::Lenient
+        };
+        self.extractor = Extractor::with_mode(mode);
+        self.path_parser = PathParser::with_mode(mode);
+    }
+
     /// Extract fields from JSON
     pub fn extract(&self, json: &str, paths: &str) -> Result<String, JsValue> {
         let paths_vec = parse_paths(paths);
 
-        let value: serde_json::Value =
-            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        check_json_depth(&value, 0).map_err(|e| JsValue::from_str(&e))?;
+        let value = self.parse_document(json)?;
 
         let result = self
             .extractor
@@ -195,14 +804,7 @@ impl JsonExtractor {
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
         // Flatten the extracted values
-        let mut all_values: Vec<serde_json::Value> = Vec::new();
-        for extracted in &result.values {
-            if let serde_json::Value::Array(arr) = &extracted.value {
-                all_values.extend(arr.clone());
-            } else {
-                all_values.push(extracted.value.clone());
-            }
-        }
+        let all_values = output_values(&result);
 
         // If single path with single value, return unwrapped
         let json_value = if paths_vec.len() == 1 && all_values.len() == 1 {
@@ -220,9 +822,7 @@ impl JsonExtractor {
     ) -> Result<String, JsValue> {
         let paths_vec = parse_paths(paths);
 
-        let value: serde_json::Value =
-            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
-        check_json_depth(&value, 0).map_err(|e| JsValue::from_str(&e))?;
+        let value = self.parse_document(json)?;
 
         let result = self
             .extractor
@@ -230,14 +830,7 @@ impl JsonExtractor {
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
         // Flatten the extracted values
-        let mut all_values: Vec<serde_json::Value> = Vec::new();
-        for extracted in &result.values {
-            if let serde_json::Value::Array(arr) = &extracted.value {
-                all_values.extend(arr.clone());
-            } else {
-                all_values.push(extracted.value.clone());
-            }
-        }
+        let all_values = output_values(&result);
 
         // If single path with single value, return unwrapped
         let json_value: serde_json::Value = if paths_vec.len() == 1 && all_values.len() == 1 {
@@ -246,27 +839,227 @@ impl JsonExtractor {
             serde_json::to_value(&all_values).map_err(|e| JsValue::from_str(&e.to_string()))?
         };
 
-        let output_format = match format.to_lowercase().as_str() {
-            "csv" => OutputFormat::Csv,
-            "text" => OutputFormat::Text,
-            "yaml" => OutputFormat::Yaml,
-            _ => OutputFormat::Json,
+        let output_format = OutputFormat::from_name(format).unwrap_or_default();
+
+        row_formatter(&self.formatter, &result)
+            .format(&json_value, output_format)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Extract every match as `{"path", "pointer", "value"}` objects
+    pub fn extract_with_paths(&self, json: &str, paths: &str) -> Result<String, JsValue> {
+        let paths_vec = parse_paths(paths);
+
+        let value = self.parse_document(json)?;
+
+        let mut result = self
+            .extractor
+            .extract(&value, &paths_vec)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        if self.source_spans {
+            if self.input_format != InputFormat::Json {
+                return Err(JsValue::from_str(
+                    "Source spans are only tracked for JSON input",
+                ));
+            }
+            result.attach_spans(&JsonSpanIndex::new(json)?.index);
+        }
+
+        serde_json::to_string(&result.to_located()).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Auto-detect available paths in JSON
+    ///
+    /// With `stats` set, returns per-path objects with `types`, `count`,
+    /// `null_ratio` and `example` instead of plain path strings.
+    pub fn detect_paths(&self, json: &str, stats: Option<bool>) -> Result<String, JsValue> {
+        let value = self.parse_document(json)?;
+
+        let mut detector = PathDetector::new();
+        detector.observe(&value);
+        let detected = detector.finish();
+
+        if stats.unwrap_or(false) {
+            serde_json::to_string(&detected).map_err(|e| JsValue::from_str(&e.to_string()))
+        } else {
+            let paths: Vec<&str> = detected.iter().map(|s| s.path.as_str()).collect();
+            serde_json::to_string(&paths).map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+    }
+
+    /// Run a jq-style transform program on the extracted values (or the whole
+    /// document when `paths` is empty) and format the result
+    pub fn transform(
+        &self, json: &str, paths: &str, program: &str, format: &str,
+    ) -> Result<String, JsValue> {
+        let transform = Transform::parse(program).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let paths_vec = parse_paths(paths);
+
+        let value = self.parse_document(json)?;
+
+        let input = if paths_vec.is_empty() {
+            value
+        } else {
+            let result = self
+                .extractor
+                .extract(&value, &paths_vec)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            serde_json::Value::Array(output_values(&result))
+        };
+
+        let mut outputs = transform
+            .apply(&input)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let json_value = if outputs.len() == 1 {
+            outputs.remove(0)
+        } else {
+            serde_json::Value::Array(outputs)
         };
 
+        let output_format = OutputFormat::from_name(format).unwrap_or_default();
+
         self.formatter
             .format(&json_value, output_format)
             .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
-    /// Auto-detect available paths in JSON
-    pub fn detect_paths(&self, json: &str) -> Result<String, JsValue> {
-        let value: serde_json::Value =
-            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    /// Validate a document against a JSON Schema, returning the violations as a JSON array
+    pub fn validate(&self, json: &str, schema: &str) -> Result<String, JsValue> {
+        let value = self.parse_document(json)?;
+        let schema: serde_json::Value =
+            serde_json::from_str(schema).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let errors = Validator::new(schema)
+            .and_then(|v| v.validate(&value))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_json::to_string(&errors).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Set every node matched by `path` to `value` (JSON), returning the modified document
+    pub fn set(&self, json: &str, path: &str, value: &str) -> Result<String, JsValue> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(value).map_err(|e| JsValue::from_str(&e.to_string()))?;
         check_json_depth(&value, 0).map_err(|e| JsValue::from_str(&e))?;
+        self.normalize(&mut value);
+        self.mutate(json, |mutator, document| mutator.set(document, path, value))
+    }
+
+    /// Remove every node matched by `path`, returning the modified document
+    pub fn delete(&self, json: &str, path: &str) -> Result<String, JsValue> {
+        self.mutate(json, |mutator, document| mutator.delete(document, path))
+    }
 
-        let paths = self.path_parser.detect_paths(&value);
+    /// Rename every object member matched by `path`, returning the modified document
+    pub fn rename(&self, json: &str, path: &str, new_name: &str) -> Result<String, JsValue> {
+        self.mutate(json, |mutator, document| {
+            mutator.rename(document, path, new_name)
+        })
+    }
 
-        serde_json::to_string(&paths).map_err(|e| JsValue::from_str(&e.to_string()))
+    /// Redact sensitive fields, returning the redacted document
+    ///
+    /// `paths` and `keys` are comma-separated JSONPaths and key patterns (e.g.
+    /// `password,*secret*`); with neither, common credential keys are used.
+    /// `strategy` is `mask` (default), `partial`, `hash` or `remove`.
+    pub fn redact(
+        &self, json: &str, paths: &str, keys: &str, strategy: &str,
+    ) -> Result<String, JsValue> {
+        let strategy = RedactStrategy::from_name(strategy).unwrap_or_default();
+        let redactor = redact::redactor_from_lists(paths, keys, strategy)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.mutate(json, |_, document| Ok(redactor.redact(document)))
+    }
+
+    /// Located parse errors in a document and paths as a JSON array, empty when both parse
+    ///
+    /// `offset` and `column` are UTF-16 positions, matching JavaScript string indices;
+    /// path diagnostics are relative to `paths`, document ones to `json`.
+    pub fn diagnose(&self, json: &str, paths: &str) -> Result<String, JsValue> {
+        let diagnostics: Vec<Diagnostic> =
+            diagnostic::diagnose(self.input_format, &self.path_parser, json, paths)
+                .iter()
+                .map(|d| match d.source {
+                    SourceKind::Document => d.to_utf16(json),
+                    SourceKind::Path => d.to_utf16(paths),
+                })
+                .collect();
+        serde_json::to_string(&diagnostics).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Suggest completions for a partially typed path as a JSON array
+    ///
+    /// `cursor` and the returned `start` / `end` offsets are UTF-16 positions,
+    /// matching JavaScript string indices. This parses `json` on every call;
+    /// use [`completer`](Self::completer) to complete as the user types.
+    pub fn complete_path(
+        &self, json: &str, partial: &str, cursor: usize,
+    ) -> Result<String, JsValue> {
+        let value = self.parse_document(json)?;
+
+        let completions =
+            PathCompleter::new(&self.path_parser, &value).complete_utf16(partial, cursor);
+        serde_json::to_string(&completions).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// A completer for `json` that is built once and reused for every keystroke
+    pub fn completer(&self, json: &str) -> Result<JsonPathCompleter, JsValue> {
+        let value = self.parse_document(json)?;
+        Ok(JsonPathCompleter {
+            completer: PathCompleter::owned(&self.path_parser, value),
+        })
+    }
+
+    /// Compare two documents
+    ///
+    /// `format` is `patch` (RFC 6902, default), `text` or `summary`; arrays are
+    /// matched by `array_key` (e.g. `$.id`) when given.
+    pub fn diff(
+        &self, left: &str, right: &str, format: &str, array_key: Option<String>,
+    ) -> Result<String, JsValue> {
+        let parse = |json: &str| -> Result<serde_json::Value, JsValue> {
+            let value = self.parse_document(json)?;
+            Ok(value)
+        };
+        let (left, right) = (parse(left)?, parse(right)?);
+
+        let mut differ = Differ::new(&self.path_parser);
+        if let Some(key) = array_key.as_deref() {
+            differ = differ
+                .with_array_key(key)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+        let format = DiffFormat::from_name(format).unwrap_or_default();
+        diff::render(&differ.diff(&left, &right), format, false)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Apply an RFC 6902 JSON Patch, or an RFC 7396 merge patch when `merge` is set
+    pub fn apply_patch(&self, json: &str, patch: &str, merge: bool) -> Result<String, JsValue> {
+        let mut document = self.parse_document(json)?;
+        let mut patch: serde_json::Value =
+            serde_json::from_str(patch).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        check_json_depth(&patch, 0).map_err(|e| JsValue::from_str(&e))?;
+        self.normalize(&mut patch);
+
+        if merge {
+            diff::apply_merge_patch(&mut document, &patch);
+        } else {
+            document = diff::parse_patch(&patch)
+                .and_then(|operations| diff::apply_patch(&document, &operations))
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+        check_json_depth(&document, 0).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string_pretty(&document).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Infer a JSON Schema (draft 2020-12) from a sample document
+    pub fn infer_schema(&self, json: &str) -> Result<String, JsValue> {
+        let value = self.parse_document(json)?;
+
+        let mut inferrer = SchemaInferrer::new();
+        inferrer.observe(&value);
+        serde_json::to_string_pretty(&inferrer.finish())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
     #[wasm_bindgen(getter)]
@@ -280,6 +1073,46 @@ impl JsonExtractor {
     }
 }
 
+#[cfg(feature = "wasm")]
+impl JsonExtractor {
+    /// Sort keys and round numbers of a freshly read value, unless the source is preserved
+    #[cfg(feature = "preserve-source")]
+    fn normalize(&self, value: &mut serde_json::Value) {
+        if !self.preserve_source {
+            input::normalize(value);
+        }
+    }
+
+    /// Values are read with sorted keys and `f64` numbers already
+    #[cfg(not(feature = "preserve-source"))]
+    fn normalize(&self, _value: &mut serde_json::Value) {}
+
+    /// Parse a document in the configured input format, enforcing the nesting limit
+    fn parse_document(&self, json: &str) -> Result<serde_json::Value, JsValue> {
+        let mut value = self
+            .input_format
+            .parse(json)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        check_json_depth(&value, 0).map_err(|e| JsValue::from_str(&e))?;
+        self.normalize(&mut value);
+        if let Some(redactor) = &self.redactor {
+            redactor.redact(&mut value);
+        }
+        Ok(value)
+    }
+
+    fn mutate(
+        &self, json: &str,
+        operation: impl FnOnce(&Mutator, &mut serde_json::Value) -> shard_den_core::Result<usize>,
+    ) -> Result<String, JsValue> {
+        let mut document = self.parse_document(json)?;
+        operation(&Mutator::new(&self.path_parser), &mut document)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        check_json_depth(&document, 0).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string_pretty(&document).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
 #[cfg(feature = "wasm")]
 impl Default for JsonExtractor {
     fn default() -> Self {
@@ -287,6 +1120,70 @@ impl Default for JsonExtractor {
     }
 }
 
+#[cfg(feature = "wasm")]
+/// Source spans of a JSON document, kept for browsing it one level at a time
+///
+/// Offsets and columns are UTF-16 positions, matching JavaScript string indices.
+#[wasm_bindgen]
+pub struct JsonSpanIndex {
+    index: SpanIndex,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl JsonSpanIndex {
+    /// Index a JSON document
+    #[wasm_bindgen(constructor)]
+    pub fn new(json: &str) -> Result<JsonSpanIndex, JsValue> {
+        let index = SpanIndex::new_utf16(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(Self { index })
+    }
+
+    /// The node at a JSON Pointer as a JSON object, or `null` when absent
+    pub fn node(&self, pointer: &str) -> Result<String, JsValue> {
+        serde_json::to_string(&self.index.get(pointer))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The members or elements of the node at a JSON Pointer as a JSON array
+    pub fn children(&self, pointer: &str) -> Result<String, JsValue> {
+        let children = self
+            .index
+            .children(pointer)
+            .ok_or_else(|| JsValue::from_str(&format!("No value at '{}'", pointer)))?;
+        serde_json::to_string(&children).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Number of indexed values
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+#[cfg(feature = "wasm")]
+/// Path completions for one document, keeping its detected paths between calls
+#[wasm_bindgen]
+pub struct JsonPathCompleter {
+    completer: PathCompleter<'static>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl JsonPathCompleter {
+    /// Ranked completions as a JSON array of `{text, kind, detail, start, end}`
+    ///
+    /// `cursor` and the returned offsets are UTF-16 positions, as in
+    /// [`JsonExtractor::complete_path`].
+    pub fn complete(&self, partial: &str, cursor: usize) -> Result<String, JsValue> {
+        let completions = self.completer.complete_utf16(partial, cursor);
+        serde_json::to_string(&completions).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -368,6 +1265,15 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_extract_strict_mode() {
+        let extractor = JsonExtractorCore::with_mode(PathMode::Rfc9535);
+        let json = r#"{"items": [{"id": 1}, {"id": 2}]}"#;
+        let result = extractor.extract(json, "$.items[?@.id > 1].id").unwrap();
+        assert_eq!(result, "[2]");
+        assert!(extractor.extract(json, "$.items[?@..id > 1]").is_err());
+    }
+
     #[test]
     fn test_extract_multiple_paths() {
         let extractor = JsonExtractorCore::new();
@@ -414,9 +1320,9 @@ mod tests {
         for _ in 0..199 {
             json.push_str("{\"a\":");
         }
-        json.push_str("1");
+        json.push('1');
         for _ in 0..200 {
-            json.push_str("}");
+            json.push('}');
         }
 
         let result = extractor.extract(&json, "$.a");
@@ -468,6 +1374,44 @@ mod tests {
         assert_eq!(paths, vec![r#"a"b"#, "c"]);
     }
 
+    #[test]
+    fn test_parse_paths_keeps_nested_commas() {
+        let paths = parse_paths("$.a[0,2], x=$[?match(@.x,'h,*')], $['b,c'], $[?search(@, \"o\")]");
+        assert_eq!(
+            paths,
+            vec![
+                "$.a[0,2]",
+                "x=$[?match(@.x,'h,*')]",
+                "$['b,c']",
+                "$[?search(@, \"o\")]"
+            ]
+        );
+        assert_eq!(
+            parse_paths(r"$['it\'s,x'],$.b"),
+            vec![r"$['it\'s,x']", "$.b"]
+        );
+    }
+
+    #[test]
+    fn test_extract_unions_and_function_arguments() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"a": [10, 11, 12], "items": [{"x": "hello"}, {"x": "world"}, {"x": "hi"}]}"#;
+
+        assert_eq!(extractor.extract(json, "$.a[0,2]").unwrap(), "[10,12]");
+        assert_eq!(
+            extractor
+                .extract(json, "$.items[?match(@.x,'h.*')].x")
+                .unwrap(),
+            r#"["hello","hi"]"#
+        );
+        assert_eq!(
+            extractor
+                .extract(json, "$.items[?search(@.x,'o')].x, $.a[1]")
+                .unwrap(),
+            r#"["hello","world",11]"#
+        );
+    }
+
     #[test]
     fn test_extract_scalar_value_not_array() {
         // Test extracting a scalar value which is not wrapped in array
@@ -523,4 +1467,509 @@ mod tests {
         let result = check_json_depth(&json, 0);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_extract_ndjson() {
+        let extractor = JsonExtractorCore::new();
+        let input = "{\"id\": 1, \"tags\": [\"a\"]}\n\n{\"id\": 2\n{\"id\": 3}\n";
+        let result = extractor.extract_ndjson(input, "$.id").unwrap();
+
+        assert_eq!(result.records.len(), 2);
+        assert_eq!(result.records[0].line, 1);
+        assert_eq!(result.records[0].values, vec![serde_json::json!(1)]);
+        assert_eq!(result.records[1].line, 4);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, 3);
+    }
+
+    #[test]
+    fn test_extract_ndjson_invalid_path() {
+        let extractor = JsonExtractorCore::new();
+        assert!(extractor.extract_ndjson("{\"a\": 1}", "$[").is_err());
+    }
+
+    #[test]
+    fn test_detect_paths_ndjson_merges_records() {
+        let extractor = JsonExtractorCore::new();
+        let input = "{\"a\": 1}\n{\"a\": 2, \"b\": true}\nnot json\n";
+        let result = extractor.detect_paths_ndjson(input);
+
+        assert_eq!(result.paths, vec!["$.a".to_string(), "$.b".to_string()]);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, 3);
+    }
+
+    #[test]
+    fn test_extract_with_aliases_csv() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"users": [{"name": "a", "email": "a@x"}, {"name": "b", "email": "b@x"}]}"#;
+        let result = extractor
+            .extract_with_format(
+                json,
+                "name=$.users[*].name, email=$.users[*].email",
+                OutputFormat::Csv,
+            )
+            .unwrap();
+        assert_eq!(result, "name,email\na,a@x\nb,b@x\n");
+
+        // Columns keep the order given, not the key order
+        let paths = "zname=$.users[*].name, aemail=$.users[*].email";
+        let csv = extractor
+            .extract_csv(json, paths, &CsvOptions::default())
+            .unwrap();
+        assert_eq!(csv, "zname,aemail\na,a@x\nb,b@x\n");
+        let md = extractor
+            .extract_with_format(json, paths, OutputFormat::Markdown)
+            .unwrap();
+        assert!(md.starts_with("| zname | aemail |\n"), "{}", md);
+        assert_eq!(alias_columns(paths), vec!["zname", "aemail"]);
+        assert!(alias_columns("$.a, $.b").is_empty());
+    }
+
+    #[test]
+    fn test_extract_with_paths() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"users": [{"email": "a@x"}, {"email": "b@x"}]}"#;
+        let result = extractor
+            .extract_with_paths(json, "$.users[*].email", OutputFormat::Json)
+            .unwrap();
+        let located: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(located[1]["path"], "$['users'][1]['email']");
+        assert_eq!(located[1]["pointer"], "/users/1/email");
+        assert_eq!(located[1]["value"], "b@x");
+    }
+
+    #[test]
+    fn test_extract_with_source_spans() {
+        let json = "{\n  \"users\": [\n    {\"email\": \"a@x\"},\n    {\"email\": \"b@x\"}\n  ]\n}";
+        let result = JsonExtractorCore::new()
+            .with_source_spans(true)
+            .extract_with_paths(json, "$.users[*].email", OutputFormat::Json)
+            .unwrap();
+        let located: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(located[1]["span"]["line"], 4);
+        assert_eq!(located[1]["span"]["column"], 15);
+        let start = located[1]["span"]["start"].as_u64().unwrap() as usize;
+        assert_eq!(&json[start..start + 5], "\"b@x\"");
+
+        let yaml = JsonExtractorCore::new()
+            .with_input_format(InputFormat::Yaml)
+            .with_source_spans(true);
+        assert!(yaml
+            .extract_with_paths("a: 1", "$.a", OutputFormat::Json)
+            .is_err());
+    }
+
+    #[test]
+    fn test_extract_csv_with_options() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"users": [{"name": "a", "address": {"city": "Oslo"}}]}"#;
+        let options = CsvOptions {
+            delimiter: ';',
+            flatten: true,
+            ..CsvOptions::default()
+        };
+        let result = extractor.extract_csv(json, "$.users[*]", &options).unwrap();
+        assert_eq!(result, "address.city;name\nOslo;a\n");
+    }
+
+    #[test]
+    fn test_extract_with_input_format() {
+        let extractor = JsonExtractorCore::new().with_input_format(InputFormat::Yaml);
+        let result = extractor
+            .extract("users:\n  - name: a\n  - name: b\n", "$.users[*].name")
+            .unwrap();
+        assert_eq!(result, r#"["a","b"]"#);
+
+        let extractor = JsonExtractorCore::new().with_input_format(InputFormat::Xml);
+        let paths = extractor
+            .detect_paths(r#"<config><port>80</port></config>"#)
+            .unwrap();
+        assert!(paths.contains(&"$.config.port".to_string()));
+    }
+
+    #[test]
+    fn test_extract_lenient_input() {
+        let input = "{\n  // comment\n  compilerOptions: {strict: true,},\n}";
+        let strict = JsonExtractorCore::new();
+        assert!(strict.extract(input, "$.compilerOptions.strict").is_err());
+
+        let lenient = JsonExtractorCore::new().with_input_format(InputFormat::Json5);
+        let result = lenient.extract(input, "$.compilerOptions.strict").unwrap();
+        assert_eq!(result, "[true]");
+
+        let err = lenient.extract("{a: }", "$.a").unwrap_err();
+        assert!(err.to_string().contains("line 1 column 5"), "{}", err);
+    }
+
+    #[test]
+    fn test_detect_stats() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"items": [{"id": 1}, {"id": null, "tag": "x"}]}"#;
+        let stats = extractor.detect_stats(json).unwrap();
+
+        let id = stats.iter().find(|s| s.path == "$.items[*].id").unwrap();
+        assert_eq!(id.types, vec!["number", "null"]);
+        assert_eq!(id.count, 2);
+        assert_eq!(id.null_ratio, 0.5);
+        assert!(stats.iter().any(|s| s.path == "$.items[*].tag"));
+    }
+
+    #[test]
+    fn test_mutations() {
+        let extractor = JsonExtractorCore::new().with_input_format(InputFormat::Yaml);
+        let yaml = "users:\n  - name: a\n  - name: b\n    tmp: 1\n";
+
+        let set = extractor
+            .set_value(yaml, "$.users[*].active", "true")
+            .unwrap();
+        assert_eq!(set.changed, 2);
+        assert_eq!(set.document["users"][1]["active"], true);
+
+        let deleted = extractor.delete_values(yaml, "$..tmp").unwrap();
+        assert_eq!(deleted.changed, 1);
+        assert_eq!(
+            deleted.document,
+            serde_json::json!({"users": [{"name": "a"}, {"name": "b"}]})
+        );
+
+        let renamed = extractor
+            .rename_keys(yaml, "$.users[*].name", "login")
+            .unwrap();
+        assert_eq!(renamed.document["users"][0]["login"], "a");
+
+        assert!(extractor.set_value(yaml, "$.x", "{not json").is_err());
+        assert!(extractor.delete_values(yaml, "$[").is_err());
+    }
+
+    #[test]
+    fn test_json_options() {
+        let json = r#"{"id": 1e3, "name": "Zoë"}"#;
+        let extractor = JsonExtractorCore::new().with_json_options(JsonOptions::canonical());
+        let output = extractor
+            .extract_with_format(json, "$", OutputFormat::Json)
+            .unwrap();
+        assert_eq!(output, r#"[{"id":1000,"name":"Zoë"}]"#);
+
+        let extractor = JsonExtractorCore::new().with_json_options(JsonOptions {
+            ascii: true,
+            ..JsonOptions::compact()
+        });
+        let output = extractor
+            .extract_with_format(json, "$.name", OutputFormat::Json)
+            .unwrap();
+        assert_eq!(output, r#"["Zo\u00eb"]"#);
+    }
+
+    #[test]
+    #[cfg(feature = "preserve-source")]
+    fn test_preserve_source_snowflake_ids() {
+        let json = r#"{"users": [
+            {"name": "a", "id": 1185937385939329024},
+            {"name": "b", "id": 18446744073709551617}
+        ]}"#;
+        let extractor = JsonExtractorCore::new().with_preserve_source(true);
+        let output = extractor.extract(json, "$.users[*].id").unwrap();
+        assert_eq!(output, "[1185937385939329024,18446744073709551617]");
+
+        let csv = extractor
+            .extract_with_format(json, "$.users[*]", OutputFormat::Csv)
+            .unwrap();
+        assert_eq!(
+            csv,
+            "name,id\na,1185937385939329024\nb,18446744073709551617\n"
+        );
+        let yaml = extractor
+            .extract_with_format(json, "$.users[1].id", OutputFormat::Yaml)
+            .unwrap();
+        assert_eq!(yaml, "- 18446744073709551617\n");
+        let matches = extractor
+            .extract(json, "$.users[?@.id == 1185937385939329024].name")
+            .unwrap();
+        assert_eq!(matches, r#"["a"]"#);
+
+        // Without it, keys are sorted and IDs beyond 64 bits go through f64
+        let output = JsonExtractorCore::new()
+            .extract_with_format(json, "$.users[1]", OutputFormat::Csv)
+            .unwrap();
+        assert_eq!(output, "id,name\n1.8446744073709552e+19,b\n");
+    }
+
+    #[test]
+    #[cfg(feature = "preserve-source")]
+    fn test_preserve_source_money_values() {
+        let json = r#"{"items": [{"sku": "x", "price": 19.90}, {"sku": "y", "price": 1234567890123456.78}]}"#;
+        let extractor = JsonExtractorCore::new().with_preserve_source(true);
+        let prices = extractor.extract(json, "$.items[*].price").unwrap();
+        assert_eq!(prices, "[19.90,1234567890123456.78]");
+
+        for (format, expected) in [
+            (
+                OutputFormat::Csv,
+                "sku,price\nx,19.90\ny,1234567890123456.78\n",
+            ),
+            (
+                OutputFormat::Yaml,
+                "- sku: x\n  price: 19.90\n- sku: y\n  price: 1234567890123456.78\n",
+            ),
+        ] {
+            let output = extractor
+                .extract_with_format(json, "$.items[*]", format)
+                .unwrap();
+            assert_eq!(output, expected);
+        }
+        let text = extractor
+            .extract_with_format(json, "$.items[1].price", OutputFormat::Text)
+            .unwrap();
+        assert_eq!(text, "1234567890123456.78");
+
+        let mutation = extractor
+            .set_value(json, "$.items[0].price", "0.10")
+            .unwrap();
+        assert_eq!(mutation.document["items"][0]["price"].to_string(), "0.10");
+        assert_eq!(
+            mutation.document["items"][1]["price"].to_string(),
+            "1234567890123456.78"
+        );
+
+        let prices = JsonExtractorCore::new()
+            .extract(json, "$.items[*].price")
+            .unwrap();
+        assert_eq!(prices, "[19.9,1234567890123456.8]");
+    }
+
+    #[test]
+    fn test_redaction() {
+        let json = r#"{"user": "ann", "password": "hunter22", "card": "4111111111111111"}"#;
+        let redactor = Redactor::with_default_keys(RedactStrategy::Mask)
+            .with_path("$.card")
+            .unwrap();
+
+        let redacted = JsonExtractorCore::new().redact(json, &redactor).unwrap();
+        assert_eq!(redacted.changed, 2);
+        assert_eq!(redacted.document["password"], "********");
+
+        let extractor = JsonExtractorCore::new().with_redactor(redactor);
+        let output = extractor
+            .extract_with_format(json, "$.password,$.user", OutputFormat::Json)
+            .unwrap();
+        assert!(!output.contains("hunter22"));
+        assert!(output.contains("ann"));
+    }
+
+    #[test]
+    fn test_redaction_ndjson() {
+        let extractor = JsonExtractorCore::new()
+            .with_redactor(Redactor::with_default_keys(RedactStrategy::Mask));
+        let input = "{\"user\": \"ann\", \"password\": \"hunter2\"}\n{\"token\": \"abc\"}\n";
+
+        let result = extractor.extract_ndjson(input, "$").unwrap();
+        let output = serde_json::to_string(&result.records[0].values).unwrap();
+        assert!(!output.contains("hunter2"));
+        assert_eq!(result.records[0].values[0]["password"], "********");
+        assert_eq!(result.records[1].values[0]["token"], "********");
+
+        let schema = extractor.infer_schema_ndjson(input).schema;
+        assert!(!schema.to_string().contains("hunter2"));
+        let detected = extractor.detect_paths_ndjson(input);
+        assert!(detected
+            .stats
+            .iter()
+            .all(|s| s.example != Some(serde_json::json!("hunter2"))));
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"users": [{"id": 1, "name": "a"}, {"id": 2}]}"#;
+        let completions = extractor.complete_path(json, "$.users[*].", 11).unwrap();
+        let texts: Vec<&str> = completions.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec![".id", ".name"]);
+        assert_eq!(completions[0].kind, CompletionKind::Key);
+        assert!(extractor.complete_path("{", "$.", 2).is_err());
+        assert!(extractor.complete_path(json, "é", 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_and_patch() {
+        let extractor = JsonExtractorCore::new();
+        let staging = r#"{"version": "1.2", "items": [{"id": 1, "qty": 2}, {"id": 2, "qty": 1}]}"#;
+        let production =
+            r#"{"version": "1.3", "items": [{"id": 2, "qty": 1}, {"id": 1, "qty": 3}]}"#;
+
+        let changes = extractor.diff(staging, production, Some("$.id")).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].kind, ChangeKind::Moved);
+
+        let patch = extractor
+            .diff_with_format(staging, production, Some("$.id"), DiffFormat::Patch)
+            .unwrap();
+        let patched = extractor.apply_patch(staging, &patch, false).unwrap();
+        assert_eq!(
+            patched,
+            serde_json::from_str::<serde_json::Value>(production).unwrap()
+        );
+
+        let merged = extractor
+            .apply_patch(staging, r#"{"version": null, "env": "prod"}"#, true)
+            .unwrap();
+        assert_eq!(merged["env"], "prod");
+        assert!(merged.get("version").is_none());
+
+        assert!(extractor.diff(staging, production, Some("$[")).is_err());
+        assert!(extractor
+            .apply_patch(staging, r#"[{"op": "remove"}]"#, false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"orders": [
+            {"customer": "a", "total": 10},
+            {"customer": "b", "total": 5},
+            {"customer": "a", "total": 20}
+        ]}"#;
+        let functions = Aggregate::parse_list("count,sum,max").unwrap();
+        let result = extractor
+            .aggregate(json, "$.orders[*].total", &functions, None)
+            .unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({"count": 3, "sum": 35, "max": 20})
+        );
+
+        let csv = extractor
+            .aggregate_with_format(
+                json,
+                "$.orders[*].total",
+                &functions,
+                Some("$.orders[*].customer"),
+                OutputFormat::Csv,
+            )
+            .unwrap();
+        assert_eq!(csv, "group,count,sum,max\na,2,30,20\nb,1,5,5\n");
+        let csv = extractor
+            .aggregate_with_format(
+                json,
+                "$.orders[*].total",
+                &functions,
+                None,
+                OutputFormat::Csv,
+            )
+            .unwrap();
+        assert_eq!(csv, "count,sum,max\n3,35,20\n");
+
+        // Keys pair with values through the order they belong to, not their position
+        let sparse = r#"{"orders": [{"c": "a"}, {"c": "b", "total": 20}, {"total": 5}]}"#;
+        let err = extractor
+            .aggregate(
+                sparse,
+                "$.orders[*].total",
+                &functions,
+                Some("$.orders[*].c"),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("No group key"));
+        let sparse = r#"{"orders": [{"c": "a"}, {"c": "b", "total": 20}]}"#;
+        let result = extractor
+            .aggregate(
+                sparse,
+                "$.orders[*].total",
+                &functions,
+                Some("$.orders[*].c"),
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!([{"group": "b", "count": 1, "sum": 20, "max": 20}])
+        );
+
+        assert!(extractor
+            .aggregate(json, "$.orders[*].total,$.x", &functions, None)
+            .is_err());
+        assert!(extractor
+            .aggregate(json, "$.orders[*].customer", &functions, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_transform_after_extraction() {
+        let extractor = JsonExtractorCore::new();
+        let json = r#"{"users": [{"name": "a", "age": 30}, {"name": "b", "age": 20}]}"#;
+        let result = extractor
+            .transform(json, "$.users[*]", "sort_by(.age) | map(.name)")
+            .unwrap();
+        assert_eq!(result, serde_json::json!(["b", "a"]));
+
+        let result = extractor.transform(json, "", ".users[] | .name").unwrap();
+        assert_eq!(result, serde_json::json!(["a", "b"]));
+
+        let csv = extractor
+            .transform_with_format(json, "", ".users | map({name})", OutputFormat::Csv)
+            .unwrap();
+        assert_eq!(csv, "name\na\nb\n");
+
+        assert!(extractor.transform(json, "", "map(").is_err());
+    }
+
+    #[test]
+    fn test_validate_document() {
+        let extractor = JsonExtractorCore::new();
+        let schema = r#"{"type": "object", "properties": {"id": {"type": "integer"}}}"#;
+        assert!(extractor
+            .validate(r#"{"id": 1}"#, schema, None)
+            .unwrap()
+            .is_empty());
+
+        let errors = extractor.validate(r#"{"id": "x"}"#, schema, None).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/id");
+        assert_eq!(errors[0].schema_path, "/properties/id/type");
+
+        assert!(extractor.validate("{}", "{bad", None).is_err());
+        assert!(extractor.validate("{}", "1", Some(Draft::Draft7)).is_err());
+    }
+
+    #[test]
+    fn test_infer_schema_ndjson() {
+        let extractor = JsonExtractorCore::new();
+        let result = extractor.infer_schema_ndjson("{\"a\": 1}\n{bad}\n{\"a\": null}\n");
+        assert_eq!(
+            result.schema["properties"]["a"]["type"],
+            serde_json::json!(["integer", "null"])
+        );
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, 2);
+    }
+
+    #[test]
+    fn test_infer_schema_from_samples() {
+        let extractor = JsonExtractorCore::new();
+        let schema = extractor
+            .infer_schema(&[r#"{"id": 1, "tag": "x"}"#, r#"{"id": 2}"#])
+            .unwrap();
+        assert_eq!(schema["$schema"], infer::SCHEMA_DIALECT);
+        assert_eq!(schema["required"], serde_json::json!(["id"]));
+
+        assert!(extractor.infer_schema(&["{bad"]).is_err());
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let extractor = JsonExtractorCore::new();
+        assert!(extractor.diagnose(r#"{"a": 1}"#, "$.a, $.b").is_empty());
+
+        let diagnostics = extractor.diagnose("{\"a\": }", "$.a, $[");
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].source, SourceKind::Document);
+        assert_eq!(diagnostics[0].column, 7);
+        assert_eq!(diagnostics[1].source, SourceKind::Path);
+        assert_eq!(diagnostics[1].offset, 7);
+
+        let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+        let diagnostics = extractor.diagnose(&deep, "$");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].source, SourceKind::Document);
+    }
 }
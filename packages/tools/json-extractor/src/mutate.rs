@@ -0,0 +1,328 @@
+//! Write operations at JSONPath locations
+//!
+//! Matches are located with the same path engine as extraction, then edited
+//! through their normalized paths. `set` on a path ending in a member name
+//! writes that member on every matched parent object, so it can add missing
+//! fields as well as replace existing ones; a path made only of member names
+//! creates any missing intermediate objects.
+
+use crate::path::{JsonPath, NormalizedPath, PathElement, PathParser};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use shard_den_core::{Result, ShardDenError};
+use std::collections::HashSet;
+
+/// A modified document and the number of nodes changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mutation {
+    pub document: Value,
+    pub changed: usize,
+}
+
+/// Applies set, delete and rename operations to a document
+#[derive(Debug)]
+pub struct Mutator<'a> {
+    parser: &'a PathParser,
+}
+
+impl<'a> Mutator<'a> {
+    pub fn new(parser: &'a PathParser) -> Self {
+        Self { parser }
+    }
+
+    /// Set every node matched by `path` to `value`, returning the number of nodes written
+    pub fn set(&self, document: &mut Value, path: &str, value: Value) -> Result<usize> {
+        let segments = self.parser.parse(path)?;
+        let Some((JsonPath::Key(key), parent)) = segments.split_last() else {
+            let targets = self.locate(document, &segments)?;
+            for target in &targets {
+                if let Some(node) = node_mut(document, target.elements()) {
+                    *node = value.clone();
+                }
+            }
+            return Ok(targets.len());
+        };
+
+        // A plain key chain is created as needed; anything else only writes where it matches
+        let names: Option<Vec<&String>> = parent
+            .iter()
+            .map(|segment| match segment {
+                JsonPath::Key(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        if let Some(names) = names {
+            let mut node = document;
+            for name in names {
+                node = object_mut(node, name)?
+                    .entry(name.clone())
+                    .or_insert_with(|| Value::Object(Map::new()));
+            }
+            object_mut(node, key)?.insert(key.clone(), value);
+            return Ok(1);
+        }
+
+        let mut written = 0;
+        for parent in &self.locate(document, parent)? {
+            if let Some(Value::Object(map)) = node_mut(document, parent.elements()) {
+                map.insert(key.clone(), value.clone());
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Remove every node matched by `path`, returning the number of nodes removed
+    pub fn delete(&self, document: &mut Value, path: &str) -> Result<usize> {
+        let segments = self.parser.parse(path)?;
+        let mut removed = 0;
+        for target in self.locate_deepest_first(document, &segments)? {
+            let Some((last, parent)) = target.elements().split_last() else {
+                return Err(ShardDenError::invalid_input(
+                    "Cannot delete the document root",
+                ));
+            };
+            let gone = match (node_mut(document, parent), last) {
+                (Some(Value::Object(map)), PathElement::Key(key)) => {
+                    remove_member(map, key).is_some()
+                }
+                (Some(Value::Array(arr)), PathElement::Index(i)) if *i < arr.len() => {
+                    arr.remove(*i);
+                    true
+                }
+                _ => false,
+            };
+            removed += usize::from(gone);
+        }
+        Ok(removed)
+    }
+
+    /// Rename every object member matched by `path` to `new_name`, returning the number renamed
+    ///
+    /// Fails without changing anything if a match is not an object member or
+    /// its object already has a member called `new_name`.
+    pub fn rename(&self, document: &mut Value, path: &str, new_name: &str) -> Result<usize> {
+        let segments = self.parser.parse(path)?;
+        let targets = self.locate_deepest_first(document, &segments)?;
+        let mut parents = HashSet::new();
+        for target in &targets {
+            match target.elements().last() {
+                Some(PathElement::Key(key)) if key == new_name => {}
+                Some(PathElement::Key(_)) => {
+                    let parent = &target.elements()[..target.elements().len() - 1];
+                    if !parents.insert(parent) {
+                        return Err(ShardDenError::invalid_input(format!(
+                            "Cannot rename {}: another member of the same object is also renamed to '{}'",
+                            target, new_name
+                        )));
+                    }
+                    if let Some(Value::Object(map)) = node_mut(document, parent) {
+                        if map.contains_key(new_name) {
+                            return Err(ShardDenError::invalid_input(format!(
+                                "Cannot rename {}: '{}' already exists",
+                                target, new_name
+                            )));
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ShardDenError::invalid_input(format!(
+                        "Cannot rename {}: only object members can be renamed",
+                        target
+                    )))
+                }
+            }
+        }
+
+        let mut renamed = 0;
+        for target in &targets {
+            let Some((PathElement::Key(key), parent)) = target.elements().split_last() else {
+                continue;
+            };
+            if key == new_name {
+                continue;
+            }
+            if let Some(Value::Object(map)) = node_mut(document, parent) {
+                if rename_member(map, key, new_name) {
+                    renamed += 1;
+                }
+            }
+        }
+        Ok(renamed)
+    }
+
+    fn locate(&self, document: &Value, segments: &[JsonPath]) -> Result<Vec<NormalizedPath>> {
+        Ok(self
+            .parser
+            .locate(document, segments)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect())
+    }
+
+    /// Matched locations ordered so that removing one never shifts another:
+    /// children before parents, later array indices before earlier ones
+    fn locate_deepest_first(
+        &self, document: &Value, segments: &[JsonPath],
+    ) -> Result<Vec<NormalizedPath>> {
+        let mut targets = self.locate(document, segments)?;
+        targets.sort_by(|a, b| cmp_elements(b.elements(), a.elements()));
+        targets.dedup();
+        Ok(targets)
+    }
+}
+
+/// Order locations by their elements, with a prefix before its extensions
+fn cmp_elements(a: &[PathElement], b: &[PathElement]) -> std::cmp::Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let ordering = match (x, y) {
+            (PathElement::Index(x), PathElement::Index(y)) => x.cmp(y),
+            (PathElement::Key(x), PathElement::Key(y)) => x.cmp(y),
+            (PathElement::Index(_), PathElement::Key(_)) => std::cmp::Ordering::Less,
+            (PathElement::Key(_), PathElement::Index(_)) => std::cmp::Ordering::Greater,
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// The node at `elements`, if it still exists
+pub(crate) fn node_mut<'v>(root: &'v mut Value, elements: &[PathElement]) -> Option<&'v mut Value> {
+    elements
+        .iter()
+        .try_fold(root, |node, element| match (node, element) {
+            (Value::Object(map), PathElement::Key(key)) => map.get_mut(key),
+            (Value::Array(arr), PathElement::Index(i)) => arr.get_mut(*i),
+            _ => None,
+        })
+}
+
+/// Remove the member `key`, leaving the members after it in their source order
+pub(crate) fn remove_member(map: &mut Map<String, Value>, key: &str) -> Option<Value> {
+    #[cfg(feature = "preserve-source")]
+    let removed = map.shift_remove(key);
+    #[cfg(not(feature = "preserve-source"))]
+    let removed = map.remove(key);
+    removed
+}
+
+/// Rename the member `key` to `new_name`, keeping its position in source order
+fn rename_member(map: &mut Map<String, Value>, key: &str, new_name: &str) -> bool {
+    #[cfg(feature = "preserve-source")]
+    let index = map.keys().position(|k| k == key);
+    let Some(value) = remove_member(map, key) else {
+        return false;
+    };
+    #[cfg(feature = "preserve-source")]
+    if let Some(index) = index {
+        map.shift_insert(index, new_name.to_string(), value);
+        return true;
+    }
+    map.insert(new_name.to_string(), value);
+    true
+}
+
+/// `node` as an object, for creating the member `key`
+fn object_mut<'v>(node: &'v mut Value, key: &str) -> Result<&'v mut Map<String, Value>> {
+    match node {
+        Value::Object(map) => Ok(map),
+        other => Err(ShardDenError::invalid_input(format!(
+            "Cannot create '{}': parent is {}, not an object",
+            key,
+            crate::detect::json_type(other)
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_set_replaces_and_adds_members() {
+        let parser = PathParser::new();
+        let mutator = Mutator::new(&parser);
+        let mut doc = json!({"users": [{"name": "a", "active": false}, {"name": "b"}]});
+        let written = mutator
+            .set(&mut doc, "$.users[*].active", json!(true))
+            .unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(
+            doc,
+            json!({"users": [{"name": "a", "active": true}, {"name": "b", "active": true}]})
+        );
+
+        assert_eq!(mutator.set(&mut doc, "$.users[1]", json!(null)).unwrap(), 1);
+        assert_eq!(doc["users"][1], Value::Null);
+    }
+
+    #[test]
+    fn test_set_creates_missing_objects() {
+        let parser = PathParser::new();
+        let mutator = Mutator::new(&parser);
+        let mut doc = json!({"a": 1});
+        assert_eq!(mutator.set(&mut doc, "$.b.c.d", json!("x")).unwrap(), 1);
+        assert_eq!(doc, json!({"a": 1, "b": {"c": {"d": "x"}}}));
+
+        assert!(mutator.set(&mut doc, "$.a.x", json!(1)).is_err());
+        assert_eq!(mutator.set(&mut doc, "$.missing[0]", json!(1)).unwrap(), 0);
+
+        assert_eq!(mutator.set(&mut doc, "$", json!([])).unwrap(), 1);
+        assert_eq!(doc, json!([]));
+    }
+
+    #[test]
+    fn test_delete() {
+        let parser = PathParser::new();
+        let mutator = Mutator::new(&parser);
+        let mut doc = json!({"items": [1, 2, 3, 4, 5], "meta": {"x": 1, "y": 2}});
+        assert_eq!(mutator.delete(&mut doc, "$.items[0,2,4]").unwrap(), 3);
+        assert_eq!(doc["items"], json!([2, 4]));
+
+        // Nested matches inside a deleted parent are simply gone
+        assert_eq!(mutator.delete(&mut doc, "$..*").unwrap(), 6);
+        assert_eq!(doc, json!({}));
+
+        assert!(mutator.delete(&mut doc, "$").is_err());
+    }
+
+    #[test]
+    fn test_delete_with_filter() {
+        let parser = PathParser::new();
+        let mutator = Mutator::new(&parser);
+        let mut doc = json!([{"id": 1, "draft": true}, {"id": 2}, {"id": 3, "draft": true}]);
+        assert_eq!(mutator.delete(&mut doc, "$[?@.draft]").unwrap(), 2);
+        assert_eq!(doc, json!([{"id": 2}]));
+    }
+
+    #[test]
+    fn test_rename() {
+        let parser = PathParser::new();
+        let mutator = Mutator::new(&parser);
+        let mut doc = json!({"rows": [{"id": 1}, {"id": 2}, {"uid": 3}]});
+        assert_eq!(mutator.rename(&mut doc, "$.rows[*].id", "uid").unwrap(), 2);
+        assert_eq!(doc, json!({"rows": [{"uid": 1}, {"uid": 2}, {"uid": 3}]}));
+
+        let mut doc = json!({"a": 1, "b": 2});
+        let err = mutator.rename(&mut doc, "$.a", "b").unwrap_err();
+        assert!(err.to_string().contains("'b' already exists"));
+        assert!(mutator.rename(&mut doc, "$", "x").is_err());
+        assert!(mutator.rename(&mut doc, "$['a','b']", "c").is_err());
+        assert_eq!(doc, json!({"a": 1, "b": 2}));
+    }
+
+    #[cfg(feature = "preserve-source")]
+    #[test]
+    fn test_preserve_source_member_order() {
+        let parser = PathParser::new();
+        let mutator = Mutator::new(&parser);
+        let mut doc: Value = serde_json::from_str(r#"{"c": 1, "a": 2, "d": 3, "b": 4}"#).unwrap();
+        assert_eq!(mutator.delete(&mut doc, "$.a").unwrap(), 1);
+        assert_eq!(doc.to_string(), r#"{"c":1,"d":3,"b":4}"#);
+        assert_eq!(mutator.rename(&mut doc, "$.d", "z").unwrap(), 1);
+        assert_eq!(doc.to_string(), r#"{"c":1,"z":3,"b":4}"#);
+    }
+}
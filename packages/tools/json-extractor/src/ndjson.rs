@@ -0,0 +1,107 @@
+//! JSON Lines / NDJSON input
+//!
+//! Each non-blank line is an independent JSON document. Lines that fail to
+//! parse are reported with their line number instead of aborting the run.
+
+use crate::detect::PathStats;
+use serde_json::Value;
+use std::fmt;
+
+/// A line that could not be processed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Values extracted from a single line
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineRecord {
+    /// 1-based line number
+    pub line: usize,
+    pub values: Vec<Value>,
+}
+
+/// Result of extracting from every line of an NDJSON input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NdjsonExtract {
+    pub records: Vec<LineRecord>,
+    pub errors: Vec<LineError>,
+}
+
+/// Paths detected across all records of an NDJSON input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NdjsonDetect {
+    /// Union of the paths of every record, in first-seen order
+    pub paths: Vec<String>,
+    /// Type statistics per path, aggregated over all records
+    pub stats: Vec<PathStats>,
+    pub errors: Vec<LineError>,
+}
+
+/// JSON Schema inferred from all records of an NDJSON input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NdjsonSchema {
+    pub schema: Value,
+    pub errors: Vec<LineError>,
+}
+
+/// Parse each non-blank line, yielding its 1-based line number
+pub fn parse_lines(input: &str) -> impl Iterator<Item = (usize, Result<Value, String>)> + '_ {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, serde_json::from_str(line).map_err(|e| describe(&e))))
+}
+
+/// Error message with the column only, since the line is reported separately
+fn describe(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(m, _)| m);
+    format!("{} at column {}", message, err.column())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_lines_skips_blank_lines() {
+        let input = "{\"a\": 1}\n\n  \r\n[2]\r\n";
+        let lines: Vec<_> = parse_lines(input).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], (1, Ok(json!({"a": 1}))));
+        assert_eq!(lines[1], (4, Ok(json!([2]))));
+    }
+
+    #[test]
+    fn test_parse_lines_reports_errors() {
+        let lines: Vec<_> = parse_lines("{\"a\": 1}\n{bad}\n").collect();
+        assert!(lines[0].1.is_ok());
+        assert_eq!(lines[1].0, 2);
+        assert_eq!(
+            lines[1].1,
+            Err("key must be a string at column 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_line_error_display() {
+        let err = LineError {
+            line: 3,
+            message: "expected value".to_string(),
+        };
+        assert_eq!(err.to_string(), "line 3: expected value");
+    }
+}
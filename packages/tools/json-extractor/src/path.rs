@@ -1,79 +1,925 @@
 //! Path parsing and traversal
+//!
+//! Supported syntax:
+//! - `$` - Document root
+//! - `.key` / `['key']` - Child member
+//! - `*` / `[*]` - Wildcard
+//! - `[0]` / `[-1]` - Array index (negative counts from the end)
+//! - `[start:end:step]` - Array slice
+//! - `['a','b']` / `[0,2]` - Union of selectors
+//! - `..key` / `..*` / `..[0]` - Recursive descent
+//! - `[?(@.price > 10)]` / `[?@.isbn]` - Filter expression
 
+use crate::complete::{Completion, PathCompleter};
+use crate::detect::PathDetector;
+use crate::filter::{
+    CompareOp, FilterExpr, Function, FunctionCall, FunctionType, Operand, Query, QueryRoot,
+};
 use serde_json::Value;
-use shard_den_core::Result;
+use shard_den_core::{Result, ShardDenError};
+use std::fmt;
 
-/// A parsed JSON path
-#[derive(Debug, Clone)]
+/// A parsed JSON path segment
+#[derive(Debug, Clone, PartialEq)]
 pub enum JsonPath {
+    /// `.key` or `['key']`
     Key(String),
-    Index(usize),
+    /// `[0]`, negative values count from the end of the array
+    Index(i64),
+    /// `*` or `[*]`
     Wildcard,
+    /// `..key`
     Recursive(String),
+    /// `..` followed by any selector other than a member name, e.g. `..*` or `..[0]`
+    Descendant(Box<JsonPath>),
+    /// `[start:end:step]`
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    /// `['a','b']` or `[0,2]`
+    Union(Vec<JsonPath>),
+    /// `[?(@.price > 10)]`
+    Filter(FilterExpr),
+}
+
+/// Error produced when a path string cannot be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    /// Byte offset in the path string where parsing failed
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl From<PathError> for ShardDenError {
+    fn from(err: PathError) -> Self {
+        ShardDenError::invalid_input(format!("JSONPath error: {}", err))
+    }
+}
+
+/// Largest integer accepted in indices and slices (I-JSON safe range)
+const MAX_SAFE_INT: i64 = (1 << 53) - 1;
+
+/// JSONPath dialect accepted by the parser
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathMode {
+    /// Accepts RFC 9535 plus common extensions (e.g. `-` in dot member names,
+    /// non-singular queries in comparisons)
+    #[default]
+    Lenient,
+    /// Rejects anything that is not a well-formed, well-typed RFC 9535 query
+    Rfc9535,
+}
+
+/// A single step of a normalized path
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathElement {
+    Key(String),
+    Index(usize),
+}
+
+/// The concrete location of a node, e.g. `$['users'][3]`
+///
+/// `Display` renders the RFC 9535 normalized path form.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NormalizedPath(Vec<PathElement>);
+
+impl NormalizedPath {
+    /// Path elements from the root down
+    pub fn elements(&self) -> &[PathElement] {
+        &self.0
+    }
+
+    /// Render as an RFC 6901 JSON Pointer, e.g. `/users/3`
+    pub fn to_json_pointer(&self) -> String {
+        let mut pointer = String::new();
+        for element in &self.0 {
+            pointer.push('/');
+            match element {
+                PathElement::Index(i) => pointer.push_str(&i.to_string()),
+                PathElement::Key(key) => {
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"))
+                }
+            }
+        }
+        pointer
+    }
+}
+
+impl From<Vec<PathElement>> for NormalizedPath {
+    fn from(elements: Vec<PathElement>) -> Self {
+        Self(elements)
+    }
+}
+
+impl fmt::Display for NormalizedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("$")?;
+        for element in &self.0 {
+            match element {
+                PathElement::Index(i) => write!(f, "[{}]", i)?,
+                PathElement::Key(key) => {
+                    f.write_str("['")?;
+                    for c in key.chars() {
+                        match c {
+                            '\u{8}' => f.write_str("\\b")?,
+                            '\u{c}' => f.write_str("\\f")?,
+                            '\n' => f.write_str("\\n")?,
+                            '\r' => f.write_str("\\r")?,
+                            '\t' => f.write_str("\\t")?,
+                            '\'' => f.write_str("\\'")?,
+                            '\\' => f.write_str("\\\\")?,
+                            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                            c => write!(f, "{}", c)?,
+                        }
+                    }
+                    f.write_str("']")?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Path parser for JSONPath-like syntax
-#[derive(Debug, Default)]
-pub struct PathParser;
+#[derive(Debug, Clone, Default)]
+pub struct PathParser {
+    mode: PathMode,
+}
 
 impl PathParser {
     /// Create a new parser
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Create a parser for the given dialect
+    pub fn with_mode(mode: PathMode) -> Self {
+        Self { mode }
+    }
+
+    /// Dialect accepted by this parser
+    pub fn mode(&self) -> PathMode {
+        self.mode
     }
 
     /// Parse a path string into components
-    #[doc(hidden)]
-    #[deprecated(since = "0.3.0", note = "Not yet implemented - use extract() instead")]
     pub fn parse(&self, path: &str) -> Result<Vec<JsonPath>> {
-        // TODO: Implement path parsing
-        let _ = path;
-        Ok(vec![])
+        Parser::new(path, self.mode)
+            .parse_query()
+            .map_err(Into::into)
+    }
+
+    /// Check that `path` parses, reporting where it does not
+    pub fn validate(&self, path: &str) -> std::result::Result<(), PathError> {
+        Parser::new(path, self.mode).parse_query().map(|_| ())
     }
 
     /// Traverse a JSON value using the parsed path
-    #[doc(hidden)]
-    #[deprecated(since = "0.3.0", note = "Not yet implemented")]
+    ///
+    /// `value` is treated as the document root, so `$` inside filters refers to it.
     pub fn traverse<'a>(&self, value: &'a Value, path: &[JsonPath]) -> Result<Vec<&'a Value>> {
-        // TODO: Implement traversal
-        let _ = path;
-        Ok(vec![value])
+        Ok(select(value, value, path))
+    }
+
+    /// Like [`traverse`](Self::traverse), but also reports where each match was found
+    pub fn locate<'a>(
+        &self, value: &'a Value, path: &[JsonPath],
+    ) -> Result<Vec<(NormalizedPath, &'a Value)>> {
+        Ok(select_with(value, (NormalizedPath::default(), value), path))
+    }
+
+    /// Ranked completions for a partially typed path, with the cursor at byte offset `cursor`
+    ///
+    /// Builds a [`PathCompleter`] for `value`; keep one around to complete
+    /// repeatedly against the same document.
+    pub fn complete(&self, value: &Value, partial: &str, cursor: usize) -> Vec<Completion> {
+        PathCompleter::new(self, value).complete(partial, cursor)
     }
 
     /// Auto-detect all possible paths in a JSON value
+    ///
+    /// Keys are merged across all (sampled) array elements; see [`PathDetector`]
+    /// for per-path type statistics.
     pub fn detect_paths(&self, value: &Value) -> Vec<String> {
-        let mut paths = Vec::new();
-        self.detect_paths_recursive(value, "", &mut paths);
-        paths
-    }
-
-    fn detect_paths_recursive(&self, value: &Value, prefix: &str, paths: &mut Vec<String>) {
-        match value {
-            Value::Object(map) => {
-                for (key, val) in map {
-                    let path = if prefix.is_empty() {
-                        format!("$.{}", key)
-                    } else {
-                        format!("{}.{}", prefix, key)
-                    };
-                    paths.push(path.clone());
-                    self.detect_paths_recursive(val, &path, paths);
+        let mut detector = PathDetector::new();
+        detector.observe(value);
+        detector.finish().into_iter().map(|s| s.path).collect()
+    }
+}
+
+/// Tracks where a node sits in the document while traversing
+trait Trail: Clone {
+    fn key(&self, key: &str) -> Self;
+    fn index(&self, index: usize) -> Self;
+}
+
+impl Trail for () {
+    fn key(&self, _: &str) -> Self {}
+    fn index(&self, _: usize) -> Self {}
+}
+
+impl Trail for NormalizedPath {
+    fn key(&self, key: &str) -> Self {
+        let mut path = self.clone();
+        path.0.push(PathElement::Key(key.to_string()));
+        path
+    }
+
+    fn index(&self, index: usize) -> Self {
+        let mut path = self.clone();
+        path.0.push(PathElement::Index(index));
+        path
+    }
+}
+
+type Node<'a, T> = (T, &'a Value);
+
+/// Apply `path` starting at `start`, with `root` used for `$` inside filters
+pub(crate) fn select<'a>(root: &'a Value, start: &'a Value, path: &[JsonPath]) -> Vec<&'a Value> {
+    select_with(root, ((), start), path)
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect()
+}
+
+fn select_with<'a, T: Trail>(
+    root: &'a Value, start: Node<'a, T>, path: &[JsonPath],
+) -> Vec<Node<'a, T>> {
+    let mut nodes = vec![start];
+    for segment in path {
+        let mut next = Vec::new();
+        for node in &nodes {
+            apply_segment(root, node, segment, &mut next);
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+fn apply_segment<'a, T: Trail>(
+    root: &'a Value, node: &Node<'a, T>, segment: &JsonPath, out: &mut Vec<Node<'a, T>>,
+) {
+    let (trail, value) = node;
+    match segment {
+        JsonPath::Key(key) => {
+            if let Some(child) = value.as_object().and_then(|obj| obj.get(key)) {
+                out.push((trail.key(key), child));
+            }
+        }
+        JsonPath::Index(index) => {
+            if let Value::Array(arr) = value {
+                if let Some(i) = normalize_index(*index, arr.len()) {
+                    out.push((trail.index(i), &arr[i]));
+                }
+            }
+        }
+        JsonPath::Wildcard => push_children(node, out),
+        JsonPath::Recursive(key) => {
+            let mut all = Vec::new();
+            push_descendants(node, &mut all);
+            for (desc_trail, desc) in &all {
+                if let Some(child) = desc.as_object().and_then(|obj| obj.get(key)) {
+                    out.push((desc_trail.key(key), child));
                 }
             }
-            Value::Array(arr) => {
-                if arr.is_empty() {
-                    return;
+        }
+        JsonPath::Descendant(inner) => {
+            let mut all = Vec::new();
+            push_descendants(node, &mut all);
+            for desc in &all {
+                apply_segment(root, desc, inner, out);
+            }
+        }
+        JsonPath::Slice { start, end, step } => {
+            if let Value::Array(arr) = value {
+                for i in slice_indices(arr.len(), *start, *end, *step) {
+                    out.push((trail.index(i), &arr[i]));
                 }
-                // For arrays, show only [*] wildcard instead of indices
-                let path = format!("{}[*]", prefix);
-                paths.push(path);
+            }
+        }
+        JsonPath::Union(selectors) => {
+            for selector in selectors {
+                apply_segment(root, node, selector, out);
+            }
+        }
+        JsonPath::Filter(expr) => {
+            let mut children = Vec::new();
+            push_children(node, &mut children);
+            out.extend(
+                children
+                    .into_iter()
+                    .filter(|(_, child)| expr.test(root, child)),
+            );
+        }
+    }
+}
 
-                // Only recurse into the first element to avoid duplicates
-                if let Some(first) = arr.first() {
-                    self.detect_paths_recursive(first, &format!("{}[*]", prefix), paths);
+fn push_children<'a, T: Trail>(node: &Node<'a, T>, out: &mut Vec<Node<'a, T>>) {
+    let (trail, value) = node;
+    match value {
+        Value::Array(arr) => out.extend(arr.iter().enumerate().map(|(i, v)| (trail.index(i), v))),
+        Value::Object(obj) => out.extend(obj.iter().map(|(k, v)| (trail.key(k), v))),
+        _ => {}
+    }
+}
+
+/// Collect `node` and all of its descendants in document order
+fn push_descendants<'a, T: Trail>(node: &Node<'a, T>, out: &mut Vec<Node<'a, T>>) {
+    out.push(node.clone());
+    let mut children = Vec::new();
+    push_children(node, &mut children);
+    for child in &children {
+        push_descendants(child, out);
+    }
+}
+
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let len = len as i64;
+    let i = if index < 0 { len + index } else { index };
+    (0..len).contains(&i).then_some(i as usize)
+}
+
+/// Indices selected by a slice, following RFC 9535 section 2.3.4.2
+fn slice_indices(
+    len: usize, start: Option<i64>, end: Option<i64>, step: Option<i64>,
+) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = start.map_or(len - 1, normalize).clamp(-1, len - 1);
+        let lower = end.map_or(-1, normalize).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+    indices
+}
+
+/// Recursive descent parser for path strings
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    mode: PathMode,
+}
+
+type ParseResult<T> = std::result::Result<T, PathError>;
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, mode: PathMode) -> Self {
+        Self { src, pos: 0, mode }
+    }
+
+    fn strict(&self) -> bool {
+        self.mode == PathMode::Rfc9535
+    }
+
+    fn parse_query(mut self) -> ParseResult<Vec<JsonPath>> {
+        if !self.eat('$') {
+            return Err(self.error("expected '$' at the start of the path"));
+        }
+        let segments = self.parse_segments()?;
+        match self.peek() {
+            None => Ok(segments),
+            Some(c) => Err(self.error(format!("unexpected character '{}'", c))),
+        }
+    }
+
+    fn parse_segments(&mut self) -> ParseResult<Vec<JsonPath>> {
+        let mut segments = Vec::new();
+        loop {
+            let before = self.pos;
+            self.skip_whitespace();
+            match self.peek() {
+                Some('.') | Some('[') => segments.push(self.parse_segment()?),
+                _ => {
+                    self.pos = before;
+                    return Ok(segments);
                 }
             }
-            _ => {}
+        }
+    }
+
+    fn parse_segment(&mut self) -> ParseResult<JsonPath> {
+        if self.eat('[') {
+            return self.parse_bracket();
+        }
+        self.expect('.')?;
+        if self.eat('.') {
+            return self.parse_descendant();
+        }
+        if self.eat('*') {
+            return Ok(JsonPath::Wildcard);
+        }
+        match self.parse_member_name() {
+            Some(name) => Ok(JsonPath::Key(name)),
+            None => Err(self.error("expected member name or '*' after '.'")),
+        }
+    }
+
+    fn parse_descendant(&mut self) -> ParseResult<JsonPath> {
+        if self.eat('*') {
+            return Ok(JsonPath::Descendant(Box::new(JsonPath::Wildcard)));
+        }
+        if self.eat('[') {
+            return Ok(match self.parse_bracket()? {
+                JsonPath::Key(name) => JsonPath::Recursive(name),
+                selector => JsonPath::Descendant(Box::new(selector)),
+            });
+        }
+        match self.parse_member_name() {
+            Some(name) => Ok(JsonPath::Recursive(name)),
+            None => Err(self.error("expected member name, '*' or '[' after '..'")),
+        }
+    }
+
+    /// Parse the selectors of a bracketed segment; the `[` is already consumed
+    fn parse_bracket(&mut self) -> ParseResult<JsonPath> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.parse_selector()?);
+            self.skip_whitespace();
+            if self.eat(',') {
+                continue;
+            }
+            if self.eat(']') {
+                break;
+            }
+            return Err(self.error("expected ',' or ']'"));
+        }
+        if selectors.len() == 1 {
+            Ok(selectors.remove(0))
+        } else {
+            Ok(JsonPath::Union(selectors))
+        }
+    }
+
+    fn parse_selector(&mut self) -> ParseResult<JsonPath> {
+        match self.peek() {
+            Some('\'') | Some('"') => Ok(JsonPath::Key(self.parse_string()?)),
+            Some('*') => {
+                self.bump();
+                Ok(JsonPath::Wildcard)
+            }
+            Some('?') => {
+                self.bump();
+                self.skip_whitespace();
+                Ok(JsonPath::Filter(self.parse_or()?))
+            }
+            Some(c) if c == '-' || c == ':' || c.is_ascii_digit() => self.parse_index_or_slice(),
+            Some(c) => Err(self.error(format!("unexpected character '{}' in selector", c))),
+            None => Err(self.error("unexpected end of path, expected a selector")),
+        }
+    }
+
+    fn parse_index_or_slice(&mut self) -> ParseResult<JsonPath> {
+        let start = self.parse_optional_int()?;
+        self.skip_whitespace();
+        if !self.eat(':') {
+            return match start {
+                Some(index) => Ok(JsonPath::Index(index)),
+                None => Err(self.error("expected an integer")),
+            };
+        }
+        self.skip_whitespace();
+        let end = self.parse_optional_int()?;
+        self.skip_whitespace();
+        let step = if self.eat(':') {
+            self.skip_whitespace();
+            self.parse_optional_int()?
+        } else {
+            None
+        };
+        Ok(JsonPath::Slice { start, end, step })
+    }
+
+    fn parse_optional_int(&mut self) -> ParseResult<Option<i64>> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        let text = &self.src[start..self.pos];
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let digits = text.trim_start_matches('-');
+        let malformed = digits.is_empty()
+            || (self.strict() && (text == "-0" || (digits.len() > 1 && digits.starts_with('0'))));
+        match text.parse::<i64>() {
+            Ok(n) if !malformed && (-MAX_SAFE_INT..=MAX_SAFE_INT).contains(&n) => Ok(Some(n)),
+            _ => Err(PathError {
+                position: start,
+                message: format!("invalid integer '{}'", text),
+            }),
+        }
+    }
+
+    fn parse_member_name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let allowed = if self.strict() {
+                // RFC 9535 member-name-shorthand: name-first followed by name-char
+                c.is_ascii_alphabetic()
+                    || c == '_'
+                    || !c.is_ascii()
+                    || (self.pos > start && c.is_ascii_digit())
+            } else {
+                c.is_ascii_alphanumeric() || c == '_' || c == '-' || !c.is_ascii()
+            };
+            if !allowed {
+                break;
+            }
+            self.bump();
+        }
+        (self.pos > start).then(|| self.src[start..self.pos].to_string())
+    }
+
+    fn parse_string(&mut self) -> ParseResult<String> {
+        let quote = self.bump().unwrap_or('\'');
+        let mut out = String::new();
+        loop {
+            let c = self
+                .bump()
+                .ok_or_else(|| self.error("unterminated string literal"))?;
+            match c {
+                c if c == quote => return Ok(out),
+                '\\' => out.push(self.parse_escape(quote)?),
+                c if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string literal"));
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, quote: char) -> ParseResult<char> {
+        let c = self
+            .bump()
+            .ok_or_else(|| self.error("unterminated escape sequence"))?;
+        if self.strict() && matches!(c, '\'' | '"') && c != quote {
+            return Err(self.error(format!("invalid escape sequence '\\{}'", c)));
+        }
+        Ok(match c {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '/' => '/',
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            'u' => return self.parse_unicode_escape(),
+            other => return Err(self.error(format!("invalid escape sequence '\\{}'", other))),
+        })
+    }
+
+    fn parse_unicode_escape(&mut self) -> ParseResult<char> {
+        let high = self.parse_hex4()?;
+        if (0xDC00..0xE000).contains(&high) {
+            return Err(self.error("unpaired low surrogate in string literal"));
+        }
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+        if !(self.eat('\\') && self.eat('u')) {
+            return Err(self.error("unpaired high surrogate in string literal"));
+        }
+        let low = self.parse_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("invalid low surrogate in string literal"));
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> ParseResult<u32> {
+        let digits = self.src.get(self.pos..self.pos + 4).unwrap_or("");
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.error("expected 4 hex digits in unicode escape"));
+        }
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap_or_default())
+    }
+
+    fn parse_or(&mut self) -> ParseResult<FilterExpr> {
+        let mut expr = self.parse_and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("||") {
+                return Ok(expr);
+            }
+            self.skip_whitespace();
+            let rhs = self.parse_and()?;
+            expr = FilterExpr::Or(Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn parse_and(&mut self) -> ParseResult<FilterExpr> {
+        let mut expr = self.parse_basic()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("&&") {
+                return Ok(expr);
+            }
+            self.skip_whitespace();
+            let rhs = self.parse_basic()?;
+            expr = FilterExpr::And(Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn parse_basic(&mut self) -> ParseResult<FilterExpr> {
+        if self.peek() == Some('!') && !self.src[self.pos..].starts_with("!=") {
+            self.bump();
+            self.skip_whitespace();
+            if self.strict() && self.peek() != Some('(') {
+                // RFC 9535 only allows negating a parenthesized expression or a test
+                let start = self.pos;
+                let operand = self.parse_operand()?;
+                return Ok(FilterExpr::Not(Box::new(self.make_test(operand, start)?)));
+            }
+            return Ok(FilterExpr::Not(Box::new(self.parse_basic()?)));
+        }
+        if self.eat('(') {
+            self.skip_whitespace();
+            let expr = self.parse_or()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            return Ok(expr);
+        }
+
+        let start = self.pos;
+        let lhs = self.parse_operand()?;
+        let before_op = self.pos;
+        self.skip_whitespace();
+        if let Some(op) = self.parse_compare_op() {
+            self.check_comparable(&lhs, start)?;
+            self.skip_whitespace();
+            let rhs_start = self.pos;
+            let rhs = self.parse_operand()?;
+            self.check_comparable(&rhs, rhs_start)?;
+            return Ok(FilterExpr::Compare(lhs, op, rhs));
+        }
+        self.pos = before_op;
+        self.make_test(lhs, start)
+    }
+
+    /// Turn an operand that is not compared into a test expression
+    fn make_test(&self, operand: Operand, start: usize) -> ParseResult<FilterExpr> {
+        match operand {
+            Operand::Query(query) => Ok(FilterExpr::Exists(query)),
+            Operand::Function(call) if call.function.result_type() != FunctionType::Value => {
+                Ok(FilterExpr::Function(call))
+            }
+            Operand::Function(call) => Err(PathError {
+                position: start,
+                message: format!("result of {}() must be compared", call.function.name()),
+            }),
+            Operand::Literal(_) => Err(PathError {
+                position: start,
+                message: "literal must be part of a comparison".to_string(),
+            }),
+        }
+    }
+
+    fn check_comparable(&self, operand: &Operand, start: usize) -> ParseResult<()> {
+        let message = match operand {
+            Operand::Query(query) if self.strict() && !query.is_singular() => {
+                "only singular queries can be compared".to_string()
+            }
+            Operand::Function(call) if call.function.result_type() != FunctionType::Value => {
+                format!("result of {}() cannot be compared", call.function.name())
+            }
+            _ => return Ok(()),
+        };
+        Err(PathError {
+            position: start,
+            message,
+        })
+    }
+
+    fn parse_compare_op(&mut self) -> Option<CompareOp> {
+        const OPS: [(&str, CompareOp); 6] = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+        OPS.iter()
+            .find(|(text, _)| self.eat_str(text))
+            .map(|(_, op)| *op)
+    }
+
+    fn parse_operand(&mut self) -> ParseResult<Operand> {
+        match self.peek() {
+            Some('@') | Some('$') => {
+                let root = if self.bump() == Some('$') {
+                    QueryRoot::Root
+                } else {
+                    QueryRoot::Current
+                };
+                let segments = self.parse_segments()?;
+                Ok(Operand::Query(Query { root, segments }))
+            }
+            Some('\'') | Some('"') => Ok(Operand::Literal(Value::String(self.parse_string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) if c.is_ascii_lowercase() => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                {
+                    self.bump();
+                }
+                let name = &self.src[start..self.pos];
+                if self.peek() == Some('(') {
+                    return self.parse_function_call(name, start);
+                }
+                match name {
+                    "true" => Ok(Operand::Literal(Value::Bool(true))),
+                    "false" => Ok(Operand::Literal(Value::Bool(false))),
+                    "null" => Ok(Operand::Literal(Value::Null)),
+                    _ => Err(PathError {
+                        position: start,
+                        message: format!("unexpected identifier '{}'", name),
+                    }),
+                }
+            }
+            _ => Err(self.error("expected a query, literal or '(' in filter expression")),
+        }
+    }
+
+    fn parse_function_call(&mut self, name: &str, start: usize) -> ParseResult<Operand> {
+        let function = Function::from_name(name).ok_or_else(|| PathError {
+            position: start,
+            message: format!("unknown function '{}'", name),
+        })?;
+        self.expect('(')?;
+        self.skip_whitespace();
+
+        let params = function.param_types();
+        let mut args = Vec::new();
+        if !self.eat(')') {
+            loop {
+                let arg_start = self.pos;
+                let arg = self.parse_operand()?;
+                self.skip_whitespace();
+                if !matches!(self.peek(), Some(',') | Some(')')) {
+                    return Err(PathError {
+                        position: arg_start,
+                        message: "function arguments must be a literal, query or function call"
+                            .to_string(),
+                    });
+                }
+                if let Some(param) = params.get(args.len()) {
+                    self.check_argument(function, *param, &arg, arg_start)?;
+                }
+                args.push(arg);
+                if self.eat(')') {
+                    break;
+                }
+                self.expect(',')?;
+                self.skip_whitespace();
+            }
+        }
+
+        if args.len() != params.len() {
+            return Err(PathError {
+                position: start,
+                message: format!(
+                    "{}() takes {} argument(s) but {} were given",
+                    function.name(),
+                    params.len(),
+                    args.len()
+                ),
+            });
+        }
+        Ok(Operand::Function(FunctionCall { function, args }))
+    }
+
+    fn check_argument(
+        &self, function: Function, param: FunctionType, arg: &Operand, start: usize,
+    ) -> ParseResult<()> {
+        let valid = match (param, arg) {
+            (FunctionType::Value, Operand::Literal(_)) => true,
+            (FunctionType::Value, Operand::Query(query)) => !self.strict() || query.is_singular(),
+            (FunctionType::Nodes, Operand::Query(_)) => true,
+            (param, Operand::Function(call)) => call.function.result_type() == param,
+            _ => false,
+        };
+        if valid {
+            return Ok(());
+        }
+        let expected = match param {
+            FunctionType::Value => "a value",
+            FunctionType::Nodes => "a query",
+            FunctionType::Logical => "a logical expression",
+        };
+        Err(PathError {
+            position: start,
+            message: format!("{}() expects {} as argument", function.name(), expected),
+        })
+    }
+
+    fn parse_number(&mut self) -> ParseResult<Operand> {
+        let start = self.pos;
+        self.eat('-');
+        let digits = |p: &mut Self| {
+            while p.peek().is_some_and(|c| c.is_ascii_digit()) {
+                p.bump();
+            }
+        };
+        digits(self);
+        if self.eat('.') {
+            digits(self);
+        }
+        if self.eat('e') || self.eat('E') {
+            if !self.eat('+') {
+                self.eat('-');
+            }
+            digits(self);
+        }
+        let text = &self.src[start..self.pos];
+        serde_json::from_str::<serde_json::Number>(text)
+            .map(|n| Operand::Literal(Value::Number(n)))
+            .map_err(|_| PathError {
+                position: start,
+                message: format!("invalid number '{}'", text),
+            })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, expected: &str) -> bool {
+        if self.src[self.pos..].starts_with(expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", expected)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> PathError {
+        PathError {
+            position: self.pos,
+            message: message.into(),
         }
     }
 }
@@ -166,24 +1012,315 @@ mod tests {
     }
 
     #[test]
-    #[allow(deprecated)]
-    fn test_parse_deprecated() {
+    fn test_parse_dot_and_index() {
+        let parser = PathParser::new();
+        let path = parser.parse("$.data.items[0]").unwrap();
+        assert_eq!(
+            path,
+            vec![
+                JsonPath::Key("data".to_string()),
+                JsonPath::Key("items".to_string()),
+                JsonPath::Index(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_root_only() {
+        let parser = PathParser::new();
+        assert!(parser.parse("$").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_bracket_notation() {
+        let parser = PathParser::new();
+        let path = parser.parse(r#"$['a b']["c\"d"][-1]"#).unwrap();
+        assert_eq!(
+            path,
+            vec![
+                JsonPath::Key("a b".to_string()),
+                JsonPath::Key("c\"d".to_string()),
+                JsonPath::Index(-1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_wildcards_and_recursive() {
+        let parser = PathParser::new();
+        let path = parser.parse("$.*[*]..id..*..[0]").unwrap();
+        assert_eq!(
+            path,
+            vec![
+                JsonPath::Wildcard,
+                JsonPath::Wildcard,
+                JsonPath::Recursive("id".to_string()),
+                JsonPath::Descendant(Box::new(JsonPath::Wildcard)),
+                JsonPath::Descendant(Box::new(JsonPath::Index(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_slice_and_union() {
+        let parser = PathParser::new();
+        let path = parser.parse("$[1:][::-1]['a', 2]").unwrap();
+        assert_eq!(
+            path,
+            vec![
+                JsonPath::Slice {
+                    start: Some(1),
+                    end: None,
+                    step: None
+                },
+                JsonPath::Slice {
+                    start: None,
+                    end: None,
+                    step: Some(-1)
+                },
+                JsonPath::Union(vec![JsonPath::Key("a".to_string()), JsonPath::Index(2)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let parser = PathParser::new();
+        let path = parser.parse("$.items[?(@.price > 15 && !@.sold)]").unwrap();
+        assert!(matches!(path[1], JsonPath::Filter(FilterExpr::And(_, _))));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parser = PathParser::new();
+        assert!(parser.parse("").is_err());
+        assert!(parser.parse("name").is_err());
+        assert!(parser.parse("$.").is_err());
+        assert!(parser.parse("$[").is_err());
+        assert!(parser.parse("$['unterminated]").is_err());
+        assert!(parser.parse("$[?(@.a == )]").is_err());
+        assert!(parser.parse("$[?(1)]").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let err = Parser::new("$.a[?@.b == ]", PathMode::Lenient)
+            .parse_query()
+            .unwrap_err();
+        assert_eq!(err.position, 12);
+        assert!(err.to_string().contains("position 12"));
+    }
+
+    #[test]
+    fn test_traverse_keys_and_indices() {
         let parser = PathParser::new();
-        let result = parser.parse("data.items[0]");
-        // Deprecated method returns empty vec
-        assert!(result.is_ok());
-        assert!(result.unwrap().is_empty());
+        let value = json!({"data": {"items": [10, 20, 30]}});
+        let path = parser.parse("$.data.items[-1]").unwrap();
+        let result = parser.traverse(&value, &path).unwrap();
+        assert_eq!(result, vec![&json!(30)]);
     }
 
     #[test]
-    #[allow(deprecated)]
-    fn test_traverse_deprecated() {
+    fn test_traverse_root() {
         let parser = PathParser::new();
         let value = json!({"key": "value"});
-        let result = parser.traverse(&value, &[]);
-        // Deprecated method returns the value itself
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 1);
+        let result = parser.traverse(&value, &[]).unwrap();
+        assert_eq!(result, vec![&value]);
+    }
+
+    #[test]
+    fn test_traverse_slices() {
+        let parser = PathParser::new();
+        let value = json!([0, 1, 2, 3, 4, 5]);
+        let cases = [
+            ("$[1:3]", json!([1, 2])),
+            ("$[:2]", json!([0, 1])),
+            ("$[-2:]", json!([4, 5])),
+            ("$[::2]", json!([0, 2, 4])),
+            ("$[::-1]", json!([5, 4, 3, 2, 1, 0])),
+            ("$[4:1:-2]", json!([4, 2])),
+            ("$[::0]", json!([])),
+            ("$[10:20]", json!([])),
+        ];
+        for (path, expected) in cases {
+            let segments = parser.parse(path).unwrap();
+            let result: Vec<Value> = parser
+                .traverse(&value, &segments)
+                .unwrap()
+                .into_iter()
+                .cloned()
+                .collect();
+            assert_eq!(Value::Array(result), expected, "path {}", path);
+        }
+    }
+
+    #[test]
+    fn test_traverse_recursive_descent() {
+        let parser = PathParser::new();
+        let value = json!({"a": {"id": 1, "b": [{"id": 2}]}, "id": 0});
+        let path = parser.parse("$..id").unwrap();
+        let result = parser.traverse(&value, &path).unwrap();
+        assert_eq!(result.len(), 3);
+
+        let path = parser.parse("$..[0]").unwrap();
+        let result = parser.traverse(&value, &path).unwrap();
+        assert_eq!(result, vec![&json!({"id": 2})]);
+    }
+
+    #[test]
+    fn test_traverse_filter() {
+        let parser = PathParser::new();
+        let value = json!({
+            "limit": 15,
+            "items": [
+                {"name": "a", "price": 10},
+                {"name": "b", "price": 20, "sale": true},
+                {"name": "c", "price": 30}
+            ]
+        });
+        let cases = [
+            ("$.items[?(@.price > 15)].name", json!(["b", "c"])),
+            ("$.items[?@.price > $.limit].name", json!(["b", "c"])),
+            ("$.items[?@.sale].name", json!(["b"])),
+            ("$.items[?!@.sale && @.price < 25].name", json!(["a"])),
+            (
+                "$.items[?@.name == 'a' || @.name == \"c\"].price",
+                json!([10, 30]),
+            ),
+        ];
+        for (path, expected) in cases {
+            let segments = parser.parse(path).unwrap();
+            let result: Vec<Value> = parser
+                .traverse(&value, &segments)
+                .unwrap()
+                .into_iter()
+                .cloned()
+                .collect();
+            assert_eq!(Value::Array(result), expected, "path {}", path);
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_extensions() {
+        let lenient = PathParser::new();
+        let strict = PathParser::with_mode(PathMode::Rfc9535);
+        for path in [
+            "$.a-b",
+            "$[01]",
+            "$[-0]",
+            "$[?@.* == 1]",
+            "$[?!@.a == 1]",
+            r#"$['\"']"#,
+        ] {
+            assert!(
+                lenient.parse(path).is_ok(),
+                "lenient should accept {}",
+                path
+            );
+            assert!(strict.parse(path).is_err(), "strict should reject {}", path);
+        }
+    }
+
+    #[test]
+    fn test_parse_functions() {
+        let parser = PathParser::with_mode(PathMode::Rfc9535);
+        assert!(parser.parse("$[?length(@.name) > 3]").is_ok());
+        assert!(parser.parse("$[?count(@.*) == 2]").is_ok());
+        assert!(parser.parse("$[?match(@.date, '1974-05-..')]").is_ok());
+        assert!(parser.parse("$[?search(@.name, 'ob')]").is_ok());
+        assert!(parser.parse("$[?value(@..id) == 1]").is_ok());
+        assert!(parser.parse("$[?length(@.name)]").is_err());
+        assert!(parser.parse("$[?match(@.a, 'x') == true]").is_err());
+        assert!(parser.parse("$[?count(1) == 1]").is_err());
+        assert!(parser.parse("$[?nope(@)]").is_err());
+    }
+
+    #[test]
+    fn test_traverse_functions() {
+        let parser = PathParser::new();
+        let value = json!([
+            {"name": "Alice", "tags": ["a", "b"]},
+            {"name": "Bob", "tags": []},
+            {"name": "Carol\nAnn", "tags": ["c"]}
+        ]);
+        let cases = [
+            (
+                "$[?length(@.tags) > 0].name",
+                json!(["Alice", "Carol\nAnn"]),
+            ),
+            ("$[?count(@.tags[*]) == 2].name", json!(["Alice"])),
+            ("$[?match(@.name, 'B.b')].name", json!(["Bob"])),
+            ("$[?search(@.name, 'o')].name", json!(["Bob", "Carol\nAnn"])),
+            ("$[?match(@.name, 'Carol.Ann')].name", json!([])),
+            ("$[?value(@.tags[0]) == 'c'].name", json!(["Carol\nAnn"])),
+        ];
+        for (path, expected) in cases {
+            let segments = parser.parse(path).unwrap();
+            let result: Vec<Value> = parser
+                .traverse(&value, &segments)
+                .unwrap()
+                .into_iter()
+                .cloned()
+                .collect();
+            assert_eq!(Value::Array(result), expected, "path {}", path);
+        }
+    }
+
+    #[test]
+    fn test_locate_normalized_paths() {
+        let parser = PathParser::new();
+        let value = json!({"users": [{"email": "a@x"}, {"email": "b@x"}], "it's": 1});
+        let path = parser.parse("$.users[*].email").unwrap();
+        let located = parser.locate(&value, &path).unwrap();
+        let paths: Vec<String> = located.iter().map(|(p, _)| p.to_string()).collect();
+        assert_eq!(
+            paths,
+            vec!["$['users'][0]['email']", "$['users'][1]['email']"]
+        );
+        assert_eq!(located[1].1, &json!("b@x"));
+
+        let path = parser.parse("$[\"it's\"]").unwrap();
+        let located = parser.locate(&value, &path).unwrap();
+        assert_eq!(located[0].0.to_string(), "$['it\\'s']");
+        assert_eq!(
+            located[0].0.elements(),
+            &[PathElement::Key("it's".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        let path = NormalizedPath::from(vec![
+            PathElement::Key("a/b".to_string()),
+            PathElement::Index(3),
+            PathElement::Key("m~n".to_string()),
+            PathElement::Key(String::new()),
+        ]);
+        assert_eq!(path.to_json_pointer(), "/a~1b/3/m~0n/");
+        assert_eq!(NormalizedPath::default().to_json_pointer(), "");
+    }
+
+    #[test]
+    fn test_traverse_missing() {
+        let parser = PathParser::new();
+        let value = json!({"a": [1]});
+        let path = parser.parse("$.b[5].c").unwrap();
+        assert!(parser.traverse(&value, &path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_detect_paths_quotes_special_keys() {
+        let parser = PathParser::new();
+        let value = json!({"@id": 1, "a b": {"it's": 2}, "ok_key-1": 3});
+        let paths = parser.detect_paths(&value);
+        assert_eq!(
+            paths,
+            vec!["$['@id']", "$['a b']", "$['a b']['it\\'s']", "$.ok_key-1"]
+        );
+        for path in &paths {
+            let segments = parser.parse(path).unwrap();
+            assert_eq!(parser.traverse(&value, &segments).unwrap().len(), 1);
+        }
     }
 
     #[test]
@@ -0,0 +1,522 @@
+//! Field-level redaction
+//!
+//! A `Redactor` selects sensitive values by JSONPath and by key-name
+//! pattern (`password`, `*secret*`, case-insensitive) and masks, partially
+//! masks, hashes or removes them. A matched member is redacted as a whole,
+//! including everything nested under it.
+
+use crate::format::{CsvOptions, Formatter, OutputFormat};
+use crate::input::InputFormat;
+use crate::lenient::parse_lenient;
+use crate::mutate::{node_mut, remove_member};
+use crate::path::{JsonPath, PathElement, PathParser};
+use crate::JsonExtractorCore;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use shard_den_core::{HistoryEntry, Result, ShardDenError};
+
+/// Key patterns used when none are given
+pub const DEFAULT_KEY_PATTERNS: [&str; 8] = [
+    "*password*",
+    "*passwd*",
+    "*secret*",
+    "*token*",
+    "*api_key*",
+    "*apikey*",
+    "authorization",
+    "cookie",
+];
+
+/// Replacement for fully masked values; fixed length so it leaks nothing
+const MASK: &str = "********";
+
+/// Stored in place of history data that cannot be parsed and so cannot be redacted
+pub const UNREADABLE: &str = "[redacted: unparseable data]";
+
+/// Characters kept by [`RedactStrategy::Partial`]
+const VISIBLE_SUFFIX: usize = 4;
+
+/// How a sensitive value is redacted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedactStrategy {
+    /// Replace with `********`
+    #[default]
+    Mask,
+    /// Keep the last 4 characters of strings and numbers, e.g. `****1234`
+    Partial,
+    /// Replace with `sha256:<hex>`, so equal values stay comparable
+    Hash,
+    /// Remove the member or element
+    Remove,
+}
+
+impl RedactStrategy {
+    /// Parse a strategy name: `mask`, `partial`, `hash` or `remove`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "mask" | "full" => Some(RedactStrategy::Mask),
+            "partial" | "last4" => Some(RedactStrategy::Partial),
+            "hash" | "sha256" => Some(RedactStrategy::Hash),
+            "remove" | "delete" => Some(RedactStrategy::Remove),
+            _ => None,
+        }
+    }
+}
+
+/// Masks sensitive values in documents
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    parser: PathParser,
+    paths: Vec<Vec<JsonPath>>,
+    key_patterns: Vec<String>,
+    strategy: RedactStrategy,
+}
+
+impl Redactor {
+    /// Create a redactor with no rules
+    pub fn new(strategy: RedactStrategy) -> Self {
+        Self {
+            strategy,
+            ..Self::default()
+        }
+    }
+
+    /// Create a redactor for the [`DEFAULT_KEY_PATTERNS`]
+    pub fn with_default_keys(strategy: RedactStrategy) -> Self {
+        DEFAULT_KEY_PATTERNS
+            .iter()
+            .fold(Self::new(strategy), |redactor, pattern| {
+                redactor.with_key_pattern(pattern)
+            })
+    }
+
+    /// Redact the values matched by a JSONPath
+    pub fn with_path(mut self, path: &str) -> Result<Self> {
+        self.paths.push(self.parser.parse(path)?);
+        Ok(self)
+    }
+
+    /// Redact the values of members whose name matches `pattern`, where `*`
+    /// matches any run of characters
+    pub fn with_key_pattern(mut self, pattern: &str) -> Self {
+        self.key_patterns.push(pattern.to_lowercase());
+        self
+    }
+
+    pub fn strategy(&self) -> RedactStrategy {
+        self.strategy
+    }
+
+    /// Redact `document` in place, returning the number of values redacted
+    pub fn redact(&self, document: &mut Value) -> usize {
+        let mut targets = Vec::new();
+        for path in &self.paths {
+            if let Ok(located) = self.parser.locate(document, path) {
+                targets.extend(
+                    located
+                        .into_iter()
+                        .map(|(path, _)| path.elements().to_vec()),
+                );
+            }
+        }
+        if !self.key_patterns.is_empty() {
+            self.collect_keys(document, &mut Vec::new(), &mut targets);
+        }
+
+        // Whole subtrees are redacted, so drop targets inside another one,
+        // then work from the end so removals never shift a pending index
+        targets.sort();
+        targets.dedup();
+        let mut outermost: Vec<Vec<PathElement>> = Vec::new();
+        for target in targets {
+            if !outermost.iter().any(|o| target.starts_with(o)) {
+                outermost.push(target);
+            }
+        }
+
+        let mut redacted = 0;
+        for target in outermost.iter().rev() {
+            redacted += usize::from(self.apply(document, target));
+        }
+        redacted
+    }
+
+    fn collect_keys(
+        &self, value: &Value, trail: &mut Vec<PathElement>, targets: &mut Vec<Vec<PathElement>>,
+    ) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    trail.push(PathElement::Key(key.clone()));
+                    if self.matches_key(key) {
+                        targets.push(trail.clone());
+                    } else {
+                        self.collect_keys(child, trail, targets);
+                    }
+                    trail.pop();
+                }
+            }
+            Value::Array(arr) => {
+                for (i, child) in arr.iter().enumerate() {
+                    trail.push(PathElement::Index(i));
+                    self.collect_keys(child, trail, targets);
+                    trail.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        let key = key.to_lowercase();
+        self.key_patterns.iter().any(|p| glob_match(p, &key))
+    }
+
+    fn apply(&self, document: &mut Value, target: &[PathElement]) -> bool {
+        match (self.strategy, target.split_last()) {
+            (RedactStrategy::Remove, Some((last, parent))) => {
+                match (node_mut(document, parent), last) {
+                    (Some(Value::Object(map)), PathElement::Key(key)) => {
+                        remove_member(map, key).is_some()
+                    }
+                    (Some(Value::Array(arr)), PathElement::Index(i)) if *i < arr.len() => {
+                        arr.remove(*i);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            (strategy, _) => match node_mut(document, target) {
+                Some(node) => {
+                    *node = mask(node, strategy);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+impl shard_den_core::Redact for Redactor {
+    /// Redact `data` if it can be read as JSON, JSONC/JSON5, a YAML collection
+    /// or CSV with a header row
+    ///
+    /// Changed CSV comes back as CSV and anything else changed as JSON. Other
+    /// data (text, Markdown or HTML output) cannot be checked for sensitive
+    /// keys, so it is replaced with [`UNREADABLE`] rather than stored as is.
+    fn redact(&self, data: &str) -> String {
+        if data.trim().is_empty() {
+            return data.to_string();
+        }
+        let Some((mut document, columns)) = read_history(data) else {
+            return UNREADABLE.to_string();
+        };
+        if Redactor::redact(self, &mut document) == 0 {
+            return data.to_string();
+        }
+        write_history(&document, columns, data)
+    }
+
+    /// Redact an extraction's document, then rebuild its output by running the
+    /// `paths` from its metadata again, in its `format`, on the redacted document
+    ///
+    /// A value extracted on its own (e.g. `["hunter2"]` from `$.password`) has
+    /// no key left to match, so the output is rebuilt rather than searched.
+    /// Entries without `paths` are redacted as data.
+    fn redact_entry(&self, entry: &mut HistoryEntry) {
+        let rebuilt = entry.metadata.get("paths").and_then(|paths| {
+            let (mut document, columns) = read_history(&entry.input)?;
+            if Redactor::redact(self, &mut document) == 0 {
+                // Nothing sensitive in the source, so nothing in what was extracted from it
+                return Some((entry.input.clone(), entry.output.clone()));
+            }
+            let format = entry
+                .metadata
+                .get("format")
+                .and_then(|f| OutputFormat::from_name(f))
+                .unwrap_or_default();
+            let output = serde_json::to_string(&document)
+                .ok()
+                .and_then(|json| {
+                    JsonExtractorCore::new()
+                        .extract_with_format(&json, paths, format)
+                        .ok()
+                })
+                .unwrap_or_else(|| shard_den_core::Redact::redact(self, &entry.output));
+            Some((write_history(&document, columns, &entry.input), output))
+        });
+        match rebuilt {
+            Some((input, output)) => {
+                entry.input = input;
+                entry.output = output;
+            }
+            None => {
+                entry.input = shard_den_core::Redact::redact(self, &entry.input);
+                entry.output = shard_den_core::Redact::redact(self, &entry.output);
+            }
+        }
+    }
+}
+
+/// Read history data as JSON, JSONC/JSON5, a YAML collection or CSV with a header row
+///
+/// CSV comes with its columns, so it can be written back as CSV.
+fn read_history(data: &str) -> Option<(Value, Option<Vec<String>>)> {
+    let structured = serde_json::from_str::<Value>(data)
+        .ok()
+        .or_else(|| parse_lenient(data).ok())
+        .or_else(|| {
+            InputFormat::Yaml
+                .parse(data)
+                .ok()
+                .filter(|v| v.is_object() || v.is_array())
+        });
+    if let Some(document) = structured {
+        return Some((document, None));
+    }
+    let header = data.lines().next()?;
+    if !header.contains(',') || data.trim_end().lines().count() < 2 {
+        return None;
+    }
+    let columns = csv::Reader::from_reader(data.as_bytes())
+        .headers()
+        .ok()?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    Some((InputFormat::Csv.parse(data).ok()?, Some(columns)))
+}
+
+/// Write redacted history data back as CSV when it was read as CSV, else as JSON
+fn write_history(document: &Value, columns: Option<Vec<String>>, original: &str) -> String {
+    let output = match columns {
+        Some(columns) => Formatter::new()
+            .with_columns(columns)
+            .format_csv_with(document, &CsvOptions::default())
+            .ok(),
+        None if original.contains('\n') => serde_json::to_string_pretty(document).ok(),
+        None => serde_json::to_string(document).ok(),
+    };
+    output.unwrap_or_else(|| UNREADABLE.to_string())
+}
+
+/// The redacted replacement for `value` (the document root is masked rather than removed)
+fn mask(value: &Value, strategy: RedactStrategy) -> Value {
+    let text = match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    match (strategy, text) {
+        (RedactStrategy::Partial, Some(text)) if text.chars().count() > VISIBLE_SUFFIX => {
+            let suffix: String = text
+                .chars()
+                .skip(text.chars().count() - VISIBLE_SUFFIX)
+                .collect();
+            Value::String(format!("****{}", suffix))
+        }
+        (RedactStrategy::Hash, text) => {
+            let bytes = text.unwrap_or_else(|| value.to_string());
+            let digest = Sha256::digest(bytes.as_bytes());
+            let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+            Value::String(format!("sha256:{}", hex))
+        }
+        _ => Value::String(MASK.to_string()),
+    }
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Build a redactor from comma-separated paths and key patterns
+///
+/// With neither given, the [`DEFAULT_KEY_PATTERNS`] are used.
+pub fn redactor_from_lists(paths: &str, keys: &str, strategy: RedactStrategy) -> Result<Redactor> {
+    let paths = crate::parse_paths(paths);
+    let keys: Vec<&str> = keys
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .collect();
+    if paths.is_empty() && keys.is_empty() {
+        return Ok(Redactor::with_default_keys(strategy));
+    }
+    let mut redactor = Redactor::new(strategy);
+    for path in &paths {
+        redactor = redactor
+            .with_path(path)
+            .map_err(|e| ShardDenError::invalid_input(format!("{}: {}", path, e)))?;
+    }
+    Ok(keys
+        .into_iter()
+        .fold(redactor, |redactor, key| redactor.with_key_pattern(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "user": {"name": "ann", "Password": "hunter22", "apiToken": "abcd1234wxyz"},
+            "cards": [{"number": "4111111111111111", "cvv": 123}],
+            "clientSecret": {"nested": true}
+        })
+    }
+
+    #[test]
+    fn test_key_patterns_mask_whole_subtrees() {
+        let mut doc = document();
+        let redacted = Redactor::with_default_keys(RedactStrategy::Mask).redact(&mut doc);
+        assert_eq!(redacted, 3);
+        assert_eq!(doc["user"]["Password"], "********");
+        assert_eq!(doc["user"]["apiToken"], "********");
+        assert_eq!(doc["clientSecret"], "********");
+        assert_eq!(doc["user"]["name"], "ann");
+    }
+
+    #[test]
+    fn test_paths_and_partial_mask() {
+        let mut doc = document();
+        let redactor = Redactor::new(RedactStrategy::Partial)
+            .with_path("$.cards[*].number")
+            .unwrap()
+            .with_path("$.cards[*].cvv")
+            .unwrap();
+        assert_eq!(redactor.redact(&mut doc), 2);
+        assert_eq!(
+            doc["cards"][0],
+            json!({"number": "****1111", "cvv": "********"})
+        );
+        assert!(Redactor::new(RedactStrategy::Mask).with_path("$[").is_err());
+    }
+
+    #[test]
+    fn test_hash_is_deterministic() {
+        let mut a = json!({"token": "x", "other": {"token": "x"}});
+        let redactor = Redactor::new(RedactStrategy::Hash).with_key_pattern("token");
+        redactor.redact(&mut a);
+        assert_eq!(
+            a["token"],
+            "sha256:2d711642b726b04401627ca9fbac32f5c8530fb1903cc4db02258717921a4881"
+        );
+        assert_eq!(a["token"], a["other"]["token"]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut doc = json!({"items": [{"secret": 1}, {"secret": 2, "id": 2}], "keep": 1});
+        let redactor = Redactor::new(RedactStrategy::Remove)
+            .with_key_pattern("secret")
+            .with_path("$.items[0]")
+            .unwrap();
+        assert_eq!(redactor.redact(&mut doc), 2);
+        assert_eq!(doc, json!({"items": [{"id": 2}], "keep": 1}));
+
+        // The root cannot be removed, so it is masked instead
+        let mut doc = json!({"a": 1});
+        let redactor = Redactor::new(RedactStrategy::Remove)
+            .with_path("$")
+            .unwrap();
+        assert_eq!(redactor.redact(&mut doc), 1);
+        assert_eq!(doc, json!("********"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("password", "password"));
+        assert!(!glob_match("password", "password2"));
+        assert!(glob_match("*secret*", "clientsecretkey"));
+        assert!(glob_match("api*key", "api_private_key"));
+        assert!(!glob_match("a*a", "a"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_history_redaction() {
+        use shard_den_core::Redact;
+        let redactor = Redactor::with_default_keys(RedactStrategy::Mask);
+        assert_eq!(
+            Redact::redact(&redactor, r#"{"id":1,"token":"abc"}"#),
+            r#"{"id":1,"token":"********"}"#
+        );
+        assert_eq!(Redact::redact(&redactor, "not json"), UNREADABLE);
+        assert_eq!(
+            Redact::redact(&redactor, "token,id\nabc,1\n"),
+            "token,id\n********,1\n"
+        );
+        assert_eq!(Redact::redact(&redactor, "a,b\nno secrets"), UNREADABLE);
+        assert_eq!(
+            Redact::redact(&redactor, "{id: 1, token: 'abc'} // jsonc"),
+            r#"{"id":1,"token":"********"}"#
+        );
+        assert_eq!(
+            Redact::redact(&redactor, "id: 1\ntoken: abc\n"),
+            "{\n  \"id\": 1,\n  \"token\": \"********\"\n}"
+        );
+        assert_eq!(Redact::redact(&redactor, "id: 1\n"), "id: 1\n");
+        assert_eq!(Redact::redact(&redactor, "{\n}"), "{\n}");
+    }
+
+    #[test]
+    fn test_history_entry_rebuilds_extracted_output() {
+        let redactor = Redactor::with_default_keys(RedactStrategy::Mask);
+        let input = r#"{"user": "ann", "password": "hunter2"}"#;
+        let entry = HistoryEntry::new("json-extractor", input, r#"["hunter2"]"#, false)
+            .with_metadata("paths", "$.password")
+            .with_metadata("format", "text")
+            .redact(&redactor);
+        assert!(!entry.input.contains("hunter2") && !entry.output.contains("hunter2"));
+        assert_eq!(entry.output, "********");
+
+        // Nothing sensitive in the source leaves the output as it was
+        let entry = HistoryEntry::new("json-extractor", r#"{"user": "ann"}"#, "| ann |", false)
+            .with_metadata("paths", "$.user")
+            .with_metadata("format", "markdown")
+            .redact(&redactor);
+        assert_eq!(entry.output, "| ann |");
+    }
+
+    #[test]
+    fn test_redactor_from_lists() {
+        let mut doc = document();
+        let redactor = redactor_from_lists("$.user.name", "cvv", RedactStrategy::Mask).unwrap();
+        assert_eq!(redactor.redact(&mut doc), 2);
+        assert_eq!(doc["user"]["Password"], "hunter22");
+
+        let mut doc = document();
+        let union = redactor_from_lists("$.user['name','apiToken']", "", RedactStrategy::Mask);
+        assert_eq!(union.unwrap().redact(&mut doc), 2);
+
+        let defaults = redactor_from_lists("", " ", RedactStrategy::Mask).unwrap();
+        assert_eq!(defaults.redact(&mut document()), 3);
+    }
+
+    #[cfg(feature = "preserve-source")]
+    #[test]
+    fn test_preserve_source_remove_keeps_order() {
+        let mut doc: Value =
+            serde_json::from_str(r#"{"c": 1, "secret": 2, "a": 3, "b": 4}"#).unwrap();
+        let redactor = Redactor::new(RedactStrategy::Remove).with_key_pattern("secret");
+        assert_eq!(redactor.redact(&mut doc), 1);
+        assert_eq!(doc.to_string(), r#"{"c":1,"a":3,"b":4}"#);
+    }
+}
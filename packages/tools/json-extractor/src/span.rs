@@ -0,0 +1,375 @@
+//! Source spans of JSON values
+//!
+//! [`SpanIndex`] scans JSON text once and maps the JSON Pointer of every value
+//! to where it sits in the source, so matches can be highlighted in the
+//! original text and large documents browsed one level at a time.
+
+use crate::diagnostic::Diagnostic;
+use serde::{Deserialize, Serialize};
+use shard_den_core::{Result, ShardDenError};
+use std::collections::HashMap;
+
+/// Where a value sits in the source text
+///
+/// Offsets are bytes and columns are characters, or both UTF-16 code units
+/// for an index built with [`SpanIndex::new_utf16`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    /// Offset of the first character
+    pub start: usize,
+    /// Offset just past the last character
+    pub end: usize,
+    /// 1-based line of `start`
+    pub line: usize,
+    /// 1-based column of `start`
+    pub column: usize,
+    /// 1-based line of `end`
+    pub end_line: usize,
+    /// 1-based column of `end`
+    pub end_column: usize,
+}
+
+/// JSON type of an indexed value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Boolean,
+    Null,
+}
+
+/// One value in a [`SpanIndex`]
+#[derive(Debug, Clone, Serialize)]
+pub struct SpanNode {
+    /// RFC 6901 JSON Pointer, `""` for the root
+    pub pointer: String,
+    pub kind: NodeKind,
+    pub span: Span,
+    /// Span of the member name, for object members
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_span: Option<Span>,
+    /// Number of members or elements; 0 for scalars
+    pub len: usize,
+    #[serde(skip)]
+    children: Vec<usize>,
+}
+
+/// Source spans of every value in a JSON document, by JSON Pointer
+#[derive(Debug, Clone, Default)]
+pub struct SpanIndex {
+    nodes: Vec<SpanNode>,
+    by_pointer: HashMap<String, usize>,
+}
+
+impl SpanIndex {
+    /// Index `text`, with byte offsets and character columns
+    pub fn new(text: &str) -> Result<Self> {
+        Self::build(text, false)
+    }
+
+    /// Index `text`, with offsets and columns in UTF-16 code units as JavaScript counts them
+    pub fn new_utf16(text: &str) -> Result<Self> {
+        Self::build(text, true)
+    }
+
+    fn build(text: &str, utf16: bool) -> Result<Self> {
+        if let Err(e) = serde_json::from_str::<serde::de::IgnoredAny>(text) {
+            return Err(Diagnostic::from_json_error(text, &e).into());
+        }
+        let mut scanner = Scanner {
+            text,
+            utf16,
+            pos: 0,
+            unit: 0,
+            line: 1,
+            column: 1,
+            index: SpanIndex::default(),
+        };
+        scanner.value(String::new(), None)?;
+        Ok(scanner.index)
+    }
+
+    /// The value at `pointer`
+    pub fn get(&self, pointer: &str) -> Option<&SpanNode> {
+        self.by_pointer.get(pointer).map(|&i| &self.nodes[i])
+    }
+
+    /// The members or elements of the value at `pointer`, in document order
+    pub fn children(&self, pointer: &str) -> Option<Vec<&SpanNode>> {
+        let node = self.get(pointer)?;
+        Some(node.children.iter().map(|&i| &self.nodes[i]).collect())
+    }
+
+    /// Every value, in document order
+    pub fn iter(&self) -> impl Iterator<Item = &SpanNode> {
+        self.nodes.iter()
+    }
+
+    /// Number of indexed values
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// Position in the source while scanning
+#[derive(Clone, Copy)]
+struct Mark {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Single pass over already validated JSON, recording spans
+struct Scanner<'a> {
+    text: &'a str,
+    utf16: bool,
+    /// Byte position
+    pos: usize,
+    /// UTF-16 position
+    unit: usize,
+    line: usize,
+    column: usize,
+    index: SpanIndex,
+}
+
+impl Scanner<'_> {
+    fn mark(&self) -> Mark {
+        Mark {
+            offset: if self.utf16 { self.unit } else { self.pos },
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn span(&self, start: Mark) -> Span {
+        let end = self.mark();
+        Span {
+            start: start.offset,
+            end: end.offset,
+            line: start.line,
+            column: start.column,
+            end_line: end.line,
+            end_column: end.column,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    /// Move to byte `to`, keeping line and column up to date
+    fn advance_to(&mut self, to: usize) {
+        for c in self.text[self.pos..to].chars() {
+            self.unit += c.len_utf16();
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += if self.utf16 { c.len_utf16() } else { 1 };
+            }
+        }
+        self.pos = to;
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        let skipped = rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+        self.advance_to(self.pos + skipped);
+    }
+
+    /// Consume `byte` after optional whitespace
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(ShardDenError::invalid_input(format!(
+                "Expected '{}' at byte {}",
+                byte as char, self.pos
+            )));
+        }
+        self.advance_to(self.pos + 1);
+        Ok(())
+    }
+
+    /// Consume a string literal, returning its byte range
+    fn string(&mut self) -> Result<(usize, usize)> {
+        let start = self.pos;
+        let bytes = self.text.as_bytes();
+        let mut end = start + 1;
+        while end < bytes.len() && bytes[end] != b'"' {
+            end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
+        if end >= bytes.len() {
+            return Err(ShardDenError::invalid_input("Unterminated string"));
+        }
+        self.advance_to(end + 1);
+        Ok((start, end + 1))
+    }
+
+    /// Index the value at the current position, returning its node
+    fn value(&mut self, pointer: String, key_span: Option<Span>) -> Result<usize> {
+        self.skip_whitespace();
+        let start = self.mark();
+        let node = self.index.nodes.len();
+        let kind = match self.peek() {
+            Some(b'{') => NodeKind::Object,
+            Some(b'[') => NodeKind::Array,
+            Some(b'"') => NodeKind::String,
+            Some(b't' | b'f') => NodeKind::Boolean,
+            Some(b'n') => NodeKind::Null,
+            Some(_) => NodeKind::Number,
+            None => return Err(ShardDenError::invalid_input("Unexpected end of input")),
+        };
+        self.index.nodes.push(SpanNode {
+            pointer: pointer.clone(),
+            kind,
+            span: self.span(start),
+            key_span,
+            len: 0,
+            children: Vec::new(),
+        });
+        // A repeated member name resolves to its last occurrence, as when parsing
+        self.index.by_pointer.insert(pointer.clone(), node);
+
+        match kind {
+            NodeKind::Object => {
+                self.advance_to(self.pos + 1);
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.advance_to(self.pos + 1);
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key_start = self.mark();
+                        let (from, to) = self.string()?;
+                        let key: String = serde_json::from_str(&self.text[from..to])?;
+                        let key_span = self.span(key_start);
+                        self.expect(b':')?;
+                        let child = self.value(
+                            format!("{}/{}", pointer, escape_token(&key)),
+                            Some(key_span),
+                        )?;
+                        self.index.nodes[node].children.push(child);
+                        if !self.separator(b'}')? {
+                            break;
+                        }
+                    }
+                }
+            }
+            NodeKind::Array => {
+                self.advance_to(self.pos + 1);
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.advance_to(self.pos + 1);
+                } else {
+                    for i in 0.. {
+                        let child = self.value(format!("{}/{}", pointer, i), None)?;
+                        self.index.nodes[node].children.push(child);
+                        if !self.separator(b']')? {
+                            break;
+                        }
+                    }
+                }
+            }
+            NodeKind::String => {
+                self.string()?;
+            }
+            _ => {
+                let rest = &self.text[self.pos..];
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')))
+                    .unwrap_or(rest.len());
+                self.advance_to(self.pos + len);
+            }
+        }
+
+        let span = self.span(start);
+        let entry = &mut self.index.nodes[node];
+        entry.span = span;
+        entry.len = entry.children.len();
+        Ok(node)
+    }
+
+    /// Consume a `,` (true) or the closing `close` (false)
+    fn separator(&mut self, close: u8) -> Result<bool> {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.advance_to(self.pos + 1);
+            return Ok(true);
+        }
+        self.expect(close)?;
+        Ok(false)
+    }
+}
+
+/// Escape a member name as a JSON Pointer reference token
+fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str =
+        "{\n  \"users\": [\n    {\"name\": \"Ann\", \"a/b\": 1},\n    {\"name\": \"Bo\"}\n  ]\n}";
+
+    #[test]
+    fn test_spans_by_pointer() {
+        let index = SpanIndex::new(DOC).unwrap();
+        assert_eq!(index.len(), 7);
+
+        let root = index.get("").unwrap();
+        assert_eq!(root.kind, NodeKind::Object);
+        assert_eq!((root.span.start, root.span.end), (0, DOC.len()));
+        assert_eq!((root.span.end_line, root.span.end_column), (6, 2));
+
+        let name = index.get("/users/1/name").unwrap();
+        assert_eq!(&DOC[name.span.start..name.span.end], "\"Bo\"");
+        assert_eq!((name.span.line, name.span.column), (4, 14));
+        let key = name.key_span.unwrap();
+        assert_eq!(&DOC[key.start..key.end], "\"name\"");
+
+        let escaped = index.get("/users/0/a~1b").unwrap();
+        assert_eq!(escaped.kind, NodeKind::Number);
+        assert_eq!(&DOC[escaped.span.start..escaped.span.end], "1");
+        assert!(index.get("/users/2").is_none());
+    }
+
+    #[test]
+    fn test_children_for_tree_view() {
+        let index = SpanIndex::new(DOC).unwrap();
+        let users = index.children("/users").unwrap();
+        let pointers: Vec<&str> = users.iter().map(|n| n.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/users/0", "/users/1"]);
+        assert_eq!(users[0].len, 2);
+        assert!(index.children("/users/0/name").unwrap().is_empty());
+        assert!(index.children("/missing").is_none());
+    }
+
+    #[test]
+    fn test_utf16_units() {
+        let text = r#"{"😀": "é", "n": -1.5e3, "ok": [true, null]}"#;
+        let chars = SpanIndex::new(text).unwrap();
+        let utf16 = SpanIndex::new_utf16(text).unwrap();
+
+        let n = chars.get("/n").unwrap().span;
+        assert_eq!(&text[n.start..n.end], "-1.5e3");
+        assert_eq!(n.column, 17);
+        let n = utf16.get("/n").unwrap().span;
+        assert_eq!((n.start, n.column), (17, 18));
+        assert_eq!(utf16.get("/ok/1").unwrap().kind, NodeKind::Null);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let err = SpanIndex::new("{\"a\": }").unwrap_err();
+        assert!(err.to_string().contains("line 1 column 7"));
+    }
+}
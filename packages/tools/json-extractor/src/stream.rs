@@ -0,0 +1,692 @@
+//! Streaming extraction for documents too large to load into memory
+//!
+//! The document is read token by token and only the subtrees selected by a
+//! path are materialized. Supported segments are child names, non-negative
+//! indices, wildcards and recursive descent; everything else needs random
+//! access and is rejected up front. Each matching node is reported once per
+//! path, in document order. Aliases in `alias=path` entries are ignored.
+
+use crate::extract::split_alias;
+use crate::path::{JsonPath, NormalizedPath, PathElement, PathParser};
+use crate::MAX_JSON_DEPTH;
+use serde_json::{Map, Value};
+use shard_den_core::{Result, ShardDenError};
+use std::io::{BufRead, BufReader, Read};
+
+/// A value matched while streaming
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamMatch {
+    /// Index of the path (in the order given) that selected the value
+    pub path_index: usize,
+    /// Where the value was found
+    pub location: NormalizedPath,
+    pub value: Value,
+}
+
+/// Single-pass extractor over a byte stream
+#[derive(Debug)]
+pub struct StreamExtractor {
+    paths: Vec<Vec<JsonPath>>,
+}
+
+/// Active automaton positions, one list per path
+type States = Vec<Vec<usize>>;
+
+impl StreamExtractor {
+    /// Parse `paths` and check that each can be answered in one pass
+    pub fn new(parser: &PathParser, paths: &[String]) -> Result<Self> {
+        let parsed = paths
+            .iter()
+            .map(|path| {
+                let segments = parser.parse(split_alias(path).1)?;
+                if !segments.iter().all(is_streamable) {
+                    return Err(ShardDenError::invalid_input(format!(
+                        "Path '{}' cannot be evaluated in streaming mode \
+                         (only child, wildcard, index and recursive descent segments are supported)",
+                        path
+                    )));
+                }
+                Ok(segments)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { paths: parsed })
+    }
+
+    /// Read a document from `reader`, calling `on_match` as soon as each match is complete
+    pub fn run<R: Read>(
+        &self, reader: R, mut on_match: impl FnMut(StreamMatch) -> Result<()>,
+    ) -> Result<()> {
+        let mut tokens = Tokenizer::new(BufReader::new(reader));
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut location: Vec<PathElement> = Vec::new();
+
+        while let Some(event) = tokens.next_event()? {
+            let element = match &event {
+                Event::Key(key) => {
+                    if let Some(frame) = frames.last_mut() {
+                        frame.pending_key = Some(key.clone());
+                    }
+                    continue;
+                }
+                Event::EndObject | Event::EndArray => {
+                    frames.pop();
+                    location.pop();
+                    continue;
+                }
+                _ => frames.last_mut().map(Frame::next_element),
+            };
+
+            let states = match (&element, frames.last()) {
+                (Some(element), Some(frame)) => self.step(&frame.states, element),
+                _ => vec![vec![0]; self.paths.len()],
+            };
+            let mut here = location.clone();
+            here.extend(element.clone());
+
+            if !self.accepting(&states).is_empty() {
+                let value = match event {
+                    Event::Value(value) => value,
+                    start => read_value(&mut tokens, start, location.len())?,
+                };
+                self.emit_tree(&value, &mut here, &states, &mut on_match)?;
+            } else if matches!(event, Event::StartObject | Event::StartArray) {
+                if states.iter().all(Vec::is_empty) {
+                    skip_container(&mut tokens, location.len())?;
+                    continue;
+                }
+                if frames.len() >= MAX_JSON_DEPTH {
+                    return Err(too_deep());
+                }
+                frames.push(Frame {
+                    states,
+                    pending_key: None,
+                    next_index: 0,
+                });
+                location = here;
+            }
+        }
+        Ok(())
+    }
+
+    /// Report `value` for every accepting path, then look for nested matches
+    fn emit_tree(
+        &self, value: &Value, location: &mut Vec<PathElement>, states: &States,
+        on_match: &mut impl FnMut(StreamMatch) -> Result<()>,
+    ) -> Result<()> {
+        for path_index in self.accepting(states) {
+            on_match(StreamMatch {
+                path_index,
+                location: NormalizedPath::from(location.clone()),
+                value: value.clone(),
+            })?;
+        }
+
+        let children: Vec<(PathElement, &Value)> = match value {
+            Value::Array(arr) => arr
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (PathElement::Index(i), v))
+                .collect(),
+            Value::Object(obj) => obj
+                .iter()
+                .map(|(k, v)| (PathElement::Key(k.clone()), v))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for (element, child) in children {
+            let child_states = self.step(states, &element);
+            if child_states.iter().all(Vec::is_empty) {
+                continue;
+            }
+            location.push(element);
+            self.emit_tree(child, location, &child_states, on_match)?;
+            location.pop();
+        }
+        Ok(())
+    }
+
+    /// Advance every path's automaton over one location element
+    fn step(&self, states: &States, element: &PathElement) -> States {
+        self.paths
+            .iter()
+            .zip(states)
+            .map(|(segments, positions)| {
+                let mut next = Vec::new();
+                for &pos in positions {
+                    let Some(segment) = segments.get(pos) else {
+                        continue;
+                    };
+                    if matches!(segment, JsonPath::Recursive(_) | JsonPath::Descendant(_)) {
+                        next.push(pos);
+                    }
+                    if segment_matches(segment, element) {
+                        next.push(pos + 1);
+                    }
+                }
+                next.sort_unstable();
+                next.dedup();
+                next
+            })
+            .collect()
+    }
+
+    fn accepting(&self, states: &States) -> Vec<usize> {
+        self.paths
+            .iter()
+            .zip(states)
+            .enumerate()
+            .filter(|(_, (segments, positions))| positions.contains(&segments.len()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+fn is_streamable(segment: &JsonPath) -> bool {
+    match segment {
+        JsonPath::Key(_) | JsonPath::Wildcard | JsonPath::Recursive(_) => true,
+        JsonPath::Index(i) => *i >= 0,
+        JsonPath::Descendant(inner) => {
+            matches!(**inner, JsonPath::Key(_) | JsonPath::Wildcard)
+                || matches!(**inner, JsonPath::Index(i) if i >= 0)
+        }
+        _ => false,
+    }
+}
+
+fn segment_matches(segment: &JsonPath, element: &PathElement) -> bool {
+    match (segment, element) {
+        (JsonPath::Key(k), PathElement::Key(e)) | (JsonPath::Recursive(k), PathElement::Key(e)) => {
+            k == e
+        }
+        (JsonPath::Index(i), PathElement::Index(e)) => usize::try_from(*i) == Ok(*e),
+        (JsonPath::Wildcard, _) => true,
+        (JsonPath::Descendant(inner), element) => segment_matches(inner, element),
+        _ => false,
+    }
+}
+
+fn too_deep() -> ShardDenError {
+    ShardDenError::invalid_input(format!("JSON too deeply nested (max: {})", MAX_JSON_DEPTH))
+}
+
+/// Position inside a container that is being walked
+struct Frame {
+    states: States,
+    pending_key: Option<String>,
+    next_index: usize,
+}
+
+impl Frame {
+    fn next_element(&mut self) -> PathElement {
+        match self.pending_key.take() {
+            Some(key) => PathElement::Key(key),
+            None => {
+                self.next_index += 1;
+                PathElement::Index(self.next_index - 1)
+            }
+        }
+    }
+}
+
+/// Materialize the value that begins with `start`
+fn read_value<R: BufRead>(tokens: &mut Tokenizer<R>, start: Event, depth: usize) -> Result<Value> {
+    let mut stack: Vec<(Value, Option<String>)> = Vec::new();
+    let mut event = start;
+    loop {
+        let completed = match event {
+            Event::StartObject | Event::StartArray => {
+                if depth + stack.len() >= MAX_JSON_DEPTH {
+                    return Err(too_deep());
+                }
+                let container = if matches!(event, Event::StartObject) {
+                    Value::Object(Map::new())
+                } else {
+                    Value::Array(Vec::new())
+                };
+                stack.push((container, None));
+                None
+            }
+            Event::Key(key) => {
+                if let Some(top) = stack.last_mut() {
+                    top.1 = Some(key);
+                }
+                None
+            }
+            Event::EndObject | Event::EndArray => stack.pop().map(|(value, _)| value),
+            Event::Value(value) => Some(value),
+        };
+
+        if let Some(value) = completed {
+            match stack.last_mut() {
+                None => return Ok(value),
+                Some((Value::Array(arr), _)) => arr.push(value),
+                Some((Value::Object(obj), key)) => {
+                    obj.insert(key.take().unwrap_or_default(), value);
+                }
+                Some(_) => {}
+            }
+        }
+        event = tokens
+            .next_event()?
+            .ok_or_else(|| tokens.unexpected_end())?;
+    }
+}
+
+/// Consume the rest of a container without building it
+fn skip_container<R: BufRead>(tokens: &mut Tokenizer<R>, depth: usize) -> Result<()> {
+    let mut open = 1;
+    while open > 0 {
+        match tokens.next_event()? {
+            Some(Event::StartObject | Event::StartArray) => {
+                open += 1;
+                if depth + open > MAX_JSON_DEPTH {
+                    return Err(too_deep());
+                }
+            }
+            Some(Event::EndObject | Event::EndArray) => open -= 1,
+            Some(_) => {}
+            None => return Err(tokens.unexpected_end()),
+        }
+    }
+    Ok(())
+}
+
+/// Structural events produced by the tokenizer
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(String),
+    Value(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    FirstKey,
+    Key,
+    FirstElement,
+    AfterValue,
+}
+
+/// Incremental JSON tokenizer over a buffered reader
+struct Tokenizer<R> {
+    reader: R,
+    containers: Vec<u8>,
+    expect: Expect,
+    line: usize,
+    column: usize,
+    started: bool,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            containers: Vec::new(),
+            expect: Expect::Value,
+            line: 1,
+            column: 1,
+            started: false,
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>> {
+        loop {
+            self.skip_whitespace()?;
+            let byte = self.peek()?;
+            match self.expect {
+                Expect::Value => {
+                    let Some(byte) = byte else {
+                        return Err(self.unexpected_end());
+                    };
+                    self.started = true;
+                    return self.read_value_start(byte).map(Some);
+                }
+                Expect::FirstKey if byte == Some(b'}') => {
+                    self.bump()?;
+                    return Ok(Some(self.close()));
+                }
+                Expect::FirstKey | Expect::Key => {
+                    if byte != Some(b'"') {
+                        return Err(self.error("expected object key"));
+                    }
+                    let key = self.read_string()?;
+                    self.skip_whitespace()?;
+                    if self.peek()? != Some(b':') {
+                        return Err(self.error("expected ':' after object key"));
+                    }
+                    self.bump()?;
+                    self.expect = Expect::Value;
+                    return Ok(Some(Event::Key(key)));
+                }
+                Expect::FirstElement if byte == Some(b']') => {
+                    self.bump()?;
+                    return Ok(Some(self.close()));
+                }
+                Expect::FirstElement => self.expect = Expect::Value,
+                Expect::AfterValue => {
+                    let Some(&container) = self.containers.last() else {
+                        return match byte {
+                            None => Ok(None),
+                            Some(_) => Err(self.error("trailing characters after JSON value")),
+                        };
+                    };
+                    let close = if container == b'{' { b'}' } else { b']' };
+                    match byte {
+                        Some(b',') => {
+                            self.bump()?;
+                            self.expect = if container == b'{' {
+                                Expect::Key
+                            } else {
+                                Expect::Value
+                            };
+                        }
+                        None => return Err(self.unexpected_end()),
+                        Some(b) if b == close => {
+                            self.bump()?;
+                            return Ok(Some(self.close()));
+                        }
+                        _ => return Err(self.error(format!("expected ',' or '{}'", close as char))),
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_value_start(&mut self, byte: u8) -> Result<Event> {
+        match byte {
+            b'{' | b'[' => {
+                self.bump()?;
+                self.containers.push(byte);
+                self.expect = if byte == b'{' {
+                    Expect::FirstKey
+                } else {
+                    Expect::FirstElement
+                };
+                Ok(if byte == b'{' {
+                    Event::StartObject
+                } else {
+                    Event::StartArray
+                })
+            }
+            b'"' => {
+                let s = self.read_string()?;
+                self.expect = Expect::AfterValue;
+                Ok(Event::Value(Value::String(s)))
+            }
+            _ => {
+                let mut text = Vec::new();
+                while let Some(b) = self.peek()? {
+                    if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.') {
+                        text.push(b);
+                        self.bump()?;
+                    } else {
+                        break;
+                    }
+                }
+                let text = String::from_utf8_lossy(&text).into_owned();
+                let value = match text.as_str() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    "null" => Value::Null,
+                    _ => serde_json::from_str::<serde_json::Number>(&text)
+                        .map(Value::Number)
+                        .map_err(|_| {
+                            if text.is_empty() {
+                                self.error(format!("unexpected character '{}'", byte as char))
+                            } else {
+                                self.error(format!("invalid literal '{}'", text))
+                            }
+                        })?,
+                };
+                self.expect = Expect::AfterValue;
+                Ok(Event::Value(value))
+            }
+        }
+    }
+
+    fn close(&mut self) -> Event {
+        let container = self.containers.pop();
+        self.expect = Expect::AfterValue;
+        if container == Some(b'{') {
+            Event::EndObject
+        } else {
+            Event::EndArray
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        self.bump()?; // opening quote
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self
+                .bump()?
+                .ok_or_else(|| self.error("unterminated string"))?;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self
+                        .bump()?
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.read_unicode_escape()?,
+                        other => {
+                            return Err(self.error(format!("invalid escape '\\{}'", other as char)))
+                        }
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                b if b < 0x20 => return Err(self.error("control character in string")),
+                b => bytes.push(b),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn read_unicode_escape(&mut self) -> Result<char> {
+        let high = self.read_hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+        if self.bump()? != Some(b'\\') || self.bump()? != Some(b'u') {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+        let low = self.read_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("invalid low surrogate in unicode escape"));
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn read_hex4(&mut self) -> Result<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .bump()?
+                .and_then(|b| (b as char).to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<()> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        let buf = self.reader.fill_buf()?;
+        Ok(buf.first().copied())
+    }
+
+    fn bump(&mut self) -> Result<Option<u8>> {
+        let byte = self.peek()?;
+        if let Some(b) = byte {
+            self.reader.consume(1);
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        Ok(byte)
+    }
+
+    /// End of input mid-document, or before any value at all
+    fn unexpected_end(&self) -> ShardDenError {
+        if self.started {
+            self.error("unexpected end of input")
+        } else {
+            ShardDenError::invalid_input("Empty input")
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ShardDenError {
+        ShardDenError::invalid_input(format!(
+            "{} at line {} column {}",
+            message.into(),
+            self.line,
+            self.column
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn stream(json: &str, paths: &[&str]) -> Result<Vec<StreamMatch>> {
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+        let extractor = StreamExtractor::new(&PathParser::new(), &paths)?;
+        let mut matches = Vec::new();
+        extractor.run(json.as_bytes(), |m| {
+            matches.push(m);
+            Ok(())
+        })?;
+        Ok(matches)
+    }
+
+    fn values(matches: &[StreamMatch]) -> Vec<Value> {
+        matches.iter().map(|m| m.value.clone()).collect()
+    }
+
+    #[test]
+    fn test_stream_child_and_index() {
+        let json = r#"{"users": [{"name": "a"}, {"name": "b"}], "count": 2}"#;
+        let matches = stream(json, &["$.users[1].name", "$.count"]).unwrap();
+        assert_eq!(values(&matches), vec![json!("b"), json!(2)]);
+        assert_eq!(matches[0].path_index, 0);
+        assert_eq!(matches[0].location.to_string(), "$['users'][1]['name']");
+        assert_eq!(matches[1].path_index, 1);
+    }
+
+    #[test]
+    fn test_stream_wildcard() {
+        let json = r#"{"users": [{"name": "a", "tags": [1]}, {"name": "b"}]}"#;
+        let matches = stream(json, &["$.users[*].name"]).unwrap();
+        assert_eq!(values(&matches), vec![json!("a"), json!("b")]);
+    }
+
+    #[test]
+    fn test_stream_captures_subtrees() {
+        let json = r#"{"a": {"b": [1, {"c": true}]}, "z": 0}"#;
+        let matches = stream(json, &["$.a"]).unwrap();
+        assert_eq!(values(&matches), vec![json!({"b": [1, {"c": true}]})]);
+    }
+
+    #[test]
+    fn test_stream_recursive_descent_in_document_order() {
+        let json = r#"{"id": 0, "a": {"id": {"id": 2}}, "b": [{"id": 3}]}"#;
+        let matches = stream(json, &["$..id"]).unwrap();
+        assert_eq!(
+            values(&matches),
+            vec![json!(0), json!({"id": 2}), json!(2), json!(3)]
+        );
+    }
+
+    #[test]
+    fn test_stream_descendant_wildcard() {
+        let matches = stream("[[1], 2]", &["$..*"]).unwrap();
+        assert_eq!(values(&matches), vec![json!([1]), json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn test_stream_root_and_scalars() {
+        assert_eq!(values(&stream("42", &["$"]).unwrap()), vec![json!(42)]);
+        let json =
+            r#"{"s": "tab\t\u00e9 \ud83d\ude00", "n": -1.5e3, "t": true, "f": false, "z": null}"#;
+        let matches = stream(json, &["$.*"]).unwrap();
+        assert_eq!(matches.len(), 5);
+        assert!(values(&matches).contains(&json!("tab\té 😀")));
+    }
+
+    #[test]
+    fn test_stream_rejects_unsupported_paths() {
+        for path in ["$[-1]", "$[0:2]", "$[?@.a]", "$['a','b']"] {
+            assert!(
+                stream("[]", &[path]).is_err(),
+                "{} should be rejected",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_stream_invalid_json() {
+        for json in [
+            "",
+            "{",
+            r#"{"a" 1}"#,
+            "[1,]x",
+            "[1] 2",
+            r#"{"a": tru}"#,
+            "[\"\\x\"]",
+        ] {
+            assert!(stream(json, &["$.a"]).is_err(), "{:?} should fail", json);
+        }
+    }
+
+    #[test]
+    fn test_stream_error_position() {
+        let err = stream("{\n  \"a\": ?\n}", &["$.a"]).unwrap_err();
+        assert!(err.to_string().contains("line 2 column 8"), "{}", err);
+    }
+
+    #[test]
+    fn test_stream_empty_input() {
+        let err = stream("  \n", &["$.a"]).unwrap_err();
+        assert!(err.to_string().contains("Empty input"), "{}", err);
+        let err = stream("[1,", &["$.a"]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unexpected end of input at line 1"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_stream_depth_limit() {
+        let json = format!("{}1{}", "[".repeat(200), "]".repeat(200));
+        assert!(stream(&json, &["$.missing"]).is_err());
+        assert!(stream(&json, &["$..*"]).is_err());
+    }
+}
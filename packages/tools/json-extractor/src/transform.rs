@@ -0,0 +1,1459 @@
+//! jq-style transformations applied after path selection
+//!
+//! A program is a pipeline of filters, each producing zero or more values:
+//!
+//! - `.`, `.name`, `.["key"]`, `.[0]`, `.[1:3]`, `.[]`, `..`, `expr?`
+//! - `a | b`, `a, b`, `a // b`, arithmetic, comparisons, `and`, `or`
+//! - `[expr]`, `{name, key: expr, "k": expr, (expr): expr}`
+//! - `"Hello \(.name)"` string interpolation
+//! - `if c then a elif d then b else e end`
+//! - `map`, `select`, `sort`, `sort_by`, `group_by`, `unique`, `unique_by`,
+//!   `keys`, `to_entries`, `from_entries`, `with_entries`, `length`, `add`,
+//!   `min`, `max`, `reverse`, `first`, `last`, `not`, `type`, `tostring`,
+//!   `tonumber`, `has`, `join`, `split`, `flatten`, `any`, `all`,
+//!   `ascii_downcase`, `ascii_upcase`, `empty`
+
+use crate::detect::json_type;
+use serde_json::{Map, Number, Value};
+use shard_den_core::ShardDenError;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Maximum nesting of brackets and parentheses in a program
+const MAX_NESTING: usize = 64;
+
+/// Error produced when a transform program cannot be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformError {
+    /// Byte offset in the program where parsing failed
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+impl From<TransformError> for ShardDenError {
+    fn from(err: TransformError) -> Self {
+        ShardDenError::invalid_input(format!("Transform error: {}", err))
+    }
+}
+
+/// A parsed transform program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform {
+    expr: Expr,
+}
+
+impl Transform {
+    /// Parse a program
+    pub fn parse(program: &str) -> Result<Self, TransformError> {
+        let mut parser = Parser {
+            src: program,
+            pos: 0,
+            nesting: 0,
+        };
+        parser.skip_ws();
+        if parser.at_end() {
+            return Ok(Self {
+                expr: Expr::Identity,
+            });
+        }
+        let expr = parser.parse_pipe()?;
+        parser.skip_ws();
+        if !parser.at_end() {
+            return Err(parser.error("Unexpected character"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Run the program, returning every value it produces
+    pub fn apply(&self, input: &Value) -> shard_den_core::Result<Vec<Value>> {
+        eval(&self.expr, input)
+            .map_err(|e| ShardDenError::invalid_input(format!("Transform error: {}", e)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Identity,
+    Recurse,
+    Literal(Value),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Str(Vec<StrPart>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Alt(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum StrPart {
+    Lit(String),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Built-in functions and their number of arguments
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("map", 1),
+    ("select", 1),
+    ("sort", 0),
+    ("sort_by", 1),
+    ("group_by", 1),
+    ("unique", 0),
+    ("unique_by", 1),
+    ("keys", 0),
+    ("to_entries", 0),
+    ("from_entries", 0),
+    ("with_entries", 1),
+    ("length", 0),
+    ("add", 0),
+    ("min", 0),
+    ("max", 0),
+    ("reverse", 0),
+    ("first", 0),
+    ("last", 0),
+    ("not", 0),
+    ("type", 0),
+    ("tostring", 0),
+    ("tonumber", 0),
+    ("has", 1),
+    ("join", 1),
+    ("split", 1),
+    ("flatten", 0),
+    ("any", 0),
+    ("all", 0),
+    ("ascii_downcase", 0),
+    ("ascii_upcase", 0),
+    ("empty", 0),
+];
+
+const KEYWORDS: &[&str] = &[
+    "if", "then", "elif", "else", "end", "and", "or", "true", "false", "null",
+];
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    nesting: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> TransformError {
+        TransformError {
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while let Some(c) = self.peek().filter(|c| *c != '\n') {
+                    self.pos += c.len_utf8();
+                }
+            } else if c.is_whitespace() {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Consume `token` (after whitespace) if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.src[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), TransformError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", token)))
+        }
+    }
+
+    /// Consume `keyword` if it comes next as a whole word
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_ws();
+        let start = self.pos;
+        match self.ident() {
+            Some(word) if word == keyword => true,
+            _ => {
+                self.pos = start;
+                false
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        let rest = &self.src[self.pos..];
+        let first = rest.chars().next()?;
+        if !(first.is_ascii_alphabetic() || first == '_') {
+            return None;
+        }
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        Some(rest[..len].to_string())
+    }
+
+    fn enter(&mut self) -> Result<(), TransformError> {
+        self.nesting += 1;
+        if self.nesting > MAX_NESTING {
+            return Err(self.error("Program nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn parse_pipe(&mut self) -> Result<Expr, TransformError> {
+        let lhs = self.parse_comma()?;
+        if self.eat("|") {
+            let rhs = self.parse_pipe()?;
+            return Ok(Expr::Pipe(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_comma(&mut self) -> Result<Expr, TransformError> {
+        let mut lhs = self.parse_alt()?;
+        while self.eat(",") {
+            let rhs = self.parse_alt()?;
+            lhs = Expr::Comma(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_alt(&mut self) -> Result<Expr, TransformError> {
+        let lhs = self.parse_or()?;
+        if self.eat("//") {
+            let rhs = self.parse_alt()?;
+            return Ok(Expr::Alt(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, TransformError> {
+        let mut lhs = self.parse_and()?;
+        while self.eat_keyword("or") {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, TransformError> {
+        let mut lhs = self.parse_comparison()?;
+        while self.eat_keyword("and") {
+            let rhs = self.parse_comparison()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, TransformError> {
+        let lhs = self.parse_additive()?;
+        let ops = [
+            ("==", BinOp::Eq),
+            ("!=", BinOp::Ne),
+            ("<=", BinOp::Le),
+            (">=", BinOp::Ge),
+            ("<", BinOp::Lt),
+            (">", BinOp::Gt),
+        ];
+        for (token, op) in ops {
+            if self.eat(token) {
+                let rhs = self.parse_additive()?;
+                return Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, TransformError> {
+        let mut lhs = self.parse_multiplicative()?;
+        loop {
+            let op = if self.eat("+") {
+                BinOp::Add
+            } else if self.eat("-") {
+                BinOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_multiplicative()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, TransformError> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            self.skip_ws();
+            let op = if self.eat("*") {
+                BinOp::Mul
+            } else if self.src[self.pos..].starts_with('/')
+                && !self.src[self.pos..].starts_with("//")
+            {
+                self.pos += 1;
+                BinOp::Div
+            } else if self.eat("%") {
+                BinOp::Mod
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_unary()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, TransformError> {
+        if self.eat("-") {
+            let operand = self.parse_postfix()?;
+            return Ok(Expr::Neg(Box::new(operand)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, TransformError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat("?") {
+                expr = Expr::Optional(Box::new(expr));
+            } else if self.src[self.pos..].starts_with('[') {
+                expr = self.parse_bracket(expr)?;
+            } else if self.src[self.pos..].starts_with('.')
+                && !self.src[self.pos..].starts_with("..")
+            {
+                self.pos += 1;
+                if self.src[self.pos..].starts_with('[') {
+                    expr = self.parse_bracket(expr)?;
+                } else {
+                    let name = self.field_name()?;
+                    expr = Expr::Field(Box::new(expr), name);
+                }
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Name after `.`: an identifier or a string literal
+    fn field_name(&mut self) -> Result<String, TransformError> {
+        if self.src[self.pos..].starts_with('"') {
+            return match self.parse_string()? {
+                Expr::Literal(Value::String(s)) => Ok(s),
+                _ => Err(self.error("Interpolation is not allowed in field names")),
+            };
+        }
+        self.ident()
+            .ok_or_else(|| self.error("Expected field name"))
+    }
+
+    /// `[]`, `[expr]` or `[from:to]` applied to `target`
+    fn parse_bracket(&mut self, target: Expr) -> Result<Expr, TransformError> {
+        self.expect("[")?;
+        self.enter()?;
+        let target = Box::new(target);
+        let expr = if self.eat("]") {
+            Expr::Iterate(target)
+        } else if self.eat(":") {
+            let to = self.parse_pipe()?;
+            self.expect("]")?;
+            Expr::Slice(target, None, Some(Box::new(to)))
+        } else {
+            let index = self.parse_pipe()?;
+            if self.eat(":") {
+                let to = if self.eat("]") {
+                    None
+                } else {
+                    let to = self.parse_pipe()?;
+                    self.expect("]")?;
+                    Some(Box::new(to))
+                };
+                Expr::Slice(target, Some(Box::new(index)), to)
+            } else {
+                self.expect("]")?;
+                Expr::Index(target, Box::new(index))
+            }
+        };
+        self.nesting -= 1;
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, TransformError> {
+        self.skip_ws();
+        let start = self.pos;
+        let Some(c) = self.peek() else {
+            return Err(self.error("Unexpected end of program"));
+        };
+        match c {
+            '.' => {
+                self.pos += 1;
+                if self.src[self.pos..].starts_with('.') {
+                    self.pos += 1;
+                    return Ok(Expr::Recurse);
+                }
+                let next = self.peek();
+                if next.is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '"') {
+                    let name = self.field_name()?;
+                    return Ok(Expr::Field(Box::new(Expr::Identity), name));
+                }
+                Ok(Expr::Identity)
+            }
+            '"' => self.parse_string(),
+            '0'..='9' => self.parse_number(),
+            '(' => {
+                self.pos += 1;
+                self.enter()?;
+                let expr = self.parse_pipe()?;
+                self.expect(")")?;
+                self.nesting -= 1;
+                Ok(expr)
+            }
+            '[' => {
+                self.pos += 1;
+                self.enter()?;
+                let expr = if self.eat("]") {
+                    Expr::Array(None)
+                } else {
+                    let inner = self.parse_pipe()?;
+                    self.expect("]")?;
+                    Expr::Array(Some(Box::new(inner)))
+                };
+                self.nesting -= 1;
+                Ok(expr)
+            }
+            '{' => self.parse_object(),
+            _ => {
+                let Some(word) = self.ident() else {
+                    return Err(self.error("Unexpected character"));
+                };
+                match word.as_str() {
+                    "true" => Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => Ok(Expr::Literal(Value::Null)),
+                    "if" => self.parse_if(),
+                    _ if KEYWORDS.contains(&word.as_str()) => {
+                        self.pos = start;
+                        Err(self.error(&format!("Unexpected keyword '{}'", word)))
+                    }
+                    _ => self.parse_call(word, start),
+                }
+            }
+        }
+    }
+
+    fn parse_call(&mut self, name: String, start: usize) -> Result<Expr, TransformError> {
+        let mut args = Vec::new();
+        if self.src[self.pos..].starts_with('(') {
+            self.pos += 1;
+            self.enter()?;
+            loop {
+                args.push(self.parse_pipe()?);
+                if !self.eat(";") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+            self.nesting -= 1;
+        }
+        match FUNCTIONS.iter().find(|(f, _)| *f == name) {
+            Some((_, arity)) if *arity == args.len() => Ok(Expr::Call(name, args)),
+            Some((_, arity)) => Err(TransformError {
+                position: start,
+                message: format!("Function '{}' takes {} argument(s)", name, arity),
+            }),
+            None => Err(TransformError {
+                position: start,
+                message: format!("Unknown function '{}'", name),
+            }),
+        }
+    }
+
+    fn parse_if(&mut self) -> Result<Expr, TransformError> {
+        self.enter()?;
+        let condition = self.parse_pipe()?;
+        if !self.eat_keyword("then") {
+            return Err(self.error("Expected 'then'"));
+        }
+        let then = self.parse_pipe()?;
+        let otherwise = if self.eat_keyword("elif") {
+            self.nesting -= 1;
+            return Ok(Expr::If(
+                Box::new(condition),
+                Box::new(then),
+                Box::new(self.parse_if()?),
+            ));
+        } else if self.eat_keyword("else") {
+            self.parse_pipe()?
+        } else {
+            Expr::Identity
+        };
+        if !self.eat_keyword("end") {
+            return Err(self.error("Expected 'end'"));
+        }
+        self.nesting -= 1;
+        Ok(Expr::If(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn parse_object(&mut self) -> Result<Expr, TransformError> {
+        self.expect("{")?;
+        self.enter()?;
+        let mut entries = Vec::new();
+        if !self.eat("}") {
+            loop {
+                self.skip_ws();
+                let (key, shorthand) = match self.peek() {
+                    Some('"') => {
+                        let key = self.parse_string()?;
+                        let shorthand = match &key {
+                            Expr::Literal(Value::String(name)) => Some(name.clone()),
+                            _ => None,
+                        };
+                        (key, shorthand)
+                    }
+                    Some('(') => {
+                        self.pos += 1;
+                        let key = self.parse_pipe()?;
+                        self.expect(")")?;
+                        (key, None)
+                    }
+                    _ => {
+                        let name = self
+                            .ident()
+                            .ok_or_else(|| self.error("Expected object key"))?;
+                        (Expr::Literal(Value::String(name.clone())), Some(name))
+                    }
+                };
+                let value = if self.eat(":") {
+                    self.parse_object_value()?
+                } else if let Some(name) = shorthand {
+                    Expr::Field(Box::new(Expr::Identity), name)
+                } else {
+                    return Err(self.error("Expected ':'"));
+                };
+                entries.push((key, value));
+                if self.eat("}") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        self.nesting -= 1;
+        Ok(Expr::Object(entries))
+    }
+
+    /// Object member value: a pipeline without top-level commas
+    fn parse_object_value(&mut self) -> Result<Expr, TransformError> {
+        let lhs = self.parse_alt()?;
+        if self.eat("|") {
+            let rhs = self.parse_object_value()?;
+            return Ok(Expr::Pipe(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_number(&mut self) -> Result<Expr, TransformError> {
+        let rest = &self.src[self.pos..];
+        let mut len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if rest[len..].starts_with(['e', 'E']) {
+            let exp = &rest[len + 1..];
+            let sign = usize::from(exp.starts_with(['+', '-']));
+            let digits = exp[sign..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(exp.len() - sign);
+            if digits > 0 {
+                len += 1 + sign + digits;
+            }
+        }
+        let value: Value = serde_json::from_str(&rest[..len])
+            .ok()
+            .or_else(|| rest[..len].parse::<f64>().ok().and_then(number))
+            .ok_or_else(|| self.error("Invalid number"))?;
+        self.pos += len;
+        Ok(Expr::Literal(value))
+    }
+
+    /// String literal, possibly with `\(expr)` interpolations
+    fn parse_string(&mut self) -> Result<Expr, TransformError> {
+        self.expect("\"")?;
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => break,
+                '\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("Unterminated string"));
+                    };
+                    self.pos += escape.len_utf8();
+                    match escape {
+                        '(' => {
+                            self.enter()?;
+                            if !text.is_empty() {
+                                parts.push(StrPart::Lit(std::mem::take(&mut text)));
+                            }
+                            parts.push(StrPart::Expr(self.parse_pipe()?));
+                            self.expect(")")?;
+                            self.nesting -= 1;
+                        }
+                        '"' | '\\' | '/' => text.push(escape),
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'u' => {
+                            let hex = self.src.get(self.pos..self.pos + 4);
+                            let ch = hex
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("Invalid unicode escape"))?;
+                            self.pos += 4;
+                            text.push(ch);
+                        }
+                        _ => return Err(self.error("Invalid escape")),
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+        if parts.is_empty() {
+            return Ok(Expr::Literal(Value::String(text)));
+        }
+        if !text.is_empty() {
+            parts.push(StrPart::Lit(text));
+        }
+        Ok(Expr::Str(parts))
+    }
+}
+
+type EvalResult = Result<Vec<Value>, String>;
+
+fn eval(expr: &Expr, input: &Value) -> EvalResult {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Recurse => {
+            let mut out = Vec::new();
+            descendants(input, &mut out);
+            Ok(out)
+        }
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Field(target, name) => eval(target, input)?
+            .iter()
+            .map(|v| index(v, &Value::String(name.clone())))
+            .collect(),
+        Expr::Index(target, idx) => {
+            let keys = eval(idx, input)?;
+            let mut out = Vec::new();
+            for v in eval(target, input)? {
+                for key in &keys {
+                    out.push(index(&v, key)?);
+                }
+            }
+            Ok(out)
+        }
+        Expr::Slice(target, from, to) => {
+            let bound = |e: &Option<Box<Expr>>| -> Result<Option<f64>, String> {
+                match e {
+                    None => Ok(None),
+                    Some(e) => match single(e, input)? {
+                        Value::Null => Ok(None),
+                        Value::Number(n) => Ok(n.as_f64()),
+                        other => Err(format!("Cannot slice with {}", json_type(&other))),
+                    },
+                }
+            };
+            let (from, to) = (bound(from)?, bound(to)?);
+            eval(target, input)?
+                .iter()
+                .map(|v| slice(v, from, to))
+                .collect()
+        }
+        Expr::Iterate(target) => {
+            let mut out = Vec::new();
+            for v in eval(target, input)? {
+                out.extend(iterate(&v)?);
+            }
+            Ok(out)
+        }
+        Expr::Optional(inner) => Ok(eval(inner, input).unwrap_or_default()),
+        Expr::Str(parts) => {
+            let mut out = vec![String::new()];
+            for part in parts {
+                match part {
+                    StrPart::Lit(text) => out.iter_mut().for_each(|s| s.push_str(text)),
+                    StrPart::Expr(e) => {
+                        let values = eval(e, input)?;
+                        out = out
+                            .iter()
+                            .flat_map(|s| {
+                                values.iter().map(move |v| format!("{}{}", s, stringify(v)))
+                            })
+                            .collect();
+                    }
+                }
+            }
+            Ok(out.into_iter().map(Value::String).collect())
+        }
+        Expr::Array(None) => Ok(vec![Value::Array(Vec::new())]),
+        Expr::Array(Some(inner)) => Ok(vec![Value::Array(eval(inner, input)?)]),
+        Expr::Object(entries) => {
+            let mut out = vec![Map::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for partial in &out {
+                    for k in &keys {
+                        let Value::String(k) = k else {
+                            return Err(format!(
+                                "Object keys must be strings, got {}",
+                                json_type(k)
+                            ));
+                        };
+                        for v in &values {
+                            let mut object = partial.clone();
+                            object.insert(k.clone(), v.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                out = next;
+            }
+            Ok(out.into_iter().map(Value::Object).collect())
+        }
+        Expr::Pipe(lhs, rhs) => {
+            let mut out = Vec::new();
+            for v in eval(lhs, input)? {
+                out.extend(eval(rhs, &v)?);
+            }
+            Ok(out)
+        }
+        Expr::Comma(lhs, rhs) => {
+            let mut out = eval(lhs, input)?;
+            out.extend(eval(rhs, input)?);
+            Ok(out)
+        }
+        Expr::Alt(lhs, rhs) => {
+            let values: Vec<Value> = eval(lhs, input)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(truthy)
+                .collect();
+            if values.is_empty() {
+                eval(rhs, input)
+            } else {
+                Ok(values)
+            }
+        }
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            let is_and = matches!(expr, Expr::And(..));
+            let mut out = Vec::new();
+            for l in eval(lhs, input)? {
+                if truthy(&l) != is_and {
+                    out.push(Value::Bool(!is_and));
+                    continue;
+                }
+                for r in eval(rhs, input)? {
+                    out.push(Value::Bool(truthy(&r)));
+                }
+            }
+            Ok(out)
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let rhs = eval(rhs, input)?;
+            let mut out = Vec::new();
+            for l in eval(lhs, input)? {
+                for r in &rhs {
+                    out.push(binary(*op, &l, r)?);
+                }
+            }
+            Ok(out)
+        }
+        Expr::Neg(operand) => eval(operand, input)?
+            .iter()
+            .map(|v| match v.as_f64() {
+                Some(n) => Ok(negate(v).unwrap_or_else(|| number(-n).unwrap_or(Value::Null))),
+                None => Err(format!("Cannot negate {}", json_type(v))),
+            })
+            .collect(),
+        Expr::If(condition, then, otherwise) => {
+            let mut out = Vec::new();
+            for c in eval(condition, input)? {
+                let branch = if truthy(&c) { then } else { otherwise };
+                out.extend(eval(branch, input)?);
+            }
+            Ok(out)
+        }
+        Expr::Call(name, args) => call(name, args, input),
+    }
+}
+
+fn call(name: &str, args: &[Expr], input: &Value) -> EvalResult {
+    let one = |v: Value| Ok(vec![v]);
+    match name {
+        "empty" => Ok(Vec::new()),
+        "select" => {
+            let mut out = Vec::new();
+            for c in eval(&args[0], input)? {
+                if truthy(&c) {
+                    out.push(input.clone());
+                }
+            }
+            Ok(out)
+        }
+        "map" => {
+            let mut out = Vec::new();
+            for v in iterate(input)? {
+                out.extend(eval(&args[0], &v)?);
+            }
+            one(Value::Array(out))
+        }
+        "with_entries" => {
+            let mut entries = Vec::new();
+            for entry in to_entries(input)? {
+                entries.extend(eval(&args[0], &entry)?);
+            }
+            one(from_entries(&entries)?)
+        }
+        "sort" | "sort_by" | "group_by" | "unique" | "unique_by" => {
+            let items = array(input, name)?;
+            let mut keyed = Vec::with_capacity(items.len());
+            for item in items {
+                let key = match args.first() {
+                    Some(f) => Value::Array(eval(f, item)?),
+                    None => item.clone(),
+                };
+                keyed.push((key, item.clone()));
+            }
+            keyed.sort_by(|a, b| compare(&a.0, &b.0));
+            let result = match name {
+                "sort" | "sort_by" => keyed.into_iter().map(|(_, v)| v).collect(),
+                "group_by" => {
+                    let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+                    for (key, v) in keyed {
+                        match groups.last_mut() {
+                            Some((k, group)) if compare(k, &key) == Ordering::Equal => {
+                                group.push(v)
+                            }
+                            _ => groups.push((key, vec![v])),
+                        }
+                    }
+                    groups.into_iter().map(|(_, g)| Value::Array(g)).collect()
+                }
+                _ => {
+                    keyed.dedup_by(|b, a| compare(&a.0, &b.0) == Ordering::Equal);
+                    keyed.into_iter().map(|(_, v)| v).collect()
+                }
+            };
+            one(Value::Array(result))
+        }
+        "keys" => match input {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                one(Value::Array(
+                    keys.into_iter().map(|k| Value::String(k.clone())).collect(),
+                ))
+            }
+            Value::Array(arr) => one(Value::Array((0..arr.len()).map(Value::from).collect())),
+            _ => Err(format!("{} has no keys", json_type(input))),
+        },
+        "to_entries" => one(Value::Array(to_entries(input)?)),
+        "from_entries" => one(from_entries(array(input, name)?)?),
+        "length" => one(match input {
+            Value::Null => Value::from(0),
+            Value::Bool(_) => return Err("boolean has no length".to_string()),
+            Value::Number(n) => number(n.as_f64().unwrap_or_default().abs()).unwrap_or(Value::Null),
+            Value::String(s) => Value::from(s.chars().count()),
+            Value::Array(arr) => Value::from(arr.len()),
+            Value::Object(map) => Value::from(map.len()),
+        }),
+        "add" => {
+            let mut total = Value::Null;
+            for v in array(input, name)? {
+                total = binary(BinOp::Add, &total, v)?;
+            }
+            one(total)
+        }
+        "min" | "max" => {
+            let items = array(input, name)?;
+            let pick = if name == "min" {
+                items.iter().min_by(|a, b| compare(a, b))
+            } else {
+                items.iter().max_by(|a, b| compare(a, b))
+            };
+            one(pick.cloned().unwrap_or(Value::Null))
+        }
+        "reverse" => match input {
+            Value::Null => one(Value::Array(Vec::new())),
+            Value::String(s) => one(Value::String(s.chars().rev().collect())),
+            _ => one(Value::Array(
+                array(input, name)?.iter().rev().cloned().collect(),
+            )),
+        },
+        "first" => one(index(input, &Value::from(0))?),
+        "last" => one(index(input, &Value::from(-1))?),
+        "not" => one(Value::Bool(!truthy(input))),
+        "type" => one(Value::String(json_type(input).to_string())),
+        "tostring" => one(Value::String(stringify(input))),
+        "tonumber" => match input {
+            Value::Number(_) => one(input.clone()),
+            Value::String(s) => serde_json::from_str::<Value>(s.trim())
+                .ok()
+                .filter(Value::is_number)
+                .map_or_else(|| Err(format!("Cannot parse '{}' as a number", s)), one),
+            _ => Err(format!("Cannot convert {} to a number", json_type(input))),
+        },
+        "has" => {
+            let mut out = Vec::new();
+            for key in eval(&args[0], input)? {
+                let has = match (input, &key) {
+                    (Value::Object(map), Value::String(k)) => map.contains_key(k),
+                    (Value::Array(arr), Value::Number(n)) => n
+                        .as_f64()
+                        .is_some_and(|i| i >= 0.0 && (i as usize) < arr.len()),
+                    _ => {
+                        return Err(format!(
+                            "Cannot check whether {} has a {} key",
+                            json_type(input),
+                            json_type(&key)
+                        ))
+                    }
+                };
+                out.push(Value::Bool(has));
+            }
+            Ok(out)
+        }
+        "join" => {
+            let mut out = Vec::new();
+            for sep in eval(&args[0], input)? {
+                let Value::String(sep) = sep else {
+                    return Err("join separator must be a string".to_string());
+                };
+                let mut parts = Vec::new();
+                for v in array(input, name)? {
+                    parts.push(match v {
+                        Value::Null => String::new(),
+                        Value::Array(_) | Value::Object(_) => {
+                            return Err(format!("Cannot join {}", json_type(v)))
+                        }
+                        other => stringify(other),
+                    });
+                }
+                out.push(Value::String(parts.join(&sep)));
+            }
+            Ok(out)
+        }
+        "split" => {
+            let mut out = Vec::new();
+            for sep in eval(&args[0], input)? {
+                out.push(binary(BinOp::Div, input, &sep)?);
+            }
+            Ok(out)
+        }
+        "flatten" => {
+            let mut out = Vec::new();
+            flatten(array(input, name)?, &mut out);
+            one(Value::Array(out))
+        }
+        "any" => one(Value::Bool(array(input, name)?.iter().any(truthy))),
+        "all" => one(Value::Bool(array(input, name)?.iter().all(truthy))),
+        "ascii_downcase" | "ascii_upcase" => match input {
+            Value::String(s) if name == "ascii_downcase" => {
+                one(Value::String(s.to_ascii_lowercase()))
+            }
+            Value::String(s) => one(Value::String(s.to_ascii_uppercase())),
+            _ => Err(format!(
+                "{} requires a string, got {}",
+                name,
+                json_type(input)
+            )),
+        },
+        _ => Err(format!("Unknown function '{}'", name)),
+    }
+}
+
+/// The single value an expression produces
+fn single(expr: &Expr, input: &Value) -> Result<Value, String> {
+    let mut values = eval(expr, input)?;
+    if values.len() != 1 {
+        return Err("Expected a single value".to_string());
+    }
+    Ok(values.remove(0))
+}
+
+fn array<'a>(value: &'a Value, function: &str) -> Result<&'a Vec<Value>, String> {
+    match value {
+        Value::Array(arr) => Ok(arr),
+        _ => Err(format!(
+            "{} requires an array, got {}",
+            function,
+            json_type(value)
+        )),
+    }
+}
+
+fn index(value: &Value, key: &Value) -> Result<Value, String> {
+    match (value, key) {
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (Value::Object(map), Value::String(k)) => Ok(map.get(k).cloned().unwrap_or(Value::Null)),
+        (Value::Array(arr), Value::Number(n)) => {
+            let i = n.as_f64().unwrap_or_default().floor() as i64;
+            let i = if i < 0 { i + arr.len() as i64 } else { i };
+            Ok(usize::try_from(i)
+                .ok()
+                .and_then(|i| arr.get(i))
+                .cloned()
+                .unwrap_or(Value::Null))
+        }
+        _ => Err(format!(
+            "Cannot index {} with {}",
+            json_type(value),
+            match key {
+                Value::String(k) => format!("\"{}\"", k),
+                other => json_type(other).to_string(),
+            }
+        )),
+    }
+}
+
+fn slice(value: &Value, from: Option<f64>, to: Option<f64>) -> Result<Value, String> {
+    let bounds = |len: usize| {
+        let clamp = |n: f64| {
+            let n = if n < 0.0 { n + len as f64 } else { n };
+            n.clamp(0.0, len as f64) as usize
+        };
+        let start = from.map_or(0, clamp);
+        let end = to.map_or(len, clamp).max(start);
+        (start, end)
+    };
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Array(arr) => {
+            let (start, end) = bounds(arr.len());
+            Ok(Value::Array(arr[start..end].to_vec()))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (start, end) = bounds(chars.len());
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }
+        _ => Err(format!("Cannot slice {}", json_type(value))),
+    }
+}
+
+fn iterate(value: &Value) -> EvalResult {
+    match value {
+        Value::Array(arr) => Ok(arr.clone()),
+        Value::Object(map) => Ok(map.values().cloned().collect()),
+        _ => Err(format!("Cannot iterate over {}", json_type(value))),
+    }
+}
+
+fn descendants(value: &Value, out: &mut Vec<Value>) {
+    out.push(value.clone());
+    match value {
+        Value::Array(arr) => arr.iter().for_each(|v| descendants(v, out)),
+        Value::Object(map) => map.values().for_each(|v| descendants(v, out)),
+        _ => {}
+    }
+}
+
+fn flatten(items: &[Value], out: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(inner) => flatten(inner, out),
+            other => out.push(other.clone()),
+        }
+    }
+}
+
+fn to_entries(value: &Value) -> EvalResult {
+    match value {
+        Value::Object(map) => Ok(map
+            .iter()
+            .map(|(k, v)| serde_json::json!({"key": k, "value": v}))
+            .collect()),
+        _ => Err(format!("Cannot convert {} to entries", json_type(value))),
+    }
+}
+
+fn from_entries(entries: &[Value]) -> Result<Value, String> {
+    let mut map = Map::new();
+    for entry in entries {
+        let field = |names: &[&str]| names.iter().find_map(|n| entry.get(*n)).cloned();
+        let key = match field(&["key", "k", "name", "Key", "Name"]) {
+            Some(Value::String(k)) => k,
+            Some(Value::Number(n)) => n.to_string(),
+            Some(Value::Bool(b)) => b.to_string(),
+            _ => return Err("Entry has no string key".to_string()),
+        };
+        let value = field(&["value", "v", "Value"]).unwrap_or(Value::Null);
+        map.insert(key, value);
+    }
+    Ok(Value::Object(map))
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn number(n: f64) -> Option<Value> {
+    if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+        Some(Value::from(n as i64))
+    } else {
+        Number::from_f64(n).map(Value::Number)
+    }
+}
+
+fn negate(value: &Value) -> Option<Value> {
+    value.as_i64().and_then(i64::checked_neg).map(Value::from)
+}
+
+fn binary(op: BinOp, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let ordering = || compare(lhs, rhs);
+    match op {
+        BinOp::Eq => return Ok(Value::Bool(ordering() == Ordering::Equal)),
+        BinOp::Ne => return Ok(Value::Bool(ordering() != Ordering::Equal)),
+        BinOp::Lt => return Ok(Value::Bool(ordering() == Ordering::Less)),
+        BinOp::Le => return Ok(Value::Bool(ordering() != Ordering::Greater)),
+        BinOp::Gt => return Ok(Value::Bool(ordering() == Ordering::Greater)),
+        BinOp::Ge => return Ok(Value::Bool(ordering() != Ordering::Less)),
+        _ => {}
+    }
+    let fail = || {
+        Err(format!(
+            "Cannot apply {:?} to {} and {}",
+            op,
+            json_type(lhs),
+            json_type(rhs)
+        ))
+    };
+    match (op, lhs, rhs) {
+        (BinOp::Add, Value::Null, other) | (BinOp::Add, other, Value::Null) => Ok(other.clone()),
+        (_, Value::Number(a), Value::Number(b)) => {
+            if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+                let exact = match op {
+                    BinOp::Add => a.checked_add(b),
+                    BinOp::Sub => a.checked_sub(b),
+                    BinOp::Mul => a.checked_mul(b),
+                    BinOp::Mod if b != 0 => a.checked_rem(b),
+                    _ => None,
+                };
+                if let Some(n) = exact {
+                    return Ok(Value::from(n));
+                }
+            }
+            let (a, b) = (
+                a.as_f64().unwrap_or_default(),
+                b.as_f64().unwrap_or_default(),
+            );
+            let result = match op {
+                BinOp::Add => a + b,
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                BinOp::Div if b == 0.0 => return Err("Division by zero".to_string()),
+                BinOp::Div => a / b,
+                BinOp::Mod if b.trunc() == 0.0 => return Err("Modulo by zero".to_string()),
+                _ => (a.trunc() % b.trunc()).trunc(),
+            };
+            number(result).ok_or_else(|| "Number out of range".to_string())
+        }
+        (BinOp::Add, Value::String(a), Value::String(b)) => {
+            Ok(Value::String(format!("{}{}", a, b)))
+        }
+        (BinOp::Add, Value::Array(a), Value::Array(b)) => {
+            Ok(Value::Array(a.iter().chain(b).cloned().collect()))
+        }
+        (BinOp::Add, Value::Object(a), Value::Object(b)) => {
+            let mut merged = a.clone();
+            merged.extend(b.iter().map(|(k, v)| (k.clone(), v.clone())));
+            Ok(Value::Object(merged))
+        }
+        (BinOp::Sub, Value::Array(a), Value::Array(b)) => Ok(Value::Array(
+            a.iter()
+                .filter(|x| !b.iter().any(|y| compare(x, y) == Ordering::Equal))
+                .cloned()
+                .collect(),
+        )),
+        (BinOp::Div, Value::String(a), Value::String(b)) => Ok(Value::Array(if a.is_empty() {
+            Vec::new()
+        } else {
+            a.split(b.as_str())
+                .map(|s| Value::String(s.to_string()))
+                .collect()
+        })),
+        _ => fail(),
+    }
+}
+
+/// Total order over JSON values: null < false < true < numbers < strings < arrays < objects
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => x
+                .as_f64()
+                .partial_cmp(&y.as_f64())
+                .unwrap_or(Ordering::Equal),
+        },
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut xk: Vec<&String> = x.keys().collect();
+            let mut yk: Vec<&String> = y.keys().collect();
+            xk.sort();
+            yk.sort();
+            xk.cmp(&yk).then_with(|| {
+                xk.iter()
+                    .map(|k| compare(&x[*k], &y[*k]))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(program: &str, input: Value) -> Vec<Value> {
+        Transform::parse(program).unwrap().apply(&input).unwrap()
+    }
+
+    #[test]
+    fn test_paths_and_iteration() {
+        let input = json!({"a": {"b": [1, 2, 3]}, "k-1": true});
+        assert_eq!(run(".", input.clone()), vec![input.clone()]);
+        assert_eq!(run(".a.b[1]", input.clone()), vec![json!(2)]);
+        assert_eq!(run(".a.b[-1]", input.clone()), vec![json!(3)]);
+        assert_eq!(run(".a.b[1:]", input.clone()), vec![json!([2, 3])]);
+        assert_eq!(
+            run(".a.b[]", input.clone()),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(run(".[\"k-1\"]", input.clone()), vec![json!(true)]);
+        assert_eq!(run(".\"k-1\"", input.clone()), vec![json!(true)]);
+        assert_eq!(run(".missing.deeper", input.clone()), vec![Value::Null]);
+        assert_eq!(
+            run("[.. | select(type == \"number\")]", input),
+            vec![json!([1, 2, 3])]
+        );
+    }
+
+    #[test]
+    fn test_map_select_and_construction() {
+        let input = json!([
+            {"name": "ann", "age": 31, "team": "a"},
+            {"name": "bob", "age": 25, "team": "b"},
+            {"name": "cy", "age": 40, "team": "a"}
+        ]);
+        assert_eq!(
+            run(
+                "map(select(.age > 30) | {name, senior: true})",
+                input.clone()
+            ),
+            vec![json!([{"name": "ann", "senior": true}, {"name": "cy", "senior": true}])]
+        );
+        assert_eq!(
+            run(".[] | \"\\(.name) is \\(.age)\"", input.clone())[1],
+            json!("bob is 25")
+        );
+        assert_eq!(
+            run("map({(.name): .age}) | add", input),
+            vec![json!({"ann": 31, "bob": 25, "cy": 40})]
+        );
+    }
+
+    #[test]
+    fn test_sorting_and_grouping() {
+        let input = json!([{"t": "b", "v": 2}, {"t": "a", "v": 3}, {"t": "b", "v": 1}]);
+        assert_eq!(
+            run("sort_by(.v) | map(.v)", input.clone()),
+            vec![json!([1, 2, 3])]
+        );
+        assert_eq!(
+            run(
+                "group_by(.t) | map({t: .[0].t, total: map(.v) | add})",
+                input.clone()
+            ),
+            vec![json!([{"t": "a", "total": 3}, {"t": "b", "total": 3}])]
+        );
+        assert_eq!(run("unique_by(.t) | length", input), vec![json!(2)]);
+        assert_eq!(
+            run("sort", json!([3, "a", null, true, [1], 1.5])),
+            vec![json!([null, true, 1.5, 3, "a", [1]])]
+        );
+        assert_eq!(run("unique", json!([2, 1, 2, 1.0])), vec![json!([1, 2])]);
+    }
+
+    #[test]
+    fn test_entries_and_keys() {
+        let input = json!({"b": 1, "a": 2});
+        assert_eq!(run("keys", input.clone()), vec![json!(["a", "b"])]);
+        // Entries follow member order, which is source order with `preserve-source`
+        #[cfg(not(feature = "preserve-source"))]
+        let entry_keys = json!(["a", "b"]);
+        #[cfg(feature = "preserve-source")]
+        let entry_keys = json!(["b", "a"]);
+        assert_eq!(
+            run("to_entries | map(.key)", input.clone()),
+            vec![entry_keys]
+        );
+        assert_eq!(
+            run(
+                "with_entries({key: (.key | ascii_upcase), value: (.value + 10)})",
+                input
+            ),
+            vec![json!({"A": 12, "B": 11})]
+        );
+        assert_eq!(
+            run(
+                "from_entries",
+                json!([{"name": "x", "value": 1}, {"k": "y"}])
+            ),
+            vec![json!({"x": 1, "y": null})]
+        );
+    }
+
+    #[test]
+    fn test_operators_and_conditionals() {
+        assert_eq!(run("1 + 2 * 3", json!(null)), vec![json!(7)]);
+        assert_eq!(run("(1 + 2) * 3 / 2", json!(null)), vec![json!(4.5)]);
+        assert_eq!(
+            run("10 % 3, -.x", json!({"x": 2})),
+            vec![json!(1), json!(-2)]
+        );
+        assert_eq!(run(".a // \"default\"", json!({})), vec![json!("default")]);
+        assert_eq!(run("\"a,b\" / \",\"", json!(null)), vec![json!(["a", "b"])]);
+        assert_eq!(
+            run(
+                "if . > 2 then \"big\" elif . > 1 then \"mid\" else \"small\" end",
+                json!(2)
+            ),
+            vec![json!("mid")]
+        );
+        assert_eq!(
+            run(".[] | . == 1 and true", json!([1, 2])),
+            vec![json!(true), json!(false)]
+        );
+        assert_eq!(
+            run("[.[] | tostring] | join(\"-\")", json!([1, "a"])),
+            vec![json!("1-a")]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Transform::parse("map(.a").unwrap_err();
+        assert_eq!(err.message, "Expected ')'");
+        let err = Transform::parse(".a | frobnicate").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown function 'frobnicate' at position 5"
+        );
+        assert!(Transform::parse("map").is_err());
+        assert!(Transform::parse(".a ]").is_err());
+        assert!(Transform::parse(&"[".repeat(100)).is_err());
+        assert_eq!(
+            Transform::parse("").unwrap().apply(&json!(1)).unwrap(),
+            vec![json!(1)]
+        );
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let transform = Transform::parse(".a").unwrap();
+        let err = transform.apply(&json!([1])).unwrap_err();
+        assert!(err.to_string().contains("Cannot index array with \"a\""));
+        assert_eq!(run(".a?", json!([1])), Vec::<Value>::new());
+        assert!(Transform::parse("1 / 0")
+            .unwrap()
+            .apply(&json!(null))
+            .is_err());
+    }
+}
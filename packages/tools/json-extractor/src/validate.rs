@@ -0,0 +1,893 @@
+//! JSON Schema validation (drafts 7 and 2020-12)
+//!
+//! Every violation is reported with a JSON Pointer to the offending value and
+//! to the schema keyword that rejected it. Only references within the schema
+//! document are resolved (`#`, `#/json/pointer` and anchors). `format` is an
+//! annotation only, and `unevaluatedProperties`, `unevaluatedItems` and
+//! `$dynamicRef` are not supported.
+
+use crate::detect::json_type;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use shard_den_core::{Result, ShardDenError};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Maximum `$ref` hops without descending into the instance, guarding against cycles
+const MAX_REF_CHAIN: usize = 32;
+
+/// JSON Schema dialect
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Draft {
+    Draft7,
+    #[default]
+    Draft202012,
+}
+
+impl Draft {
+    /// Dialect declared by a schema's `$schema`, if it is a supported one
+    pub fn from_schema(schema: &Value) -> Option<Self> {
+        let uri = schema.get("$schema")?.as_str()?;
+        if uri.contains("draft-07") {
+            Some(Draft::Draft7)
+        } else if uri.contains("2020-12") {
+            Some(Draft::Draft202012)
+        } else {
+            None
+        }
+    }
+
+    /// Parse a dialect name (`7`, `draft7`, `2020-12`, ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "7" | "draft7" | "draft-07" => Some(Draft::Draft7),
+            "2020-12" | "draft2020-12" | "draft-2020-12" => Some(Draft::Draft202012),
+            _ => None,
+        }
+    }
+}
+
+/// A single schema violation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationError {
+    /// JSON Pointer to the rejected value (empty for the document root)
+    pub instance_path: String,
+    /// JSON Pointer to the schema keyword that rejected it
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.instance_path.is_empty() {
+            "(root)"
+        } else {
+            &self.instance_path
+        };
+        write!(f, "{}: {} [{}]", at, self.message, self.schema_path)
+    }
+}
+
+/// Validates documents against a JSON Schema
+#[derive(Debug)]
+pub struct Validator {
+    schema: Value,
+    draft: Draft,
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+impl Validator {
+    /// Create a validator; the dialect is taken from `$schema`, defaulting to 2020-12
+    pub fn new(schema: Value) -> Result<Self> {
+        if !schema.is_object() && !schema.is_boolean() {
+            return Err(ShardDenError::invalid_input(
+                "JSON Schema must be an object or a boolean",
+            ));
+        }
+        let draft = Draft::from_schema(&schema).unwrap_or_default();
+        Ok(Self {
+            schema,
+            draft,
+            regexes: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Override the dialect declared by the schema
+    pub fn with_draft(mut self, draft: Draft) -> Self {
+        self.draft = draft;
+        self
+    }
+
+    /// Dialect used for validation
+    pub fn draft(&self) -> Draft {
+        self.draft
+    }
+
+    /// Every violation of the schema by `instance`
+    ///
+    /// Errors are returned for problems with the schema itself, such as an
+    /// unresolvable `$ref` or an invalid `pattern`.
+    pub fn validate(&self, instance: &Value) -> Result<Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.check(&self.schema, instance, "", "", 0, &mut errors)?;
+        Ok(errors)
+    }
+
+    fn is_valid(&self, schema: &Value, instance: &Value, spath: &str, refs: usize) -> Result<bool> {
+        let mut errors = Vec::new();
+        self.check(schema, instance, "", spath, refs, &mut errors)?;
+        Ok(errors.is_empty())
+    }
+
+    fn check(
+        &self, schema: &Value, instance: &Value, ipath: &str, spath: &str, refs: usize,
+        errors: &mut Vec<ValidationError>,
+    ) -> Result<()> {
+        if refs > MAX_REF_CHAIN {
+            return Err(ShardDenError::invalid_input("Circular $ref in schema"));
+        }
+        let schema = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => {
+                errors.push(ValidationError {
+                    instance_path: ipath.to_string(),
+                    schema_path: spath.to_string(),
+                    message: "No value is allowed here".to_string(),
+                });
+                return Ok(());
+            }
+            Value::Object(schema) => schema,
+            _ => {
+                return Err(ShardDenError::invalid_input(format!(
+                    "Invalid schema at '{}': expected an object or a boolean",
+                    spath
+                )))
+            }
+        };
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = self.resolve(reference)?;
+            let mut nested = Vec::new();
+            self.check(
+                target,
+                instance,
+                ipath,
+                &pointer(spath, "$ref"),
+                refs + 1,
+                &mut nested,
+            )?;
+            errors.extend(nested);
+            // In draft 7 keywords next to `$ref` are ignored
+            if self.draft == Draft::Draft7 {
+                return Ok(());
+            }
+        }
+        self.assertions(schema, instance, ipath, spath, errors)?;
+        self.applicators(schema, instance, ipath, spath, refs, errors)
+    }
+
+    /// Keywords constraining the value itself
+    fn assertions(
+        &self, schema: &Map<String, Value>, instance: &Value, ipath: &str, spath: &str,
+        errors: &mut Vec<ValidationError>,
+    ) -> Result<()> {
+        let mut fail = |keyword: &str, message: String| {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: pointer(spath, keyword),
+                message,
+            })
+        };
+
+        if let Some(expected) = schema.get("type") {
+            let names: Vec<&str> = match expected {
+                Value::String(name) => vec![name.as_str()],
+                Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !names.is_empty() && !names.iter().any(|name| has_type(instance, name)) {
+                fail(
+                    "type",
+                    format!(
+                        "Expected {}, found {}",
+                        names.join(" or "),
+                        json_type(instance)
+                    ),
+                );
+            }
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.iter().any(|v| json_equal(v, instance)) {
+                fail(
+                    "enum",
+                    format!("{} is not one of the allowed values", preview(instance)),
+                );
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if !json_equal(expected, instance) {
+                fail("const", format!("Expected {}", preview(expected)));
+            }
+        }
+
+        match instance {
+            Value::Number(n) => {
+                let x = n.as_f64().unwrap_or_default();
+                if let Some(m) = number(schema, "multipleOf").filter(|m| *m > 0.0) {
+                    let q = x / m;
+                    if (q - q.round()).abs() > 1e-9 * q.abs().max(1.0) {
+                        fail("multipleOf", format!("{} is not a multiple of {}", n, m));
+                    }
+                }
+                if let Some(max) = number(schema, "maximum").filter(|max| x > *max) {
+                    fail("maximum", format!("{} is greater than {}", n, max));
+                }
+                if let Some(max) = number(schema, "exclusiveMaximum").filter(|max| x >= *max) {
+                    fail(
+                        "exclusiveMaximum",
+                        format!("{} is not less than {}", n, max),
+                    );
+                }
+                if let Some(min) = number(schema, "minimum").filter(|min| x < *min) {
+                    fail("minimum", format!("{} is less than {}", n, min));
+                }
+                if let Some(min) = number(schema, "exclusiveMinimum").filter(|min| x <= *min) {
+                    fail(
+                        "exclusiveMinimum",
+                        format!("{} is not greater than {}", n, min),
+                    );
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count();
+                if let Some(max) = count(schema, "maxLength").filter(|max| len > *max) {
+                    fail(
+                        "maxLength",
+                        format!("String is longer than {} characters", max),
+                    );
+                }
+                if let Some(min) = count(schema, "minLength").filter(|min| len < *min) {
+                    fail(
+                        "minLength",
+                        format!("String is shorter than {} characters", min),
+                    );
+                }
+                if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                    if !self.matches(pattern, s)? {
+                        fail(
+                            "pattern",
+                            format!("String does not match pattern '{}'", pattern),
+                        );
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(max) = count(schema, "maxItems").filter(|max| items.len() > *max) {
+                    fail("maxItems", format!("Array has more than {} items", max));
+                }
+                if let Some(min) = count(schema, "minItems").filter(|min| items.len() < *min) {
+                    fail("minItems", format!("Array has fewer than {} items", min));
+                }
+                if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+                    let duplicate = (0..items.len())
+                        .find(|&i| items[..i].iter().any(|prev| json_equal(prev, &items[i])));
+                    if let Some(i) = duplicate {
+                        fail("uniqueItems", format!("Item {} is a duplicate", i));
+                    }
+                }
+            }
+            Value::Object(members) => {
+                if let Some(Value::Array(required)) = schema.get("required") {
+                    for name in required.iter().filter_map(Value::as_str) {
+                        if !members.contains_key(name) {
+                            fail("required", format!("Missing required property '{}'", name));
+                        }
+                    }
+                }
+                if let Some(max) = count(schema, "maxProperties").filter(|max| members.len() > *max)
+                {
+                    fail(
+                        "maxProperties",
+                        format!("Object has more than {} properties", max),
+                    );
+                }
+                if let Some(min) = count(schema, "minProperties").filter(|min| members.len() < *min)
+                {
+                    fail(
+                        "minProperties",
+                        format!("Object has fewer than {} properties", min),
+                    );
+                }
+                let dependent_required = match self.draft {
+                    Draft::Draft7 => "dependencies",
+                    Draft::Draft202012 => "dependentRequired",
+                };
+                if let Some(Value::Object(dependencies)) = schema.get(dependent_required) {
+                    for (name, needed) in dependencies {
+                        let Value::Array(needed) = needed else {
+                            continue;
+                        };
+                        if !members.contains_key(name) {
+                            continue;
+                        }
+                        for other in needed.iter().filter_map(Value::as_str) {
+                            if !members.contains_key(other) {
+                                fail(
+                                    dependent_required,
+                                    format!("Property '{}' requires property '{}'", name, other),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Keywords applying subschemas to the value or its children
+    fn applicators(
+        &self, schema: &Map<String, Value>, instance: &Value, ipath: &str, spath: &str,
+        refs: usize, errors: &mut Vec<ValidationError>,
+    ) -> Result<()> {
+        let mut sub = |keyword: &str, subschema: &Value, value: &Value, ipath: &str| {
+            // `keyword` may span several already escaped tokens
+            let keyword_path = format!("{}/{}", spath, keyword);
+            let refs = if std::ptr::eq(value, instance) {
+                refs
+            } else {
+                0
+            };
+            self.check(subschema, value, ipath, &keyword_path, refs, errors)
+        };
+
+        match instance {
+            Value::Array(items) => {
+                let (prefix, rest, rest_keyword) = match self.draft {
+                    Draft::Draft7 => match schema.get("items") {
+                        Some(Value::Array(tuple)) => (
+                            Some(tuple),
+                            schema.get("additionalItems"),
+                            "additionalItems",
+                        ),
+                        other => (None, other, "items"),
+                    },
+                    Draft::Draft202012 => match schema.get("prefixItems") {
+                        Some(Value::Array(tuple)) => (Some(tuple), schema.get("items"), "items"),
+                        _ => (None, schema.get("items"), "items"),
+                    },
+                };
+                let prefix_keyword = match self.draft {
+                    Draft::Draft7 => "items",
+                    Draft::Draft202012 => "prefixItems",
+                };
+                let prefix_len = prefix.map_or(0, Vec::len);
+                for (i, item) in items.iter().enumerate() {
+                    let item_path = pointer(ipath, &i.to_string());
+                    match prefix.and_then(|p| p.get(i)) {
+                        Some(subschema) => sub(
+                            &format!("{}/{}", prefix_keyword, i),
+                            subschema,
+                            item,
+                            &item_path,
+                        )?,
+                        None if i >= prefix_len => {
+                            if let Some(subschema) = rest {
+                                sub(rest_keyword, subschema, item, &item_path)?;
+                            }
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Value::Object(members) => {
+                let properties = schema.get("properties").and_then(Value::as_object);
+                let patterns = schema.get("patternProperties").and_then(Value::as_object);
+                for (name, value) in members {
+                    let member_path = pointer(ipath, name);
+                    let mut evaluated = false;
+                    if let Some(subschema) = properties.and_then(|p| p.get(name)) {
+                        evaluated = true;
+                        sub(&pointer("properties", name), subschema, value, &member_path)?;
+                    }
+                    for (pattern, subschema) in patterns.into_iter().flatten() {
+                        if self.matches(pattern, name)? {
+                            evaluated = true;
+                            sub(
+                                &pointer("patternProperties", pattern),
+                                subschema,
+                                value,
+                                &member_path,
+                            )?;
+                        }
+                    }
+                    if !evaluated {
+                        if let Some(subschema) = schema.get("additionalProperties") {
+                            sub("additionalProperties", subschema, value, &member_path)?;
+                        }
+                    }
+                    if let Some(subschema) = schema.get("propertyNames") {
+                        sub(
+                            "propertyNames",
+                            subschema,
+                            &Value::String(name.clone()),
+                            &member_path,
+                        )?;
+                    }
+                }
+                let dependent_schemas = match self.draft {
+                    Draft::Draft7 => "dependencies",
+                    Draft::Draft202012 => "dependentSchemas",
+                };
+                if let Some(Value::Object(dependencies)) = schema.get(dependent_schemas) {
+                    for (name, subschema) in dependencies {
+                        if members.contains_key(name) && !subschema.is_array() {
+                            sub(
+                                &pointer(dependent_schemas, name),
+                                subschema,
+                                instance,
+                                ipath,
+                            )?;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for (i, subschema) in all.iter().enumerate() {
+                sub(&format!("allOf/{}", i), subschema, instance, ipath)?;
+            }
+        }
+
+        let mut fail = |keyword: &str, message: String| {
+            errors.push(ValidationError {
+                instance_path: ipath.to_string(),
+                schema_path: pointer(spath, keyword),
+                message,
+            })
+        };
+
+        if let (Value::Array(items), Some(contains)) = (instance, schema.get("contains")) {
+            let keyword_path = pointer(spath, "contains");
+            let mut matched = 0;
+            for item in items {
+                if self.is_valid(contains, item, &keyword_path, 0)? {
+                    matched += 1;
+                }
+            }
+            let (min, max) = match self.draft {
+                Draft::Draft7 => (1, None),
+                Draft::Draft202012 => (
+                    count(schema, "minContains").unwrap_or(1),
+                    count(schema, "maxContains"),
+                ),
+            };
+            if matched < min {
+                fail(
+                    "contains",
+                    format!(
+                        "Array has {} matching item(s), expected at least {}",
+                        matched, min
+                    ),
+                );
+            }
+            if let Some(max) = max.filter(|max| matched > *max) {
+                fail(
+                    "maxContains",
+                    format!(
+                        "Array has {} matching item(s), expected at most {}",
+                        matched, max
+                    ),
+                );
+            }
+        }
+        if let Some(Value::Array(any)) = schema.get("anyOf") {
+            let mut valid = false;
+            for (i, subschema) in any.iter().enumerate() {
+                let path = pointer(spath, &format!("anyOf/{}", i));
+                if self.is_valid(subschema, instance, &path, refs)? {
+                    valid = true;
+                    break;
+                }
+            }
+            if !valid {
+                fail(
+                    "anyOf",
+                    "Value does not match any schema in anyOf".to_string(),
+                );
+            }
+        }
+        if let Some(Value::Array(one)) = schema.get("oneOf") {
+            let mut matched = 0;
+            for (i, subschema) in one.iter().enumerate() {
+                let path = pointer(spath, &format!("oneOf/{}", i));
+                if self.is_valid(subschema, instance, &path, refs)? {
+                    matched += 1;
+                }
+            }
+            if matched != 1 {
+                fail(
+                    "oneOf",
+                    format!(
+                        "Value matches {} schemas in oneOf, expected exactly one",
+                        matched
+                    ),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.is_valid(not, instance, &pointer(spath, "not"), refs)? {
+                fail("not", "Value must not match the schema in not".to_string());
+            }
+        }
+
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.is_valid(condition, instance, &pointer(spath, "if"), refs)? {
+                "then"
+            } else {
+                "else"
+            };
+            if let Some(subschema) = schema.get(branch) {
+                self.check(
+                    subschema,
+                    instance,
+                    ipath,
+                    &pointer(spath, branch),
+                    refs,
+                    errors,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `text` matches `pattern` (unanchored, as JSON Schema requires)
+    fn matches(&self, pattern: &str, text: &str) -> Result<bool> {
+        if let Some(regex) = self.regexes.borrow().get(pattern) {
+            return Ok(regex.is_match(text));
+        }
+        let regex = Regex::new(pattern).map_err(|e| {
+            ShardDenError::invalid_input(format!("Invalid pattern '{}' in schema: {}", pattern, e))
+        })?;
+        let matched = regex.is_match(text);
+        self.regexes.borrow_mut().insert(pattern.to_string(), regex);
+        Ok(matched)
+    }
+
+    /// Find the subschema a `$ref` points to
+    fn resolve(&self, reference: &str) -> Result<&Value> {
+        let root_id = self
+            .schema
+            .get("$id")
+            .and_then(Value::as_str)
+            .map(|id| id.trim_end_matches('#'));
+        let (base, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        if !base.is_empty() && Some(base) != root_id {
+            return Err(ShardDenError::invalid_input(format!(
+                "Unsupported $ref '{}': only references within the schema are supported",
+                reference
+            )));
+        }
+
+        let target = if fragment.is_empty() {
+            Some(&self.schema)
+        } else if fragment.starts_with('/') {
+            self.schema.pointer(&percent_decode(fragment))
+        } else {
+            find_anchor(&self.schema, fragment, self.draft)
+        };
+        target.ok_or_else(|| {
+            ShardDenError::invalid_input(format!("Cannot resolve $ref '{}'", reference))
+        })
+    }
+}
+
+/// Append a reference token to a JSON Pointer, escaping `~` and `/`
+fn pointer(base: &str, token: &str) -> String {
+    format!("{}/{}", base, token.replace('~', "~0").replace('/', "~1"))
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+/// Equality where numbers compare by value (`1` equals `1.0`)
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => x.as_f64() == y.as_f64(),
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equal(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|other| json_equal(v, other)))
+        }
+        _ => a == b,
+    }
+}
+
+fn number(schema: &Map<String, Value>, keyword: &str) -> Option<f64> {
+    schema.get(keyword).and_then(Value::as_f64)
+}
+
+fn count(schema: &Map<String, Value>, keyword: &str) -> Option<usize> {
+    number(schema, keyword).map(|n| n as usize)
+}
+
+/// Short rendering of a value for error messages
+fn preview(value: &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > 40 {
+        format!("{}...", text.chars().take(37).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// Decode `%XX` escapes in a URI fragment
+fn percent_decode(fragment: &str) -> String {
+    let bytes = fragment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Subschema declaring the plain-name anchor `name`
+fn find_anchor<'a>(schema: &'a Value, name: &str, draft: Draft) -> Option<&'a Value> {
+    match schema {
+        Value::Object(members) => {
+            let declared = match draft {
+                Draft::Draft7 => members
+                    .get("$id")
+                    .and_then(Value::as_str)
+                    .and_then(|id| id.strip_prefix('#')),
+                Draft::Draft202012 => members.get("$anchor").and_then(Value::as_str),
+            };
+            if declared == Some(name) {
+                return Some(schema);
+            }
+            members.values().find_map(|v| find_anchor(v, name, draft))
+        }
+        Value::Array(items) => items.iter().find_map(|v| find_anchor(v, name, draft)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(schema: Value, instance: Value) -> Vec<ValidationError> {
+        Validator::new(schema).unwrap().validate(&instance).unwrap()
+    }
+
+    fn paths(errors: &[ValidationError]) -> Vec<(&str, &str)> {
+        errors
+            .iter()
+            .map(|e| (e.instance_path.as_str(), e.schema_path.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}}
+            }
+        });
+        let errors = validate(schema, json!({"id": 0, "tags": ["ok", 5, "toolong"]}));
+        assert_eq!(
+            paths(&errors),
+            vec![
+                ("", "/required"),
+                ("/id", "/properties/id/minimum"),
+                ("/tags/1", "/properties/tags/items/type"),
+                ("/tags/2", "/properties/tags/items/maxLength"),
+            ]
+        );
+        assert_eq!(errors[0].message, "Missing required property 'name'");
+        assert_eq!(
+            errors[0].to_string(),
+            "(root): Missing required property 'name' [/required]"
+        );
+    }
+
+    #[test]
+    fn test_type_and_value_keywords() {
+        assert!(validate(json!({"type": "integer"}), json!(2.0)).is_empty());
+        assert_eq!(
+            validate(json!({"type": ["string", "null"]}), json!(1)).len(),
+            1
+        );
+        assert!(validate(json!({"enum": [1, "a"]}), json!(1.0)).is_empty());
+        assert_eq!(
+            validate(json!({"const": {"a": [1]}}), json!({"a": [2]})).len(),
+            1
+        );
+        assert_eq!(validate(json!({"multipleOf": 0.1}), json!(0.3)).len(), 0);
+        assert_eq!(validate(json!({"exclusiveMaximum": 3}), json!(3)).len(), 1);
+        assert_eq!(validate(json!({"pattern": "^a+$"}), json!("aab")).len(), 1);
+        assert_eq!(
+            validate(json!({"uniqueItems": true}), json!([1, 2, 1.0])).len(),
+            1
+        );
+        assert_eq!(validate(json!(false), json!(null)).len(), 1);
+    }
+
+    #[test]
+    fn test_object_keywords() {
+        let schema = json!({
+            "properties": {"a": true},
+            "patternProperties": {"^x-": {"type": "string"}},
+            "additionalProperties": false,
+            "propertyNames": {"maxLength": 4},
+            "dependentRequired": {"a": ["b"]}
+        });
+        let errors = validate(schema, json!({"a": 1, "other": 3, "x-1": 2}));
+        assert_eq!(
+            paths(&errors),
+            vec![
+                ("", "/dependentRequired"),
+                ("/other", "/additionalProperties"),
+                ("/other", "/propertyNames/maxLength"),
+                ("/x-1", "/patternProperties/^x-/type"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = json!({"oneOf": [{"type": "integer"}, {"minimum": 0}]});
+        assert_eq!(validate(schema.clone(), json!(-1.5)).len(), 1);
+        assert_eq!(
+            validate(schema, json!(3))[0].message,
+            "Value matches 2 schemas in oneOf, expected exactly one"
+        );
+
+        assert!(validate(
+            json!({"anyOf": [{"type": "string"}, {"type": "null"}]}),
+            json!(null)
+        )
+        .is_empty());
+        assert_eq!(
+            validate(json!({"not": {"type": "null"}}), json!(null)).len(),
+            1
+        );
+
+        let schema = json!({
+            "if": {"properties": {"kind": {"const": "a"}}},
+            "then": {"required": ["a"]},
+            "else": {"required": ["b"]}
+        });
+        assert_eq!(
+            paths(&validate(schema.clone(), json!({"kind": "a"}))),
+            vec![("", "/then/required")]
+        );
+        assert_eq!(
+            paths(&validate(schema, json!({"kind": "z"}))),
+            vec![("", "/else/required")]
+        );
+    }
+
+    #[test]
+    fn test_array_keywords_by_draft() {
+        let schema = json!({"prefixItems": [{"type": "string"}], "items": {"type": "integer"}});
+        let errors = validate(schema, json!(["a", 1, "b"]));
+        assert_eq!(paths(&errors), vec![("/2", "/items/type")]);
+
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "items": [{"type": "string"}],
+            "additionalItems": {"type": "integer"}
+        });
+        let errors = validate(schema, json!([1, 2, "b"]));
+        assert_eq!(
+            paths(&errors),
+            vec![("/0", "/items/0/type"), ("/2", "/additionalItems/type")]
+        );
+
+        let schema = json!({"contains": {"type": "string"}, "minContains": 2, "maxContains": 2});
+        assert_eq!(validate(schema.clone(), json!(["a", 1])).len(), 1);
+        assert_eq!(
+            validate(schema, json!(["a", "b", "c"]))[0].schema_path,
+            "/maxContains"
+        );
+    }
+
+    #[test]
+    fn test_refs() {
+        let schema = json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/node"}}},
+                    "required": ["name"]
+                }
+            },
+            "$ref": "#/$defs/node"
+        });
+        let errors = validate(schema, json!({"name": "a", "children": [{"children": []}]}));
+        assert_eq!(
+            paths(&errors),
+            vec![(
+                "/children/0",
+                "/$ref/properties/children/items/$ref/required"
+            )]
+        );
+
+        let schema = json!({"$defs": {"n": {"$anchor": "num", "type": "number"}}, "items": {"$ref": "#num"}});
+        assert_eq!(validate(schema, json!([1, "x"])).len(), 1);
+
+        // Draft 7 ignores keywords next to $ref
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "definitions": {"any": {}},
+            "$ref": "#/definitions/any",
+            "type": "string"
+        });
+        assert!(validate(schema, json!(1)).is_empty());
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        assert!(Validator::new(json!(1)).is_err());
+        let validator = Validator::new(json!({"$ref": "https://example.com/s.json"})).unwrap();
+        assert!(validator.validate(&json!(1)).is_err());
+        let validator = Validator::new(json!({"$ref": "#"})).unwrap();
+        assert!(validator.validate(&json!(1)).is_err());
+        let validator = Validator::new(json!({"pattern": "("})).unwrap();
+        assert!(validator.validate(&json!("a")).is_err());
+    }
+
+    #[test]
+    fn test_draft_detection() {
+        let schema = json!({"$schema": "http://json-schema.org/draft-07/schema#"});
+        assert_eq!(Validator::new(schema).unwrap().draft(), Draft::Draft7);
+        assert_eq!(
+            Validator::new(json!({})).unwrap().draft(),
+            Draft::Draft202012
+        );
+        assert_eq!(Draft::from_name("7"), Some(Draft::Draft7));
+        assert_eq!(Draft::from_name("2020-12"), Some(Draft::Draft202012));
+        assert_eq!(Draft::from_name("4"), None);
+    }
+}
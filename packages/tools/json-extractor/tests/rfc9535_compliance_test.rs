@@ -0,0 +1,116 @@
+//! RFC 9535 conformance tests
+//!
+//! Runs every fixture in `tests/fixtures/rfc9535/` against the strict parser,
+//! plus the suite named by the `RFC9535_CTS` environment variable, if set.
+//! Fixtures use the jsonpath-compliance-test-suite format. The bundled
+//! `cts-subset.json` is a hand-picked subset, not the upstream suite; see the
+//! README in that directory for how to vendor and run the full `cts.json`.
+
+use serde_json::Value;
+use shard_den_json_extractor::{PathMode, PathParser};
+use std::path::PathBuf;
+
+/// Cases known to fail, by name, with the reason they are skipped
+///
+/// Every entry must name a case in the suites run, so fixed or renamed cases
+/// do not linger here.
+const SKIPPED: &[(&str, &str)] = &[];
+
+fn fixture_files() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rfc9535");
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .expect("fixture directory should exist")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    if let Some(full) = std::env::var_os("RFC9535_CTS") {
+        files.push(PathBuf::from(full));
+    }
+    files
+}
+
+/// Run a single case, returning a description of the failure if any
+fn run_case(parser: &PathParser, case: &Value) -> Option<String> {
+    let selector = case["selector"].as_str()?;
+    let invalid = case["invalid_selector"].as_bool().unwrap_or(false);
+
+    let path = match parser.parse(selector) {
+        Ok(_) if invalid => return Some("expected selector to be rejected".to_string()),
+        Err(_) if invalid => return None,
+        Err(e) => return Some(format!("unexpected parse error: {}", e)),
+        Ok(path) => path,
+    };
+
+    let document = &case["document"];
+    let located = match parser.locate(document, &path) {
+        Ok(located) => located,
+        Err(e) => return Some(format!("unexpected traversal error: {}", e)),
+    };
+    let values = Value::Array(located.iter().map(|(_, v)| (*v).clone()).collect());
+    let paths: Vec<Value> = located
+        .iter()
+        .map(|(p, _)| Value::String(p.to_string()))
+        .collect();
+
+    let expected: Vec<&Value> = match (&case["result"], &case["results"]) {
+        (Value::Array(_), _) => vec![&case["result"]],
+        (_, Value::Array(results)) => results.iter().collect(),
+        _ => return Some("case has neither result nor results".to_string()),
+    };
+    if !expected.contains(&&values) {
+        return Some(format!("got {}, expected one of {:?}", values, expected));
+    }
+
+    if let Value::Array(expected_paths) = &case["result_paths"] {
+        if expected_paths != &paths {
+            return Some(format!(
+                "got paths {:?}, expected {:?}",
+                paths, expected_paths
+            ));
+        }
+    }
+    None
+}
+
+#[test]
+fn test_rfc9535_compliance() {
+    let parser = PathParser::with_mode(PathMode::Rfc9535);
+    let mut total = 0;
+    let mut skipped = Vec::new();
+    let mut failures = Vec::new();
+
+    for file in fixture_files() {
+        let text = std::fs::read_to_string(&file).expect("fixture should be readable");
+        let suite: Value = serde_json::from_str(&text).expect("fixture should be valid JSON");
+        for case in suite["tests"]
+            .as_array()
+            .expect("fixture should have tests")
+        {
+            total += 1;
+            let name = case["name"].as_str().unwrap_or("<unnamed>");
+            if SKIPPED.iter().any(|(skip, _)| *skip == name) {
+                skipped.push(name.to_string());
+                continue;
+            }
+            if let Some(failure) = run_case(&parser, case) {
+                failures.push(format!("{} ({}): {}", name, case["selector"], failure));
+            }
+        }
+    }
+
+    assert!(total > 0, "no conformance cases found");
+    let stale: Vec<&str> = SKIPPED
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !skipped.iter().any(|s| s == name))
+        .collect();
+    assert!(stale.is_empty(), "skipped cases not found: {:?}", stale);
+    assert!(
+        failures.is_empty(),
+        "{} of {} conformance cases failed:\n{}",
+        failures.len(),
+        total,
+        failures.join("\n")
+    );
+}
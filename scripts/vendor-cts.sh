@@ -0,0 +1,40 @@
+#!/bin/bash
+# Vendor the upstream JSONPath compliance test suite for the RFC 9535 tests
+# Usage: ./scripts/vendor-cts.sh <upstream-commit-hash>
+
+set -e
+
+# Colors
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+NC='\033[0m' # No Color
+
+REPO="jsonpath-standard/jsonpath-compliance-test-suite"
+FIXTURES="packages/tools/json-extractor/tests/fixtures/rfc9535"
+COMMIT="$1"
+
+if [[ ! "$COMMIT" =~ ^[0-9a-f]{40}$ ]]; then
+    echo -e "${RED}❌ Pass the full 40-character upstream commit hash${NC}"
+    echo "Usage: $0 <upstream-commit-hash>"
+    exit 1
+fi
+
+cd "$(dirname "$0")/.."
+
+echo "📥 Downloading cts.json at $COMMIT..."
+curl -fsSL "https://raw.githubusercontent.com/$REPO/$COMMIT/cts.json" -o "$FIXTURES/cts.json"
+
+# The full suite supersedes the hand-picked subset
+rm -f "$FIXTURES/cts-subset.json"
+
+# Record the commit in the fixture README
+sed -i.bak "/^## Vendored upstream commit$/,/^\[cts\]/{
+/^## Vendored upstream commit$/!{/^\[cts\]/!d;}
+}" "$FIXTURES/README.md"
+sed -i.bak "s|^## Vendored upstream commit$|## Vendored upstream commit\n\n[\`$COMMIT\`](https://github.com/$REPO/tree/$COMMIT)\n|" "$FIXTURES/README.md"
+rm -f "$FIXTURES/README.md.bak"
+
+echo -e "${GREEN}✅ Vendored cts.json at $COMMIT${NC}"
+echo ""
+echo "Run the suite and list any failing cases in SKIPPED with a reason:"
+echo "  cargo test -p shard-den-json-extractor --test rfc9535_compliance_test"